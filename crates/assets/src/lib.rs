@@ -5,10 +5,15 @@ use bevy::{
     gltf::{GltfMesh, GltfPlugin},
     prelude::*,
 };
-use protocol::message::Level;
+use protocol::{
+    fingerprint::ContentHasher,
+    message::{AssetHash, Level},
+};
 
 pub mod assets;
 
+const CHARACTER_SCENE_PATH: &str = "scenes/example_character.glb";
+
 pub struct AssetPlugin;
 
 impl Plugin for AssetPlugin {
@@ -19,12 +24,19 @@ impl Plugin for AssetPlugin {
                 check_asset_loading.run_if(in_state(LevelState::Loading)),
             )
             .add_systems(OnEnter(LevelState::Postprocess), postprocess_assets)
+            .add_systems(
+                Update,
+                on_level_scene_modified.run_if(in_state(LevelState::Loaded)),
+            )
+            .add_event::<LevelAssetsReloaded>()
             .init_state::<LevelState>()
             .init_resource::<CurrentLevel>()
             .init_resource::<LoadingAssets>()
             .init_resource::<LevelAssets>()
             .init_resource::<GlobalAssets>()
-            .register_type::<Geometry>();
+            .init_resource::<LevelAssetHashes>()
+            .register_type::<Geometry>()
+            .register_type::<Waypoint>();
     }
 }
 
@@ -46,11 +58,34 @@ pub struct LoadingAssets {
 #[derive(Resource, Clone, Deref, DerefMut, Default)]
 pub struct CurrentLevel(pub Level);
 
+/// The root asset path the app was started with, as given to bevy's `AssetPlugin`
+#[derive(Resource, Clone, Deref)]
+pub struct AssetPath(pub String);
+
+/// Content hashes of the files backing the `CurrentLevel`, refreshed whenever it changes.
+/// The server sends these to clients so they can detect that their assets differ.
+#[derive(Resource, Clone, Deref, Default)]
+pub struct LevelAssetHashes(pub Vec<AssetHash>);
+
+/// Sent after a level scene changed on disk and was reloaded while the level was in use.
+/// The level goes back through `LevelState::Postprocess`, so anything spawned
+/// `OnEnter(LevelState::Loaded)` is rebuilt from the new scene.
+#[derive(Event, Debug, Clone)]
+pub struct LevelAssetsReloaded;
+
 /// Tag component to let external systems identify "what" kind of thing got loaded
 #[derive(Component, Reflect)]
 #[reflect(Component)]
 pub struct Geometry;
 
+/// Tags level nodes named `Waypoint...`, e.g. `Waypoint.001`, as points for NPCs to patrol
+/// in name order
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Waypoint;
+
+pub const WAYPOINT_NAME_PREFIX: &str = "Waypoint";
+
 /// When CurrentLevel changes, load the assets required.
 /// Queue the resultant Handles to be polled for completion in `check_asset_loading`
 fn on_level_change(
@@ -59,22 +94,28 @@ fn on_level_change(
     mut loading_assets: ResMut<LoadingAssets>,
     mut level_assets: ResMut<LevelAssets>,
     mut global_assets: ResMut<GlobalAssets>,
+    mut level_asset_hashes: ResMut<LevelAssetHashes>,
     mut next_level_state: ResMut<NextState<LevelState>>,
+    asset_path: Option<Res<AssetPath>>,
 ) {
     if !current_level.is_changed() {
         return;
     }
 
+    if let Some(asset_path) = asset_path {
+        level_asset_hashes.0 = hash_level_assets(&asset_path, **current_level);
+    }
+
     // TODO: need to drop all handles from the loaded level
     // and despawn everything from the loaded level
 
     global_assets.character =
-        asset_server.load(GltfAssetLabel::Scene(0).from_asset("scenes/example_character.glb"));
+        asset_server.load(GltfAssetLabel::Scene(0).from_asset(CHARACTER_SCENE_PATH));
 
     match **current_level {
         Level::Example => {
             level_assets.example_level = asset_server
-                .load(GltfAssetLabel::Scene(0).from_asset("scenes/example_environment.glb"));
+                .load(GltfAssetLabel::Scene(0).from_asset(level_asset_paths(Level::Example)[0]));
 
             loading_assets
                 .handles
@@ -86,6 +127,119 @@ fn on_level_change(
     next_level_state.set(LevelState::Loading);
 }
 
+/// Watch for the current level's scene being replaced by the asset server, either by bevy's
+/// file watcher or an explicit `AssetServer::reload`, and rerun postprocessing on the new copy
+fn on_level_scene_modified(
+    mut scene_events: EventReader<AssetEvent<Scene>>,
+    mut reloaded_events: EventWriter<LevelAssetsReloaded>,
+    mut level_asset_hashes: ResMut<LevelAssetHashes>,
+    mut next_level_state: ResMut<NextState<LevelState>>,
+    current_level: Res<CurrentLevel>,
+    level_assets: Res<LevelAssets>,
+    scenes: Res<Assets<Scene>>,
+    asset_path: Option<Res<AssetPath>>,
+) {
+    let level_scene = match **current_level {
+        Level::Example => level_assets.example_level.id(),
+        Level::Void => return,
+    };
+
+    for event in scene_events.read() {
+        if !event.is_modified(level_scene) {
+            continue;
+        }
+
+        // `postprocess_assets` modifies the scene too. Only a freshly loaded copy is missing
+        // the Geometry we tagged it with.
+        let Some(scene) = scenes.get(level_scene) else {
+            continue;
+        };
+        if scene
+            .world
+            .iter_entities()
+            .any(|entity| entity.contains::<Geometry>())
+        {
+            continue;
+        }
+
+        info!("level {:?} changed on disk, rebuilding", **current_level);
+
+        if let Some(asset_path) = &asset_path {
+            level_asset_hashes.0 = hash_level_assets(asset_path, **current_level);
+        }
+
+        next_level_state.set(LevelState::Postprocess);
+        reloaded_events.send(LevelAssetsReloaded);
+        return;
+    }
+}
+
+/// Drop everything loaded for the current level and go back to `LevelState::Unloaded`,
+/// so that the next change of `CurrentLevel` loads from scratch, even to the same level
+pub fn reset_level_assets(
+    mut current_level: ResMut<CurrentLevel>,
+    mut loading_assets: ResMut<LoadingAssets>,
+    mut level_assets: ResMut<LevelAssets>,
+    mut global_assets: ResMut<GlobalAssets>,
+    mut level_asset_hashes: ResMut<LevelAssetHashes>,
+    mut next_level_state: ResMut<NextState<LevelState>>,
+) {
+    // Bypass change detection, changing the level is what triggers a load
+    current_level.bypass_change_detection().0 = Level::Void;
+    loading_assets.handles.clear();
+    *level_assets = LevelAssets::default();
+    *global_assets = GlobalAssets::default();
+    *level_asset_hashes = LevelAssetHashes::default();
+    next_level_state.set(LevelState::Unloaded);
+}
+
+/// The files that make up a level, relative to the asset root
+pub fn level_asset_paths(level: Level) -> &'static [&'static str] {
+    match level {
+        Level::Example => &["scenes/example_environment.glb"],
+        Level::Void => &[],
+    }
+}
+
+/// Hash the files for `level`, plus the global assets every level uses, straight from disk.
+/// Files that can't be read are skipped, so they show up as missing on comparison.
+#[cfg(not(target_family = "wasm"))]
+pub fn hash_level_assets(asset_path: &str, level: Level) -> Vec<AssetHash> {
+    use bevy::asset::io::file::FileAssetReader;
+
+    let root = FileAssetReader::get_base_path().join(asset_path);
+
+    level_asset_paths(level)
+        .iter()
+        .chain(std::iter::once(&CHARACTER_SCENE_PATH))
+        .filter_map(|path| match std::fs::read(root.join(path)) {
+            Ok(bytes) => Some(AssetHash {
+                path: path.to_string(),
+                hash: ContentHasher::hash_bytes(&bytes),
+            }),
+            Err(e) => {
+                warn!("unable to hash asset {}: {}", path, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Assets are fetched over http on the web, so there is nothing local to hash
+#[cfg(target_family = "wasm")]
+pub fn hash_level_assets(asset_path: &str, level: Level) -> Vec<AssetHash> {
+    Vec::new()
+}
+
+/// Compare a server's asset hashes against our own, returning the paths that differ
+pub fn mismatched_assets(ours: &[AssetHash], theirs: &[AssetHash]) -> Vec<String> {
+    theirs
+        .iter()
+        .filter(|their_hash| !ours.contains(their_hash))
+        .map(|their_hash| their_hash.path.clone())
+        .collect()
+}
+
 /// Sets the AssetState to Loaded once all queued Handles have finished loading
 /// Downstream systems should consume this state change as part of their loading sequence
 fn check_asset_loading(
@@ -121,12 +275,23 @@ fn postprocess_assets(
             // that contains a World we can mutate freely
             if let Some(scene) = scenes.get_mut(&level_assets.example_level) {
                 let mut entities_to_process = Vec::new();
+                let mut waypoints = Vec::new();
 
                 for entity_ref in scene.world.iter_entities() {
                     let entity = entity_ref.id();
                     if let Some(mesh_handle) = scene.world.get::<Mesh3d>(entity) {
                         entities_to_process.push((entity, mesh_handle.clone()));
                     }
+                    if entity_ref
+                        .get::<Name>()
+                        .is_some_and(|name| name.starts_with(WAYPOINT_NAME_PREFIX))
+                    {
+                        waypoints.push(entity);
+                    }
+                }
+
+                for entity in waypoints {
+                    scene.world.entity_mut(entity).insert(Waypoint);
                 }
 
                 for (entity, mesh_handle) in entities_to_process {
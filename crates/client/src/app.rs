@@ -1,28 +1,34 @@
+use assets::AssetPath;
 use bevy::asset::AssetMetaCheck;
 use bevy::{
     log::{Level, LogPlugin},
     prelude::*,
 };
-use common::CommonPlugin;
+use common::{CommonPlugin, headless::HeadlessPlugin};
 use lightyear::prelude::client::{NetConfig, VisualInterpolationPlugin};
 use lightyear::{
     client::{config::ClientConfig, plugin::ClientPlugins},
     server::config::ServerConfig,
 };
+use protocol::replay::Replay;
 use render::RenderPlugin;
 
 use crate::game_state::{GameLifecyclePlugin, GameState};
 use crate::input::InputPlugin;
 use crate::{
-    interpolation::InterpolationPlugin, network::NetworkPlugin, replication::ReplicationPlugin,
-    ui::UiPlugin,
+    chat::ChatPlugin, conditioner::ConditionerPlugin, discovery::DiscoveryPlugin,
+    input_log::InputLogPlugin, interpolation::InterpolationPlugin, lobby::LobbyPlugin,
+    metrics::MetricsPlugin, network::NetworkPlugin, prediction::PredictionPlugin,
+    profile::ProfilePlugin, replay::ReplayPlugin, replication::ReplicationPlugin,
+    spectator::SpectatorPlugin, ui::UiPlugin,
 };
 
-// TODO: remove this?
-/// The root asset path is preserved here by the client at startup so it can be forwarded
-/// to the client server, should they choose to host.
-#[derive(Resource)]
-pub struct AssetPath(pub String);
+#[derive(Resource, PartialEq, Eq, Clone, Copy)]
+pub enum ClientMode {
+    Windowed,
+    /// No window, rendering, UI or local input, for tests and bots
+    Headless,
+}
 
 #[derive(Resource)]
 pub struct LaunchConfigurations {
@@ -35,35 +41,78 @@ fn build_core_client_app(
     app: &mut App,
     client_remote_config: ClientConfig,
     asset_path: String,
+    hot_reload: bool,
+    mode: ClientMode,
 ) -> &mut App {
+    match mode {
+        ClientMode::Windowed => {
+            app.add_plugins((
+                DefaultPlugins.build().set(AssetPlugin {
+                    file_path: asset_path.clone(),
+                    meta_check: AssetMetaCheck::Never,
+                    watch_for_changes_override: Some(hot_reload),
+                    ..default()
+                }),
+                UiPlugin,
+                RenderPlugin,
+                InputPlugin,
+            ));
+        }
+        ClientMode::Headless => {
+            app.add_plugins((
+                MinimalPlugins,
+                HeadlessPlugin {
+                    asset_path: asset_path.clone(),
+                    hot_reload,
+                },
+            ));
+        }
+    }
+
     app.add_plugins((
-        DefaultPlugins.build().set(AssetPlugin {
-            file_path: asset_path.clone(),
-            meta_check: AssetMetaCheck::Never,
-            ..default()
-        }),
         ClientPlugins {
             config: client_remote_config.clone(),
         },
         CommonPlugin,
         GameLifecyclePlugin,
-        UiPlugin,
         NetworkPlugin,
-        RenderPlugin,
         ReplicationPlugin,
-        InputPlugin,
         InterpolationPlugin,
+        PredictionPlugin,
+        DiscoveryPlugin,
+        LobbyPlugin,
+        MetricsPlugin,
+        ConditionerPlugin,
+        InputLogPlugin,
+        SpectatorPlugin,
+        ChatPlugin,
+        ProfilePlugin,
     ));
 
-    app.insert_resource(AssetPath(asset_path));
+    // The root asset path is preserved here by the client at startup so it can be forwarded
+    // to the client server, should they choose to host.
+    app.insert_resource(AssetPath(asset_path))
+        .insert_resource(mode);
 
     app
 }
 
-pub fn build_client_app(client_config: ClientConfig, asset_path: String) -> App {
+/// `hot_reload` turns on bevy's file watcher for the asset folder
+pub fn build_client_app(
+    client_config: ClientConfig,
+    asset_path: String,
+    hot_reload: bool,
+    mode: ClientMode,
+) -> App {
     let mut app = App::new();
 
-    build_core_client_app(&mut app, client_config.clone(), asset_path);
+    build_core_client_app(
+        &mut app,
+        client_config.clone(),
+        asset_path,
+        hot_reload,
+        mode,
+    );
 
     app.insert_resource(LaunchConfigurations {
         server_config: None,
@@ -73,3 +122,25 @@ pub fn build_client_app(client_config: ClientConfig, asset_path: String) -> App
 
     app
 }
+
+/// A windowed client that plays `replay` back and never connects to a server
+pub fn build_replay_app(replay: Replay, asset_path: String) -> App {
+    let mut app = App::new();
+
+    build_core_client_app(
+        &mut app,
+        ClientConfig::default(),
+        asset_path,
+        false,
+        ClientMode::Windowed,
+    );
+
+    app.insert_resource(LaunchConfigurations {
+        server_config: None,
+        client_local_config: None,
+        client_remote_config: None,
+    })
+    .add_plugins(ReplayPlugin { replay });
+
+    app
+}
@@ -0,0 +1,167 @@
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use common::player::wander_direction;
+use leafwing_input_manager::prelude::{ActionState, InputMap};
+use lightyear::prelude::client::{InputSystemSet, Predicted};
+use protocol::{input::NetworkedInput, metrics::METRICS_INTERVAL};
+
+use crate::{
+    game_state::{GameState, InSession},
+    lobby::SetReadyExt,
+    metrics::NetworkMetrics,
+    prediction::PredictionCounts,
+    replication::LocalPlayer,
+};
+
+/// Plays on its own for load testing: connects on startup, readies up in the lobby
+/// and moves its player in a scripted pattern, keeping `BotStats` as it goes
+pub struct BotPlugin {
+    pub pattern: MovePattern,
+    /// Offsets the pattern, so bots with the same one don't move in lockstep
+    pub phase: f32,
+}
+
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(BotMovement {
+            pattern: self.pattern,
+            phase: self.phase,
+        })
+        .init_resource::<BotStats>()
+        .add_systems(Startup, connect)
+        .add_systems(OnEnter(GameState::Lobby), ready_up)
+        .add_systems(OnEnter(GameState::Playing), note_playing)
+        .add_systems(OnExit(InSession), note_session_end)
+        .add_systems(
+            FixedPreUpdate,
+            drive_local_player.before(InputSystemSet::BufferClientInputs),
+        )
+        .add_systems(
+            Update,
+            (
+                remove_local_input_map,
+                copy_prediction_counts,
+                sample_rtt.run_if(on_timer(METRICS_INTERVAL)),
+            )
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Scripted movement for a bot
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MovePattern {
+    /// Runs in circles a few seconds across
+    Circle,
+    /// Strafes left and right while slowly going back and forth
+    Zigzag,
+    /// Picks a new random direction every couple of seconds
+    Wander,
+}
+
+impl MovePattern {
+    pub const ALL: [MovePattern; 3] = [
+        MovePattern::Circle,
+        MovePattern::Zigzag,
+        MovePattern::Wander,
+    ];
+
+    /// Where to move at `secs`, any length since `move_player` normalizes it
+    fn direction(self, secs: f32) -> Vec2 {
+        match self {
+            MovePattern::Circle => Vec2::from_angle(secs),
+            MovePattern::Zigzag => {
+                let side = if (secs as i64) % 2 == 0 { 1.0 } else { -1.0 };
+                let forward = if (secs as i64 / 4) % 2 == 0 {
+                    1.0
+                } else {
+                    -1.0
+                };
+                Vec2::new(side, forward)
+            }
+            // A new direction every two seconds
+            MovePattern::Wander => wander_direction((secs / 2.0).floor()),
+        }
+    }
+}
+
+/// What a bot saw while playing
+#[derive(Resource, Default, Debug, Clone)]
+pub struct BotStats {
+    /// Whether the bot made it into a match at least once
+    pub played: bool,
+    pub rtt_samples: u32,
+    pub rtt_ms_total: f32,
+    pub rtt_ms_max: f32,
+    pub rollbacks: u32,
+    pub corrections: u32,
+    /// Sessions that ended, whether we or the server ended them
+    pub disconnects: u32,
+}
+
+impl BotStats {
+    pub fn mean_rtt_ms(&self) -> Option<f32> {
+        (self.rtt_samples > 0).then(|| self.rtt_ms_total / self.rtt_samples as f32)
+    }
+}
+
+#[derive(Resource)]
+struct BotMovement {
+    pattern: MovePattern,
+    phase: f32,
+}
+
+fn connect(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::ConnectingRemote);
+}
+
+fn ready_up(mut commands: Commands) {
+    commands.set_ready(true);
+}
+
+fn note_playing(mut stats: ResMut<BotStats>) {
+    stats.played = true;
+}
+
+fn note_session_end(mut stats: ResMut<BotStats>) {
+    stats.disconnects += 1;
+}
+
+/// Input is scripted, so the input map must not overwrite it
+fn remove_local_input_map(
+    mut commands: Commands,
+    q_local_player: Query<Entity, (With<LocalPlayer>, With<InputMap<NetworkedInput>>)>,
+) {
+    for entity in &q_local_player {
+        commands.entity(entity).remove::<InputMap<NetworkedInput>>();
+    }
+}
+
+fn drive_local_player(
+    time: Res<Time>,
+    movement: Res<BotMovement>,
+    mut q_local_player: Query<
+        &mut ActionState<NetworkedInput>,
+        (With<LocalPlayer>, With<Predicted>),
+    >,
+) {
+    let direction = movement
+        .pattern
+        .direction(time.elapsed_secs() + movement.phase);
+
+    for mut action_state in &mut q_local_player {
+        action_state.set_axis_pair(&NetworkedInput::Move, direction);
+    }
+}
+
+fn copy_prediction_counts(mut stats: ResMut<BotStats>, counts: Res<PredictionCounts>) {
+    stats.rollbacks = counts.rollbacks;
+    stats.corrections = counts.corrections;
+}
+
+fn sample_rtt(mut stats: ResMut<BotStats>, metrics: Res<NetworkMetrics>) {
+    let rtt_ms = metrics.local.rtt_ms;
+
+    stats.rtt_samples += 1;
+    stats.rtt_ms_total += rtt_ms;
+    stats.rtt_ms_max = stats.rtt_ms_max.max(rtt_ms);
+}
@@ -0,0 +1,137 @@
+use std::collections::VecDeque;
+
+use bevy::{ecs::query::QueryFilter, prelude::*};
+use leafwing_input_manager::{Actionlike, plugin::InputManagerSystem, prelude::ActionState};
+use lightyear::prelude::{ClientConnectionManager, ClientId, ClientReceiveMessage};
+use protocol::{
+    input::NetworkedInput,
+    message::{ChatChannel, ClientChat, MAX_CHAT_LENGTH, Reliable, ServerChat},
+};
+
+use crate::{
+    game_state::InSession, input::LocalInput, replication::LocalPlayer, spectator::SpectatorInput,
+};
+
+/// Keeps the chat history of the session, and holds back gameplay input while typing
+pub struct ChatPlugin;
+
+impl Plugin for ChatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChatHistory>()
+            .init_resource::<ChatInput>()
+            .configure_sets(
+                PreUpdate,
+                ChatFocus.after(InputManagerSystem::ManualControl),
+            )
+            .add_systems(
+                PreUpdate,
+                (
+                    suspend_while_typing::<NetworkedInput, With<LocalPlayer>>,
+                    suspend_while_typing::<SpectatorInput, ()>,
+                    suspend_while_typing::<LocalInput, ()>,
+                )
+                    .after(ChatFocus),
+            )
+            .add_systems(Update, receive_chat)
+            .add_systems(OnExit(InSession), clear_chat);
+    }
+}
+
+/// Where `ChatInput::focused` changes, once this frame's input has been read. Gameplay input is
+/// suspended right after, so nothing reading an `ActionState` later in the frame sees the Enter
+/// that opened chat as `LocalInput::MenuConfirm`, or any key typed after it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChatFocus;
+
+/// Messages kept for the chat box, oldest dropped first
+const CHAT_HISTORY_LENGTH: usize = 50;
+
+#[derive(Resource, Default, Debug)]
+pub struct ChatHistory(pub VecDeque<ServerChat>);
+
+impl ChatHistory {
+    pub fn push(&mut self, message: ServerChat) {
+        self.0.push_back(message);
+        if self.0.len() > CHAT_HISTORY_LENGTH {
+            self.0.pop_front();
+        }
+    }
+}
+
+/// The message being typed. Gameplay input is suspended while `focused`.
+#[derive(Resource, Default, Debug)]
+pub struct ChatInput {
+    pub focused: bool,
+    pub text: String,
+}
+
+pub trait SendChatExt {
+    fn send_chat(&mut self, message: ClientChat);
+}
+
+impl SendChatExt for Commands<'_, '_> {
+    fn send_chat(&mut self, message: ClientChat) {
+        self.queue(move |world: &mut World| {
+            let mut client = world.resource_mut::<ClientConnectionManager>();
+            if let Err(e) = client.send_message::<Reliable, ClientChat>(&message) {
+                error!("unable to send chat message, had error {}", e);
+            }
+        });
+    }
+}
+
+/// `/team message` goes to the team, `/w <client id> message` to one player,
+/// and anything else to everyone
+pub fn parse_chat_input(text: &str) -> Result<ClientChat, String> {
+    let text = text.trim();
+    let (channel, text) = match text.split_once(' ') {
+        Some(("/t" | "/team", rest)) => (ChatChannel::Team, rest),
+        Some(("/w" | "/whisper", rest)) => {
+            let (client_id, rest) = rest.trim_start().split_once(' ').unwrap_or((rest, ""));
+            let client_id = client_id
+                .parse()
+                .map_err(|_| format!("{} is not a client id", client_id))?;
+            (ChatChannel::Whisper(ClientId::Netcode(client_id)), rest)
+        }
+        _ if text.starts_with('/') => return Err(format!("unknown chat command {}", text)),
+        _ => (ChatChannel::All, text),
+    };
+
+    let text: String = text.trim().chars().take(MAX_CHAT_LENGTH).collect();
+    if text.is_empty() {
+        return Err(String::from("nothing to send"));
+    }
+
+    Ok(ClientChat { channel, text })
+}
+
+fn receive_chat(
+    mut chat_events: ResMut<Events<ClientReceiveMessage<ServerChat>>>,
+    mut history: ResMut<ChatHistory>,
+) {
+    for ev in chat_events.drain() {
+        history.push(ev.message);
+    }
+}
+
+/// Typing must not move the player, fly the camera or open menus. Keys still held when typing
+/// stops, like the Enter that sent or the Escape that gave up, are consumed until released.
+fn suspend_while_typing<A: Actionlike, F: QueryFilter>(
+    chat_input: Res<ChatInput>,
+    mut q_action_state: Query<&mut ActionState<A>, F>,
+) {
+    for mut action_state in &mut q_action_state {
+        if chat_input.focused && !action_state.disabled() {
+            action_state.reset_all();
+            action_state.disable();
+        } else if !chat_input.focused && action_state.disabled() {
+            action_state.enable();
+            action_state.consume_all();
+        }
+    }
+}
+
+fn clear_chat(mut history: ResMut<ChatHistory>, mut chat_input: ResMut<ChatInput>) {
+    history.0.clear();
+    *chat_input = ChatInput::default();
+}
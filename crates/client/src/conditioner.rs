@@ -0,0 +1,141 @@
+use bevy::prelude::*;
+use common::conditioner::{ConditionerPreset, NetworkConditions};
+use lightyear::client::config::ClientConfig;
+#[cfg(not(target_family = "wasm"))]
+use {
+    common::relay::{ConditionedEnd, ConditionedRelay},
+    lightyear::{
+        connection::client::NetConfig,
+        prelude::client::{Authentication, ClientTransport},
+    },
+    std::net::{IpAddr, Ipv4Addr, SocketAddr},
+};
+
+/// Changes the link conditioner on our side of the connection. On native clients a
+/// `ConditionedRelay` sits between us and the server, so a change applies straight away.
+pub struct ConditionerPlugin;
+
+impl Plugin for ConditionerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SetLinkConditioner>()
+            .init_resource::<ClientConditions>()
+            .add_systems(Update, apply_link_conditioner);
+    }
+}
+
+/// Condition our connection with these, or turn conditioning off with `None`
+#[derive(Event, Debug, Clone)]
+pub struct SetLinkConditioner(pub Option<NetworkConditions>);
+
+/// What the client is conditioned with
+#[derive(Resource, Default)]
+pub struct ClientConditions {
+    /// The preset the conditions match, if any
+    pub preset: Option<ConditionerPreset>,
+    pub conditions: Option<NetworkConditions>,
+    /// Between us and the server while connected to a remote server
+    #[cfg(not(target_family = "wasm"))]
+    relay: Option<ConditionedRelay>,
+}
+
+impl ClientConditions {
+    /// Insert before connecting to condition the connection from the start
+    pub fn new(conditions: Option<NetworkConditions>) -> Self {
+        Self {
+            preset: preset_of(conditions),
+            conditions,
+            ..default()
+        }
+    }
+}
+
+fn preset_of(conditions: Option<NetworkConditions>) -> Option<ConditionerPreset> {
+    ConditionerPreset::ALL
+        .into_iter()
+        .find(|preset| preset.conditions() == conditions)
+}
+
+fn apply_link_conditioner(
+    mut set_conditioner: EventReader<SetLinkConditioner>,
+    mut client_conditions: ResMut<ClientConditions>,
+) {
+    let Some(SetLinkConditioner(conditions)) = set_conditioner.read().last() else {
+        return;
+    };
+
+    client_conditions.preset = preset_of(*conditions);
+    client_conditions.conditions = *conditions;
+    #[cfg(not(target_family = "wasm"))]
+    if let Some(relay) = &client_conditions.relay {
+        relay.set_conditions(*conditions);
+    }
+
+    match conditions {
+        Some(conditions) => info!("link conditioner set to {}", conditions),
+        None => info!("link conditioner turned off"),
+    }
+}
+
+/// Send a UDP connection to a remote server through a relay that conditions it, so the
+/// conditions can change without reconnecting. Other connections are left alone.
+#[cfg(not(target_family = "wasm"))]
+pub(crate) fn relay_connection(
+    client_config: &mut ClientConfig,
+    client_conditions: &mut ClientConditions,
+) {
+    client_conditions.relay = None;
+
+    let NetConfig::Netcode {
+        auth: Authentication::Manual { server_addr, .. },
+        io,
+        ..
+    } = &mut client_config.net
+    else {
+        return;
+    };
+    let ClientTransport::UdpSocket(local_addr) = io.transport else {
+        return;
+    };
+
+    let relay = ConditionedRelay::spawn(
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
+        *server_addr,
+        local_addr,
+        ConditionedEnd::Peers,
+        client_conditions.conditions,
+    );
+    match relay {
+        Ok(relay) => {
+            *server_addr = relay.listen_addr();
+            io.transport =
+                ClientTransport::UdpSocket(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0));
+            io.conditioner = None;
+            client_conditions.relay = Some(relay);
+        }
+        Err(e) => warn!(
+            "unable to put a link conditioner in front of {}, connecting without one: {}",
+            server_addr, e
+        ),
+    }
+}
+
+/// Browsers can't open a UDP socket for a relay, so lightyear's conditioner holds back our
+/// incoming packets instead. It is built when connecting, so changes apply from the next
+/// connection, and there is no outgoing latency or duplication.
+#[cfg(target_family = "wasm")]
+pub(crate) fn relay_connection(
+    client_config: &mut ClientConfig,
+    client_conditions: &mut ClientConditions,
+) {
+    use lightyear::{connection::client::NetConfig, prelude::LinkConditionerConfig};
+
+    if let NetConfig::Netcode { io, .. } = &mut client_config.net {
+        io.conditioner = client_conditions
+            .conditions
+            .map(|conditions| LinkConditionerConfig {
+                incoming_latency: conditions.incoming_latency,
+                incoming_jitter: conditions.incoming_jitter,
+                incoming_loss: conditions.incoming_loss,
+            });
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    io::ErrorKind,
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+    time::Duration,
+};
+
+use bevy::prelude::*;
+use protocol::{
+    discovery::{
+        DISCOVERY_PORT, DiscoveryQuery, DiscoveryResponse, decode_discovery_packet,
+        encode_discovery_packet,
+    },
+    fingerprint::ProtocolFingerprint,
+    message::Level,
+};
+
+use crate::game_state::GameState;
+
+/// Finds servers on the local network while the main menu is open
+pub struct DiscoveryPlugin;
+
+impl Plugin for DiscoveryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DiscoveredServers>()
+            .init_resource::<DiscoveryTargets>()
+            .init_resource::<DiscoveryQueryTimer>();
+
+        // Browsers have no UDP sockets
+        #[cfg(not(target_family = "wasm"))]
+        app.add_systems(Startup, bind_discovery_socket).add_systems(
+            Update,
+            (
+                send_discovery_queries,
+                receive_discovery_responses,
+                forget_stale_servers,
+            )
+                .chain()
+                .run_if(in_state(GameState::MainMenu).and(resource_exists::<DiscoverySocket>)),
+        );
+    }
+}
+
+const QUERY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Servers that miss this many queries in a row are dropped from the list
+const MISSED_QUERIES_BEFORE_STALE: u32 = 3;
+
+#[derive(Clone, Debug)]
+pub struct DiscoveredServer {
+    /// Where to connect to play, not where the response came from
+    pub addr: SocketAddr,
+    pub name: String,
+    pub current_level: Level,
+    pub player_count: u32,
+    /// Whether the server's protocol fingerprint matches ours
+    pub compatible: bool,
+    last_seen: Duration,
+}
+
+/// Servers that answered a discovery query recently.
+/// Only changes when a server appears, disappears or reports something new.
+#[derive(Resource, Default)]
+pub struct DiscoveredServers(pub Vec<DiscoveredServer>);
+
+/// Where discovery queries are sent. Broadcast on the well known port by default, on the LAN
+/// and on loopback, since a machine without a LAN can't send the first. Every server sharing
+/// the port gets a broadcast, where a unicast would only reach one of them.
+#[derive(Resource, Clone, Debug)]
+pub struct DiscoveryTargets(pub Vec<SocketAddr>);
+
+impl Default for DiscoveryTargets {
+    fn default() -> Self {
+        Self(vec![
+            SocketAddr::from((Ipv4Addr::BROADCAST, DISCOVERY_PORT)),
+            SocketAddr::from((Ipv4Addr::new(127, 255, 255, 255), DISCOVERY_PORT)),
+        ])
+    }
+}
+
+/// `None` until the first query goes out, so one is sent as soon as possible
+#[derive(Resource, Default)]
+struct DiscoveryQueryTimer(Option<Timer>);
+
+#[derive(Resource)]
+struct DiscoverySocket(UdpSocket);
+
+fn bind_discovery_socket(mut commands: Commands) {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).and_then(|socket| {
+        socket.set_broadcast(true)?;
+        socket.set_nonblocking(true)?;
+        Ok(socket)
+    });
+
+    match socket {
+        Ok(socket) => {
+            commands.insert_resource(DiscoverySocket(socket));
+        }
+        Err(e) => warn!(
+            "unable to open discovery socket, LAN servers won't be listed: {}",
+            e
+        ),
+    }
+}
+
+fn send_discovery_queries(
+    time: Res<Time>,
+    socket: Res<DiscoverySocket>,
+    targets: Res<DiscoveryTargets>,
+    mut query_timer: ResMut<DiscoveryQueryTimer>,
+) {
+    let due = match &mut query_timer.0 {
+        Some(timer) => timer.tick(time.delta()).just_finished(),
+        None => {
+            query_timer.0 = Some(Timer::new(QUERY_INTERVAL, TimerMode::Repeating));
+            true
+        }
+    };
+
+    if !due {
+        return;
+    }
+
+    let Some(packet) = encode_discovery_packet(&DiscoveryQuery) else {
+        error!("unable to encode discovery query");
+        return;
+    };
+
+    for target in &targets.0 {
+        if let Err(e) = socket.0.send_to(&packet, target) {
+            debug!("unable to send discovery query to {}: {}", target, e);
+        }
+    }
+}
+
+fn receive_discovery_responses(
+    time: Res<Time>,
+    socket: Res<DiscoverySocket>,
+    protocol_fingerprint: Res<ProtocolFingerprint>,
+    mut discovered: ResMut<DiscoveredServers>,
+) {
+    let mut buffer = [0; 512];
+
+    loop {
+        let (len, from) = match socket.0.recv_from(&mut buffer) {
+            Ok(received) => received,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => return,
+            Err(e) => {
+                warn!("discovery socket error: {}", e);
+                return;
+            }
+        };
+
+        let Some(response) = decode_discovery_packet::<DiscoveryResponse>(&buffer[..len]) else {
+            continue;
+        };
+
+        let server = DiscoveredServer {
+            addr: SocketAddr::new(response.game_ip.unwrap_or(from.ip()), response.game_port),
+            name: response.name,
+            current_level: response.current_level,
+            player_count: response.player_count,
+            compatible: response.protocol_fingerprint == **protocol_fingerprint,
+            last_seen: time.elapsed(),
+        };
+
+        let existing = discovered
+            .0
+            .iter()
+            .position(|known| known.addr == server.addr);
+
+        match existing {
+            Some(index) => {
+                let known = &discovered.0[index];
+                let unchanged = known.name == server.name
+                    && known.current_level == server.current_level
+                    && known.player_count == server.player_count
+                    && known.compatible == server.compatible;
+
+                if unchanged {
+                    discovered.bypass_change_detection().0[index].last_seen = server.last_seen;
+                } else {
+                    discovered.0[index] = server;
+                }
+            }
+            None => {
+                info!("discovered server {} at {}", server.name, server.addr);
+                discovered.0.push(server);
+            }
+        }
+    }
+}
+
+fn forget_stale_servers(time: Res<Time>, mut discovered: ResMut<DiscoveredServers>) {
+    let stale_after = QUERY_INTERVAL * MISSED_QUERIES_BEFORE_STALE;
+    let now = time.elapsed();
+
+    let any_stale = discovered
+        .0
+        .iter()
+        .any(|server| now.saturating_sub(server.last_seen) > stale_after);
+
+    if any_stale {
+        discovered
+            .0
+            .retain(|server| now.saturating_sub(server.last_seen) <= stale_after);
+    }
+}
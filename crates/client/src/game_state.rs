@@ -1,10 +1,10 @@
-use assets::Geometry;
+use assets::reset_level_assets;
 use bevy::prelude::*;
+use common::level::LevelRoot;
 use lightyear::prelude::{
-    ClientConnectionManager, Replicated,
-    client::{ClientCommandsExt, Confirmed, Predicted},
+    Replicated,
+    client::{Confirmed, Interpolated, Predicted},
 };
-use protocol::message::Reliable;
 
 #[derive(States, Debug, Default, Clone, PartialEq, Eq, Hash)]
 pub enum GameState {
@@ -12,36 +12,52 @@ pub enum GameState {
     MainMenu,
     ConnectingRemote, // Connection request sent to the server,
     Loading,          // Connected and server told us to load something
-    Spawning,         // Loaded the assets, now wait for the Player to be replicated
+    Lobby,            // Loaded the assets, waiting in the lobby for the match to start
+    Spawning,         // The match started, now wait for the Player to be replicated
     Playing,          // Player exists and we can give control to the client
+    Spectating,       // Loaded the assets, watching the match without a player
+    Replay,           // Playing back a recorded session, no server involved
+}
+
+/// Exists from the moment we start connecting to a server until we are back in the main menu.
+/// Everything that belongs to a session is `StateScoped(InSession)`, so it is despawned
+/// when the session ends, whichever `GameState` it ended from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InSession;
+
+impl ComputedStates for InSession {
+    type SourceStates = GameState;
+
+    fn compute(game_state: GameState) -> Option<Self> {
+        match game_state {
+            GameState::MainMenu => None,
+            _ => Some(InSession),
+        }
+    }
 }
 
 pub struct GameLifecyclePlugin;
 
 impl Plugin for GameLifecyclePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnExit(GameState::Playing), cleanup_on_exit_to_menu);
+        app.init_state::<GameState>()
+            .add_computed_state::<InSession>()
+            .enable_state_scoped_entities::<InSession>();
+
+        // The level and everything the server replicates to us is spawned by shared code,
+        // so tag it here instead of at every spawn site
+        app.add_observer(scope_to_session::<LevelRoot>)
+            .add_observer(scope_to_session::<Replicated>)
+            .add_observer(scope_to_session::<Predicted>)
+            .add_observer(scope_to_session::<Confirmed>)
+            .add_observer(scope_to_session::<Interpolated>);
 
-        app.init_state::<GameState>();
+        app.add_systems(OnExit(InSession), reset_level_assets);
     }
 }
 
-fn cleanup_on_exit_to_menu(
-    mut commands: Commands,
-    q_everything: Query<
-        Entity,
-        Or<(
-            With<Geometry>,
-            With<Predicted>,
-            With<Confirmed>,
-            With<Replicated>,
-        )>,
-    >,
-    //    mut client_manager: ResMut<ClientConnectionManager>,
-) {
-    println!("sending request shutdown");
-
-    for thing in &q_everything {
-        commands.entity(thing).despawn_recursive()
-    }
+fn scope_to_session<C: Component>(trigger: Trigger<OnAdd, C>, mut commands: Commands) {
+    commands
+        .entity(trigger.entity())
+        .try_insert(StateScoped(InSession));
 }
@@ -7,7 +7,11 @@ use leafwing_input_manager::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::{game_state::GameState, replication::LocalPlayer, ui::system_menu::SystemMenuState};
+use crate::{
+    game_state::{GameState, InSession},
+    replication::LocalPlayer,
+    ui::system_menu::SystemMenuState,
+};
 
 pub struct InputPlugin;
 impl Plugin for InputPlugin {
@@ -17,9 +21,11 @@ impl Plugin for InputPlugin {
                 Update,
                 (
                     add_local_input_map,
-                    handle_system_menu_or_cancel.run_if(in_state(GameState::Playing)),
+                    handle_system_menu_or_cancel
+                        .run_if(in_state(GameState::Playing).or(in_state(GameState::Spectating))),
                 ),
-            );
+            )
+            .add_systems(OnEnter(GameState::Spectating), spawn_spectator_local_input);
     }
 }
 
@@ -27,6 +33,41 @@ impl Plugin for InputPlugin {
 pub enum LocalInput {
     #[actionlike(Button)]
     SystemMenuOrCancel,
+    #[actionlike(Button)]
+    MenuUp,
+    #[actionlike(Button)]
+    MenuDown,
+    #[actionlike(Button)]
+    MenuConfirm,
+    /// Held to show the scoreboard
+    #[actionlike(Button)]
+    Scoreboard,
+    /// Toggles the network metrics overlay
+    #[actionlike(Button)]
+    NetworkMetrics,
+    /// Toggles the netcode HUD
+    #[actionlike(Button)]
+    NetcodeHud,
+    /// Switches to the next link conditioner preset
+    #[actionlike(Button)]
+    CycleConditioner,
+}
+
+fn local_input_map() -> InputMap<LocalInput> {
+    InputMap::<LocalInput>::default()
+        .with(LocalInput::SystemMenuOrCancel, KeyCode::Escape)
+        .with(LocalInput::SystemMenuOrCancel, GamepadButton::Start)
+        .with(LocalInput::MenuUp, KeyCode::ArrowUp)
+        .with(LocalInput::MenuUp, GamepadButton::DPadUp)
+        .with(LocalInput::MenuDown, KeyCode::ArrowDown)
+        .with(LocalInput::MenuDown, GamepadButton::DPadDown)
+        .with(LocalInput::MenuConfirm, KeyCode::Enter)
+        .with(LocalInput::MenuConfirm, GamepadButton::South)
+        .with(LocalInput::Scoreboard, KeyCode::Tab)
+        .with(LocalInput::Scoreboard, GamepadButton::Select)
+        .with(LocalInput::NetworkMetrics, KeyCode::F3)
+        .with(LocalInput::NetcodeHud, KeyCode::F4)
+        .with(LocalInput::CycleConditioner, KeyCode::F5)
 }
 
 fn add_local_input_map(
@@ -34,13 +75,21 @@ fn add_local_input_map(
     q_local_player: Query<Entity, (Simulated, Added<LocalPlayer>)>,
 ) {
     for player in &q_local_player {
-        commands.entity(player).insert((
-            InputMap::<LocalInput>::default().with(LocalInput::SystemMenuOrCancel, KeyCode::Escape),
-            ActionState::<LocalInput>::default(),
-        ));
+        commands
+            .entity(player)
+            .insert((local_input_map(), ActionState::<LocalInput>::default()));
     }
 }
 
+/// Spectators have no player to hang the input map on, but still need the system menu
+fn spawn_spectator_local_input(mut commands: Commands) {
+    commands.spawn((
+        local_input_map(),
+        ActionState::<LocalInput>::default(),
+        StateScoped(InSession),
+    ));
+}
+
 fn handle_system_menu_or_cancel(
     q_local_inputs: Query<&ActionState<LocalInput>>,
     system_menu_state: Res<State<SystemMenuState>>,
@@ -51,6 +100,9 @@ fn handle_system_menu_or_cancel(
             match **system_menu_state {
                 SystemMenuState::Open => next_system_menu_state.set(SystemMenuState::Closed),
                 SystemMenuState::Closed => next_system_menu_state.set(SystemMenuState::Open),
+                SystemMenuState::Settings => next_system_menu_state.set(SystemMenuState::Open),
+                // Nothing to cancel, we are waiting on the server
+                SystemMenuState::Disconnecting => {}
             }
         }
     }
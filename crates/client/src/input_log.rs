@@ -0,0 +1,159 @@
+use std::{fs::File, io::BufWriter, path::PathBuf};
+
+use avian3d::prelude::{Position, Rotation};
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use leafwing_input_manager::prelude::ActionState;
+use lightyear::{
+    client::prediction::rollback::is_in_rollback,
+    prelude::{
+        TickManager,
+        client::{ClientConnection, Confirmed, NetClient, Predicted},
+    },
+};
+use protocol::{
+    framed::FLUSH_INTERVAL,
+    input::NetworkedInput,
+    input_log::{
+        BodyState, ConfirmedState, INPUT_LOG_VERSION, InputLogFrame, InputLogHeader, InputLogWriter,
+    },
+};
+
+use crate::{
+    game_state::{GameState, InSession},
+    replication::LocalPlayer,
+};
+
+/// Logs the local player's input, predicted and confirmed state every tick, when
+/// `InputLogConfig::path` is set, so that `launcher resim` can look for where prediction went wrong
+pub struct InputLogPlugin;
+
+impl Plugin for InputLogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputLogConfig>()
+            .add_systems(OnEnter(GameState::Playing), start_input_log)
+            .add_systems(OnExit(InSession), stop_input_log)
+            .add_systems(
+                FixedLast,
+                log_tick.run_if(resource_exists::<InputLogger>.and(not(is_in_rollback))),
+            )
+            .add_systems(
+                Update,
+                flush_input_log
+                    .run_if(resource_exists::<InputLogger>.and(on_timer(FLUSH_INTERVAL))),
+            );
+    }
+}
+
+/// Insert before startup to log inputs
+#[derive(Resource, Clone, Debug, Default)]
+pub struct InputLogConfig {
+    /// Overwritten by every session
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Resource)]
+struct InputLogger {
+    writer: InputLogWriter<BufWriter<File>>,
+    last_confirmed_tick: Option<u16>,
+}
+
+/// Every match of a session goes to the same log, started by the first one
+fn start_input_log(
+    mut commands: Commands,
+    config: Res<InputLogConfig>,
+    logger: Option<Res<InputLogger>>,
+    client: Res<ClientConnection>,
+    fixed_time: Res<Time<Fixed>>,
+) {
+    let Some(path) = &config.path else {
+        return;
+    };
+    if logger.is_some() {
+        return;
+    }
+
+    let header = InputLogHeader {
+        version: INPUT_LOG_VERSION,
+        client_id: client.id(),
+        tick_duration: fixed_time.timestep(),
+    };
+
+    match File::create(path).and_then(|file| InputLogWriter::new(BufWriter::new(file), &header)) {
+        Ok(writer) => {
+            info!("logging inputs to {:?}", path);
+            commands.insert_resource(InputLogger {
+                writer,
+                last_confirmed_tick: None,
+            });
+        }
+        Err(e) => error!("unable to log inputs to {:?}: {}", path, e),
+    }
+}
+
+fn stop_input_log(mut commands: Commands, logger: Option<ResMut<InputLogger>>) {
+    if let Some(mut logger) = logger {
+        if let Err(e) = logger.writer.flush() {
+            error!("unable to finish the input log: {}", e);
+        }
+        commands.remove_resource::<InputLogger>();
+    }
+}
+
+fn log_tick(
+    mut commands: Commands,
+    mut logger: ResMut<InputLogger>,
+    tick_manager: Res<TickManager>,
+    q_local_player: Query<
+        (
+            &ActionState<NetworkedInput>,
+            &Position,
+            &Rotation,
+            &Predicted,
+        ),
+        With<LocalPlayer>,
+    >,
+    q_confirmed: Query<(&Confirmed, &Position, &Rotation)>,
+) {
+    let Ok((action_state, position, rotation, predicted)) = q_local_player.get_single() else {
+        return;
+    };
+
+    let confirmed = predicted
+        .confirmed_entity
+        .and_then(|entity| q_confirmed.get(entity).ok())
+        .filter(|(confirmed, _, _)| logger.last_confirmed_tick != Some(confirmed.tick.0))
+        .map(|(confirmed, position, rotation)| ConfirmedState {
+            tick: confirmed.tick.0,
+            body: BodyState {
+                position: position.0,
+                rotation: rotation.0,
+            },
+        });
+    if let Some(confirmed) = &confirmed {
+        logger.last_confirmed_tick = Some(confirmed.tick);
+    }
+
+    let frame = InputLogFrame {
+        tick: tick_manager.tick().0,
+        movement: action_state
+            .dual_axis_data(&NetworkedInput::Move)
+            .map(|movement| movement.pair),
+        predicted: BodyState {
+            position: position.0,
+            rotation: rotation.0,
+        },
+        confirmed,
+    };
+
+    if let Err(e) = logger.writer.write_frame(&frame) {
+        error!("stopped logging inputs: {}", e);
+        commands.remove_resource::<InputLogger>();
+    }
+}
+
+fn flush_input_log(mut commands: Commands, mut logger: ResMut<InputLogger>) {
+    if let Err(e) = logger.writer.flush() {
+        error!("stopped logging inputs: {}", e);
+        commands.remove_resource::<InputLogger>();
+    }
+}
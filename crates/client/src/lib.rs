@@ -1,8 +1,19 @@
 pub mod app;
+pub mod bot;
+pub mod chat;
+pub mod conditioner;
+pub mod discovery;
 
-mod game_state;
+pub mod game_state;
 mod input;
+pub mod input_log;
 mod interpolation;
-mod network;
+pub mod lobby;
+pub mod metrics;
+pub mod network;
+pub mod prediction;
+pub mod profile;
+pub mod replay;
 mod replication;
-mod ui;
+pub mod spectator;
+pub mod ui;
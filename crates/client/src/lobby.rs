@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+use lightyear::prelude::{
+    ClientConnectionManager, ClientReceiveMessage,
+    client::{ClientConnection, NetClient},
+};
+use protocol::{
+    component::{LobbyMember, LobbyRoster, MatchState, MatchStatus},
+    message::{ClientSetReady, ServerQueuePosition, UnorderedReliable},
+};
+
+use crate::game_state::{GameState, InSession};
+
+/// Follows the server's `MatchState`, moving between the lobby and the match
+pub struct LobbyPlugin;
+
+impl Plugin for LobbyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<QueuePosition>()
+            .add_systems(
+                Update,
+                (
+                    (receive_queue_position, leave_queue_when_admitted)
+                        .chain()
+                        .run_if(in_state(GameState::Lobby)),
+                    follow_match_state.run_if(
+                        in_state(GameState::Lobby)
+                            .or(in_state(GameState::Spawning))
+                            .or(in_state(GameState::Playing)),
+                    ),
+                ),
+            )
+            .add_systems(OnExit(InSession), clear_queue_position);
+    }
+}
+
+/// Where we are in the server's queue, while it is full and we wait for a slot
+#[derive(Resource, Default, Debug)]
+pub struct QueuePosition(pub Option<ServerQueuePosition>);
+
+pub trait SetReadyExt {
+    /// Tell the server whether we are ready for the match to start
+    fn set_ready(&mut self, ready: bool);
+}
+
+impl SetReadyExt for Commands<'_, '_> {
+    fn set_ready(&mut self, ready: bool) {
+        self.queue(move |world: &mut World| {
+            let mut client = world.resource_mut::<ClientConnectionManager>();
+            if let Err(e) =
+                client.send_message::<UnorderedReliable, ClientSetReady>(&ClientSetReady(ready))
+            {
+                error!("unable to send ready state, had error {}", e);
+            }
+        });
+    }
+}
+
+/// Our own entry in the lobby, once the server has added us
+pub fn local_lobby_member<'a>(
+    roster: &'a LobbyRoster,
+    client: &ClientConnection,
+) -> Option<&'a LobbyMember> {
+    roster.member(client.id())
+}
+
+fn receive_queue_position(
+    mut queue_position_events: ResMut<Events<ClientReceiveMessage<ServerQueuePosition>>>,
+    mut queue_position: ResMut<QueuePosition>,
+) {
+    for ev in queue_position_events.drain() {
+        queue_position.0 = Some(ev.message);
+    }
+}
+
+fn leave_queue_when_admitted(
+    q_roster: Query<&LobbyRoster>,
+    client: Res<ClientConnection>,
+    mut queue_position: ResMut<QueuePosition>,
+) {
+    if queue_position.0.is_some()
+        && q_roster
+            .iter()
+            .any(|roster| local_lobby_member(roster, &client).is_some())
+    {
+        queue_position.0 = None;
+    }
+}
+
+fn clear_queue_position(mut queue_position: ResMut<QueuePosition>) {
+    queue_position.0 = None;
+}
+
+/// Players are only spawned once the match starts, and are despawned when it ends.
+/// Anyone still queued for a slot stays in the lobby.
+fn follow_match_state(
+    q_match_status: Query<&MatchStatus>,
+    q_roster: Query<&LobbyRoster>,
+    client: Res<ClientConnection>,
+    game_state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let admitted = q_roster
+        .iter()
+        .any(|roster| local_lobby_member(roster, &client).is_some());
+
+    for status in &q_match_status {
+        match (game_state.get(), status.state) {
+            (GameState::Lobby, MatchState::InProgress) if admitted => {
+                next_state.set(GameState::Spawning)
+            }
+            (
+                GameState::Spawning | GameState::Playing,
+                MatchState::Lobby | MatchState::Countdown,
+            ) => next_state.set(GameState::Lobby),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use lightyear::prelude::{
+    ClientConnectionManager, ClientReceiveMessage,
+    client::{ClientConnection, NetClient},
+};
+use protocol::metrics::{
+    ConnectionMetrics, LossEstimator, METRICS_INTERVAL, MessageCounts, MetricsChannel,
+    MetricsReport, TrafficRates, TrafficTotals,
+};
+
+use crate::game_state::{GameState, InSession};
+
+/// Samples our connection to the server, and swaps reports with the server so
+/// each side also knows how the other sees it
+pub struct MetricsPlugin;
+
+impl Plugin for MetricsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NetworkMetrics>()
+            .add_systems(OnExit(InSession), reset_metrics)
+            .add_systems(
+                Update,
+                (
+                    receive_server_reports,
+                    sample_connection_metrics.run_if(on_timer(METRICS_INTERVAL)),
+                )
+                    .chain()
+                    .run_if(
+                        in_state(GameState::Loading)
+                            .or(in_state(GameState::Lobby))
+                            .or(in_state(GameState::Spawning))
+                            .or(in_state(GameState::Playing))
+                            .or(in_state(GameState::Spectating)),
+                    ),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct NetworkMetrics {
+    /// How the connection looks from here
+    pub local: ConnectionMetrics,
+    /// How the server last said it looks from there
+    pub server: Option<ConnectionMetrics>,
+    rates: TrafficRates,
+    loss: LossEstimator,
+    sequence: u32,
+}
+
+fn sample_connection_metrics(
+    mut metrics: ResMut<NetworkMetrics>,
+    message_counts: Res<MessageCounts>,
+    mut connection_manager: ResMut<ClientConnectionManager>,
+    client: Res<ClientConnection>,
+) {
+    let metrics = &mut *metrics;
+
+    if let Some(io) = client.io() {
+        let stats = io.stats();
+        let totals = TrafficTotals {
+            bytes_in: stats.bytes_received,
+            bytes_out: stats.bytes_sent,
+            packets_in: stats.packets_received,
+            packets_out: stats.packets_sent,
+        };
+        metrics
+            .rates
+            .sample(totals, METRICS_INTERVAL, &mut metrics.local);
+    }
+
+    metrics.local.rtt_ms = connection_manager.rtt().as_secs_f32() * 1000.0;
+    metrics.local.jitter_ms = connection_manager.jitter().as_secs_f32() * 1000.0;
+    metrics.local.packet_loss = metrics.loss.loss();
+    metrics.local.messages = message_counts.to_vec();
+
+    let report = MetricsReport {
+        sequence: metrics.sequence,
+        metrics: metrics.local.clone(),
+    };
+    metrics.sequence = metrics.sequence.wrapping_add(1);
+
+    if let Err(e) = connection_manager.send_message::<MetricsChannel, MetricsReport>(&report) {
+        debug!("unable to send metrics report, had error {}", e);
+    }
+}
+
+fn receive_server_reports(
+    mut report_events: ResMut<Events<ClientReceiveMessage<MetricsReport>>>,
+    mut metrics: ResMut<NetworkMetrics>,
+) {
+    for ev in report_events.drain() {
+        metrics.loss.receive(ev.message.sequence);
+        metrics.server = Some(ev.message.metrics);
+    }
+}
+
+fn reset_metrics(mut metrics: ResMut<NetworkMetrics>, mut message_counts: ResMut<MessageCounts>) {
+    *metrics = NetworkMetrics::default();
+    *message_counts = MessageCounts::default();
+}
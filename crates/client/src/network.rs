@@ -1,10 +1,21 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
 use bevy::prelude::*;
 use lightyear::{
     client::config::ClientConfig,
-    prelude::{ClientConnectEvent, ClientDisconnectEvent, client::ClientCommandsExt},
+    connection::client::NetConfig,
+    prelude::{
+        ClientConnectEvent, ClientDisconnectEvent, ClientReceiveMessage,
+        client::{Authentication, ClientCommandsExt, ClientTransport},
+    },
 };
+use protocol::message::{DisconnectReason, ServerDisconnectNotice};
 
 use crate::app::LaunchConfigurations;
+use crate::conditioner::{ClientConditions, relay_connection};
 use crate::game_state::GameState;
 
 pub struct NetworkPlugin;
@@ -16,8 +27,77 @@ impl Plugin for NetworkPlugin {
             connect_to_remote_server,
         );
 
+        app.add_systems(
+            Update,
+            (
+                on_server_disconnect_notice,
+                tick_reconnect.run_if(in_state(GameState::MainMenu)),
+            ),
+        );
+        app.add_systems(OnEnter(GameState::Playing), reset_reconnect_attempts);
+        app.add_systems(OnEnter(GameState::Spectating), reset_reconnect_attempts);
+
         app.add_observer(on_client_connect_success)
             .add_observer(on_client_disconnect);
+
+        app.init_resource::<Disconnection>()
+            .init_resource::<Reconnect>()
+            .init_resource::<ConnectTarget>();
+    }
+}
+
+/// Give up reconnecting after this many attempts in a row
+pub const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+
+/// Delay before the first reconnect attempt, doubled for each attempt after that
+const RECONNECT_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// The server picked in the main menu. When unset we connect to the
+/// address from the launch configuration.
+#[derive(Resource, Default)]
+pub struct ConnectTarget(pub Option<SocketAddr>);
+
+#[derive(Resource, Default)]
+pub struct Disconnection {
+    /// Why we are about to disconnect, recorded just before the disconnect happens.
+    /// If nothing was recorded the transport dropped, which we treat as a timeout.
+    pending: Option<DisconnectReason>,
+    /// Set when the user asked to leave, so there is nothing to report
+    requested: bool,
+    /// Why the last session ended, shown in the main menu
+    pub last_reason: Option<DisconnectReason>,
+}
+
+/// Automatic reconnection after a timeout
+#[derive(Resource, Default)]
+pub struct Reconnect {
+    /// Consecutive attempts since we were last playing
+    pub attempts: u32,
+    /// Counts down to the next attempt, if one is scheduled
+    pub timer: Option<Timer>,
+}
+
+pub trait DisconnectWithReasonExt {
+    /// Disconnect, recording `reason` so it can be shown in the main menu
+    fn disconnect_with_reason(&mut self, reason: DisconnectReason);
+
+    /// Disconnect because the user asked to. No reason is shown and we don't reconnect.
+    fn disconnect_by_request(&mut self);
+}
+
+impl DisconnectWithReasonExt for Commands<'_, '_> {
+    fn disconnect_with_reason(&mut self, reason: DisconnectReason) {
+        self.queue(move |world: &mut World| {
+            world.resource_mut::<Disconnection>().pending = Some(reason);
+        });
+        self.disconnect_client();
+    }
+
+    fn disconnect_by_request(&mut self) {
+        self.queue(|world: &mut World| {
+            world.resource_mut::<Disconnection>().requested = true;
+        });
+        self.disconnect_client();
     }
 }
 
@@ -25,23 +105,135 @@ fn connect_to_remote_server(
     mut commands: Commands,
     host_config: ResMut<LaunchConfigurations>,
     mut client_config: ResMut<ClientConfig>,
+    mut disconnection: ResMut<Disconnection>,
+    mut reconnect: ResMut<Reconnect>,
+    connect_target: Res<ConnectTarget>,
+    mut client_conditions: ResMut<ClientConditions>,
 ) {
+    *disconnection = Disconnection::default();
+    reconnect.timer = None;
+
     *client_config = host_config
         .client_remote_config
         .clone()
         .expect("There must be a remote client config we are a client.");
+
+    if let Some(server_addr) = connect_target.0 {
+        set_server_addr(&mut client_config, server_addr);
+    }
+
+    info!("connecting to {:?}", configured_server_addr(&client_config));
+    relay_connection(&mut client_config, &mut client_conditions);
     commands.connect_client();
 }
 
+/// The address of the server a client config will connect to, if it connects over the network
+pub fn configured_server_addr(client_config: &ClientConfig) -> Option<SocketAddr> {
+    match &client_config.net {
+        NetConfig::Netcode {
+            auth: Authentication::Manual { server_addr, .. },
+            ..
+        } => Some(*server_addr),
+        _ => None,
+    }
+}
+
+/// Point a client config at another server. Local channel transports are swapped for UDP,
+/// since a channel can only ever reach the server it was created with.
+fn set_server_addr(client_config: &mut ClientConfig, addr: SocketAddr) {
+    let NetConfig::Netcode { auth, io, .. } = &mut client_config.net else {
+        warn!("unable to set the server address of a non-netcode client config");
+        return;
+    };
+
+    if let Authentication::Manual { server_addr, .. } = auth {
+        *server_addr = addr;
+    }
+
+    match &mut io.transport {
+        #[cfg(target_family = "wasm")]
+        ClientTransport::WebTransportClient { server_addr, .. } => *server_addr = addr,
+        ClientTransport::LocalChannel { .. } => {
+            io.transport =
+                ClientTransport::UdpSocket(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0));
+        }
+        // A socket bound to loopback can't reach other machines
+        ClientTransport::UdpSocket(local_addr)
+            if local_addr.ip().is_loopback() && !addr.ip().is_loopback() =>
+        {
+            *local_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), local_addr.port());
+        }
+        _ => {}
+    }
+}
+
 fn on_client_connect_success(_trigger: Trigger<ClientConnectEvent>) {
     // No need to do anything, we are waiting for a ServerWelcome message
     info!("successful client connection");
 }
 
+/// The server is about to drop us, remember why
+fn on_server_disconnect_notice(
+    mut disconnect_notice_events: ResMut<Events<ClientReceiveMessage<ServerDisconnectNotice>>>,
+    mut disconnection: ResMut<Disconnection>,
+) {
+    for ev in disconnect_notice_events.drain() {
+        disconnection.pending = Some(ev.message.reason);
+    }
+}
+
 fn on_client_disconnect(
     _trigger: Trigger<ClientDisconnectEvent>,
     mut game_state: ResMut<NextState<GameState>>,
+    mut disconnection: ResMut<Disconnection>,
+    mut reconnect: ResMut<Reconnect>,
 ) {
-    // TODO: Cleanup existing state?
+    let reason = if disconnection.requested {
+        None
+    } else {
+        Some(
+            disconnection
+                .pending
+                .take()
+                .unwrap_or(DisconnectReason::Timeout),
+        )
+    };
+
+    info!("disconnected from server: {:?}", reason);
+
+    // Anything else is deliberate on one side or the other, so trying again won't help
+    if reason == Some(DisconnectReason::Timeout) && reconnect.attempts < MAX_RECONNECT_ATTEMPTS {
+        let delay = RECONNECT_BASE_DELAY * 2u32.pow(reconnect.attempts);
+        reconnect.attempts += 1;
+        reconnect.timer = Some(Timer::new(delay, TimerMode::Once));
+    } else {
+        reconnect.attempts = 0;
+        reconnect.timer = None;
+    }
+
+    disconnection.requested = false;
+    disconnection.last_reason = reason;
+
+    // Leaving the session cleans up after it, whichever state we disconnected from
     game_state.set(GameState::MainMenu);
 }
+
+fn tick_reconnect(
+    time: Res<Time>,
+    mut reconnect: ResMut<Reconnect>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(timer) = reconnect.timer.as_mut() else {
+        return;
+    };
+
+    if timer.tick(time.delta()).just_finished() {
+        info!("reconnecting, attempt {}", reconnect.attempts);
+        reconnect.timer = None;
+        next_state.set(GameState::ConnectingRemote);
+    }
+}
+
+fn reset_reconnect_attempts(mut reconnect: ResMut<Reconnect>) {
+    reconnect.attempts = 0;
+}
@@ -0,0 +1,43 @@
+use avian3d::prelude::Position;
+use bevy::prelude::*;
+use lightyear::{client::prediction::rollback::is_in_rollback, prelude::client::Correction};
+
+/// Counts rollbacks and corrections of the prediction, for the netcode HUD and bots
+pub struct PredictionPlugin;
+
+impl Plugin for PredictionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PredictionCounts>()
+            .add_systems(FixedPostUpdate, note_rollback_tick.run_if(is_in_rollback))
+            .add_systems(Last, (count_rollbacks, count_corrections));
+    }
+}
+
+/// Since the client started, read them as differences to count over a window
+#[derive(Resource, Default, Debug)]
+pub struct PredictionCounts {
+    /// Frames in which lightyear rolled back
+    pub rollbacks: u32,
+    /// Predicted entities a rollback moved away from where we had them
+    pub corrections: u32,
+    rolled_back_this_frame: bool,
+}
+
+/// A rollback resimulates several ticks within one frame, so count frames rather than ticks
+fn note_rollback_tick(mut counts: ResMut<PredictionCounts>) {
+    counts.rolled_back_this_frame = true;
+}
+
+fn count_rollbacks(mut counts: ResMut<PredictionCounts>) {
+    if counts.rolled_back_this_frame {
+        counts.rolled_back_this_frame = false;
+        counts.rollbacks += 1;
+    }
+}
+
+fn count_corrections(
+    mut counts: ResMut<PredictionCounts>,
+    q_corrections: Query<(), Added<Correction<Position>>>,
+) {
+    counts.corrections += q_corrections.iter().count() as u32;
+}
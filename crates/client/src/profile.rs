@@ -0,0 +1,78 @@
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+use protocol::component::MAX_PLAYER_NAME_LENGTH;
+use serde::{Deserialize, Serialize};
+
+/// What we tell servers about ourselves, kept in a file between runs when
+/// `ProfileConfig::path` is set
+pub struct ProfilePlugin;
+
+impl Plugin for ProfilePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ProfileConfig>()
+            .init_resource::<Profile>()
+            .add_systems(Startup, load_profile)
+            .add_systems(
+                Update,
+                save_profile
+                    .run_if(resource_changed::<Profile>.and(not(resource_added::<Profile>))),
+            );
+    }
+}
+
+/// Insert before startup to load the profile from, and save it to, this file
+#[derive(Resource, Default, Debug)]
+pub struct ProfileConfig {
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Profile {
+    /// Sent in `ClientProfile`. The server may change it to keep names unique.
+    pub name: String,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: String::from("Player"),
+        }
+    }
+}
+
+impl Profile {
+    /// Longer names are cut short by the server anyway
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name.chars().take(MAX_PLAYER_NAME_LENGTH).collect();
+    }
+}
+
+fn load_profile(config: Res<ProfileConfig>, mut profile: ResMut<Profile>) {
+    let Some(path) = &config.path else {
+        return;
+    };
+
+    match fs::read_to_string(path) {
+        Ok(contents) => match ron::from_str::<Profile>(&contents) {
+            Ok(loaded) => *profile = loaded,
+            Err(e) => warn!("unable to parse profile {:?}, had error {}", path, e),
+        },
+        // First run, it is written once something changes
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => warn!("unable to read profile {:?}, had error {}", path, e),
+    }
+}
+
+fn save_profile(config: Res<ProfileConfig>, profile: Res<Profile>) {
+    let Some(path) = &config.path else {
+        return;
+    };
+
+    let result = ron::ser::to_string_pretty(&*profile, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())
+        .and_then(|contents| fs::write(path, contents).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        warn!("unable to save profile {:?}, had error {}", path, e);
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+
+use assets::{CurrentLevel, LevelState};
+use avian3d::prelude::{Position, Rotation};
+use bevy::{color::palettes::tailwind::SLATE_800, prelude::*};
+use common::Replayed;
+use leafwing_input_manager::{
+    Actionlike,
+    plugin::InputManagerPlugin,
+    prelude::{ActionState, InputMap},
+};
+use protocol::{
+    component::{Npc, Player},
+    replay::{EntitySnapshot, Replay, SnapshotKind},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::game_state::{GameState, InSession};
+
+/// Plays a recorded session back through the usual rendering, instead of connecting anywhere
+pub struct ReplayPlugin {
+    pub replay: Replay,
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(InputManagerPlugin::<ReplayInput>::default())
+            .insert_resource(ReplayPlayback {
+                replay: self.replay.clone(),
+                position: 0.0,
+                speed: 1.0,
+                paused: false,
+            })
+            .init_resource::<ReplayEntities>()
+            .add_systems(Startup, start_replay)
+            .add_systems(
+                OnEnter(GameState::Replay),
+                (load_replay_level, spawn_replay_controls),
+            )
+            .add_systems(OnExit(InSession), stop_replay)
+            .add_systems(
+                Update,
+                (
+                    handle_replay_input,
+                    advance_playback,
+                    apply_replay_frame,
+                    update_replay_text,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Replay).and(in_state(LevelState::Loaded))),
+            );
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash, Reflect, Actionlike)]
+pub enum ReplayInput {
+    #[actionlike(Button)]
+    TogglePause,
+    #[actionlike(Button)]
+    SeekBack,
+    #[actionlike(Button)]
+    SeekForward,
+    #[actionlike(Button)]
+    SlowDown,
+    #[actionlike(Button)]
+    SpeedUp,
+    #[actionlike(Button)]
+    Quit,
+}
+
+/// How far the playback is, in frames, and how it moves on
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    /// Between two frames when not a whole number, which are then blended
+    pub position: f32,
+    pub speed: f32,
+    pub paused: bool,
+}
+
+impl ReplayPlayback {
+    fn last_frame(&self) -> f32 {
+        self.replay.frames.len().saturating_sub(1) as f32
+    }
+
+    fn secs_per_frame(&self) -> f32 {
+        self.replay.header.tick_duration.as_secs_f32()
+    }
+
+    /// Jump by `secs` of recorded time, staying within the recording
+    pub fn seek(&mut self, secs: f32) {
+        self.position =
+            (self.position + secs / self.secs_per_frame()).clamp(0.0, self.last_frame());
+    }
+}
+
+/// The entity standing in for each recorded server entity
+#[derive(Resource, Default)]
+struct ReplayEntities(HashMap<u64, Entity>);
+
+#[derive(Component)]
+struct ReplayText;
+
+const SEEK_SECS: f32 = 5.0;
+const MIN_SPEED: f32 = 0.125;
+const MAX_SPEED: f32 = 8.0;
+
+fn start_replay(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Replay);
+}
+
+fn load_replay_level(mut current_level: ResMut<CurrentLevel>, playback: Res<ReplayPlayback>) {
+    current_level.0 = playback.replay.header.level;
+}
+
+fn spawn_replay_controls(mut commands: Commands) {
+    commands.spawn((
+        InputMap::<ReplayInput>::default()
+            .with(ReplayInput::TogglePause, KeyCode::Space)
+            .with(ReplayInput::SeekBack, KeyCode::ArrowLeft)
+            .with(ReplayInput::SeekForward, KeyCode::ArrowRight)
+            .with(ReplayInput::SlowDown, KeyCode::ArrowDown)
+            .with(ReplayInput::SpeedUp, KeyCode::ArrowUp)
+            .with(ReplayInput::Quit, KeyCode::Escape),
+        ActionState::<ReplayInput>::default(),
+        StateScoped(InSession),
+    ));
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.),
+                bottom: Val::Px(10.),
+                padding: UiRect::all(Val::Px(8.)),
+                ..default()
+            },
+            BackgroundColor(SLATE_800.with_alpha(0.8).into()),
+            PickingBehavior::IGNORE,
+            StateScoped(InSession),
+        ))
+        .with_children(|hud| {
+            hud.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 14.,
+                    ..default()
+                },
+                ReplayText,
+            ));
+        });
+}
+
+fn stop_replay(mut replay_entities: ResMut<ReplayEntities>) {
+    replay_entities.0.clear();
+}
+
+fn handle_replay_input(
+    q_replay_input: Query<&ActionState<ReplayInput>>,
+    mut playback: ResMut<ReplayPlayback>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    for input in &q_replay_input {
+        if input.just_pressed(&ReplayInput::TogglePause) {
+            // Starting again from the end plays the recording from the start
+            if playback.paused && playback.position >= playback.last_frame() {
+                playback.position = 0.0;
+            }
+            playback.paused = !playback.paused;
+        }
+        if input.just_pressed(&ReplayInput::SeekBack) {
+            playback.seek(-SEEK_SECS);
+        }
+        if input.just_pressed(&ReplayInput::SeekForward) {
+            playback.seek(SEEK_SECS);
+        }
+        if input.just_pressed(&ReplayInput::SlowDown) {
+            playback.speed = (playback.speed / 2.0).max(MIN_SPEED);
+        }
+        if input.just_pressed(&ReplayInput::SpeedUp) {
+            playback.speed = (playback.speed * 2.0).min(MAX_SPEED);
+        }
+        if input.just_pressed(&ReplayInput::Quit) {
+            app_exit.send(AppExit::Success);
+        }
+    }
+}
+
+fn advance_playback(time: Res<Time>, mut playback: ResMut<ReplayPlayback>) {
+    if playback.paused {
+        return;
+    }
+
+    let secs = time.delta_secs() * playback.speed;
+    playback.seek(secs);
+
+    if playback.position >= playback.last_frame() {
+        playback.paused = true;
+    }
+}
+
+/// Where an entity is between two frames, or where it last was if it is gone in the next one
+fn blend(snapshot: &EntitySnapshot, next: Option<&EntitySnapshot>, t: f32) -> (Vec3, Quat) {
+    match next {
+        Some(next) => (
+            snapshot.position.lerp(next.position, t),
+            snapshot.rotation.slerp(next.rotation, t),
+        ),
+        None => (snapshot.position, snapshot.rotation),
+    }
+}
+
+fn apply_replay_frame(
+    mut commands: Commands,
+    playback: Res<ReplayPlayback>,
+    mut replay_entities: ResMut<ReplayEntities>,
+    mut q_replayed: Query<(&mut Position, &mut Rotation, &mut Transform), With<Replayed>>,
+) {
+    let frames = &playback.replay.frames;
+    let index = playback.position.floor() as usize;
+    let Some(frame) = frames.get(index) else {
+        return;
+    };
+    let next_frame = frames.get(index + 1);
+    let t = playback.position.fract();
+
+    for snapshot in &frame.entities {
+        let next = next_frame.and_then(|next_frame| {
+            next_frame
+                .entities
+                .iter()
+                .find(|next| next.id == snapshot.id)
+        });
+        let (position, rotation) = blend(snapshot, next, t);
+
+        if let Some(entity) = replay_entities.0.get(&snapshot.id) {
+            if let Ok((mut current_position, mut current_rotation, mut transform)) =
+                q_replayed.get_mut(*entity)
+            {
+                current_position.0 = position;
+                current_rotation.0 = rotation;
+                transform.translation = position;
+                transform.rotation = rotation;
+            }
+            continue;
+        }
+
+        let mut entity = commands.spawn((
+            Position(position),
+            Rotation(rotation),
+            Transform::from_translation(position).with_rotation(rotation),
+            Replayed,
+            StateScoped(InSession),
+        ));
+        match snapshot.kind {
+            SnapshotKind::Player(client_id) => {
+                entity.insert((
+                    Player(client_id),
+                    Name::new(format!("Player {}", client_id)),
+                ));
+            }
+            SnapshotKind::Npc => {
+                entity.insert((Npc, Name::new("Npc")));
+            }
+        }
+        replay_entities.0.insert(snapshot.id, entity.id());
+    }
+
+    // Whatever isn't in this frame had not spawned yet, or was already despawned
+    replay_entities.0.retain(|id, entity| {
+        let present = frame.entities.iter().any(|snapshot| snapshot.id == *id);
+        if !present {
+            commands.entity(*entity).despawn_recursive();
+        }
+        present
+    });
+}
+
+fn update_replay_text(
+    playback: Res<ReplayPlayback>,
+    mut q_replay_text: Query<&mut Text, With<ReplayText>>,
+) {
+    let secs_per_frame = playback.secs_per_frame();
+    let state = if playback.paused { "paused" } else { "playing" };
+
+    let message = format!(
+        "replay {:.1} / {:.1} s, {}x, {}\nspace pause, left/right seek, up/down speed, esc quit",
+        playback.position * secs_per_frame,
+        playback.last_frame() * secs_per_frame,
+        playback.speed,
+        state
+    );
+
+    for mut text in &mut q_replay_text {
+        if text.0 != message {
+            text.0 = message.clone();
+        }
+    }
+}
@@ -1,5 +1,9 @@
-use crate::game_state::GameState;
-use assets::{CurrentLevel, LevelState};
+use crate::{
+    game_state::GameState, network::DisconnectWithReasonExt, profile::Profile, spectator::Spectate,
+};
+use assets::{
+    AssetPath, CurrentLevel, LevelState, hash_level_assets, level_asset_paths, mismatched_assets,
+};
 use bevy::prelude::*;
 use lightyear::prelude::{
     client::{ClientCommandsExt, ClientConnection, NetClient},
@@ -7,7 +11,11 @@ use lightyear::prelude::{
 };
 use protocol::{
     component::Player,
-    message::{ClientLevelLoadComplete, ServerWelcome, UnorderedReliable},
+    fingerprint::ProtocolFingerprint,
+    message::{
+        ClientLevelLoadComplete, ClientProfile, DisconnectReason, ServerHello, ServerLevelReload,
+        ServerWelcome, UnorderedReliable,
+    },
 };
 
 pub struct ReplicationPlugin;
@@ -16,10 +24,14 @@ impl Plugin for ReplicationPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             Update,
-            on_server_welcome.run_if(in_state(GameState::ConnectingRemote)),
+            (on_server_hello, on_server_welcome).run_if(in_state(GameState::ConnectingRemote)),
         );
         app.add_systems(Update, await_spawn.run_if(in_state(GameState::Spawning)));
-        app.add_systems(OnEnter(LevelState::Loaded), on_assets_loaded);
+        app.add_systems(Update, on_server_level_reload);
+        app.add_systems(
+            OnEnter(LevelState::Loaded),
+            on_assets_loaded.run_if(in_state(GameState::Loading)),
+        );
     }
 }
 
@@ -27,29 +39,115 @@ impl Plugin for ReplicationPlugin {
 #[derive(Component)]
 pub struct LocalPlayer;
 
+/// Leave if the server was built from a different protocol, since nothing else it sends can be
+/// read. Otherwise tell it who we are and whether we came to watch, which it needs to find us a
+/// slot before welcoming us.
+fn on_server_hello(
+    mut commands: Commands,
+    mut server_hello_events: EventReader<ClientReceiveMessage<ServerHello>>,
+    mut client: ResMut<ClientConnectionManager>,
+    protocol_fingerprint: Res<ProtocolFingerprint>,
+    profile: Res<Profile>,
+    spectate: Res<Spectate>,
+) {
+    for ev in server_hello_events.read() {
+        let server_fingerprint = ev.message.protocol_fingerprint;
+
+        if server_fingerprint != **protocol_fingerprint {
+            warn!(
+                "server protocol fingerprint {:x} does not match ours, {:x}",
+                server_fingerprint, **protocol_fingerprint
+            );
+            commands.disconnect_with_reason(DisconnectReason::ProtocolMismatch {
+                server: server_fingerprint,
+                client: **protocol_fingerprint,
+            });
+            return;
+        }
+
+        if let Err(e) = client.send_message::<UnorderedReliable, ClientProfile>(&ClientProfile {
+            name: profile.name.clone(),
+            spectator: spectate.0,
+        }) {
+            error!("unable to send profile, had error {}", e);
+        }
+    }
+}
+
 /// Once finished loading the assets that the server requested the client to load
-/// Signal the completion to the server
-fn on_assets_loaded(mut commands: Commands, mut client: ResMut<ClientConnectionManager>) {
-    commands.set_state(GameState::Spawning);
+/// Signal the completion to the server, which adds us to the lobby, or to its spectators
+fn on_assets_loaded(
+    mut commands: Commands,
+    mut client: ResMut<ClientConnectionManager>,
+    spectate: Res<Spectate>,
+) {
+    if spectate.0 {
+        commands.set_state(GameState::Spectating);
+    } else {
+        commands.set_state(GameState::Lobby);
+    }
 
     if let Err(e) =
         client.send_message::<UnorderedReliable, ClientLevelLoadComplete>(&ClientLevelLoadComplete)
     {
         println!("unable to signal client level load complete due to {}", e);
-        commands.disconnect_client();
+        commands.disconnect_with_reason(DisconnectReason::LoadFailure(e.to_string()));
     }
 }
 
-/// Respond to the welcome message from the server by initiating a load of the level requested
+/// Respond to the welcome message from the server with a load of the level requested, or
+/// disconnect if the server has different assets. A different protocol is refused earlier,
+/// on the `ServerHello`.
 fn on_server_welcome(
+    mut commands: Commands,
     mut server_welcome_events: ResMut<Events<ClientReceiveMessage<ServerWelcome>>>,
     game_state: Res<State<GameState>>,
     mut current_level: ResMut<CurrentLevel>,
     mut next_state: ResMut<NextState<GameState>>,
+    asset_path: Res<AssetPath>,
 ) {
     for ev in server_welcome_events.drain() {
+        let welcome = ev.message;
+
+        let our_hashes = hash_level_assets(&asset_path, welcome.current_level);
+        let mismatched = mismatched_assets(&our_hashes, &welcome.level_asset_hashes);
+
+        // On the web there are no local files to hash, so trust the server
+        if !our_hashes.is_empty() && !mismatched.is_empty() {
+            warn!("assets differ from the server's: {:?}", mismatched);
+            commands.disconnect_with_reason(DisconnectReason::VersionMismatch(format!(
+                "your game files differ from the server's: {}",
+                mismatched.join(", ")
+            )));
+            return;
+        }
+
         next_state.set(GameState::Loading);
-        current_level.0 = ev.message.current_level;
+        current_level.0 = welcome.current_level;
+    }
+}
+
+/// The server rebuilt its level after the files changed. Reload ours so that
+/// the asset plugin rebuilds it too, which only helps if we share the same files.
+fn on_server_level_reload(
+    mut level_reload_events: ResMut<Events<ClientReceiveMessage<ServerLevelReload>>>,
+    asset_server: Res<AssetServer>,
+    current_level: Res<CurrentLevel>,
+    asset_path: Res<AssetPath>,
+) {
+    for ev in level_reload_events.drain() {
+        for path in level_asset_paths(**current_level) {
+            asset_server.reload(*path);
+        }
+
+        let our_hashes = hash_level_assets(&asset_path, **current_level);
+        let mismatched = mismatched_assets(&our_hashes, &ev.message.level_asset_hashes);
+        if !our_hashes.is_empty() && !mismatched.is_empty() {
+            warn!(
+                "server reloaded the level, but our assets still differ: {:?}",
+                mismatched
+            );
+        }
     }
 }
 
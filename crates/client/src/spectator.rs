@@ -0,0 +1,211 @@
+use avian3d::prelude::Position;
+use bevy::{color::palettes::tailwind::SLATE_800, prelude::*};
+use leafwing_input_manager::{
+    Actionlike,
+    plugin::InputManagerPlugin,
+    prelude::{ActionState, InputMap, VirtualDPad},
+};
+use lightyear::prelude::{ClientId, client::Interpolated};
+use protocol::component::{ClientNames, Player};
+use render::default_camera_transform;
+use serde::{Deserialize, Serialize};
+
+use crate::game_state::{GameState, InSession};
+
+/// Watches the match without a player: every entity arrives interpolated, and the camera
+/// flies freely or follows one player after another
+pub struct SpectatorPlugin;
+
+impl Plugin for SpectatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(InputManagerPlugin::<SpectatorInput>::default())
+            .init_resource::<Spectate>()
+            .init_resource::<SpectatorCamera>()
+            .add_systems(OnEnter(GameState::Spectating), spawn_spectator_controls)
+            .add_systems(OnExit(GameState::Spectating), reset_spectator_camera)
+            .add_systems(
+                Update,
+                (
+                    cycle_followed_player,
+                    move_spectator_camera,
+                    update_spectator_text,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Spectating)),
+            );
+    }
+}
+
+/// Whether the next connection joins as a spectator. Chosen in the main menu.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Spectate(pub bool);
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash, Reflect, Actionlike)]
+pub enum SpectatorInput {
+    /// Flies the free camera along the ground
+    #[actionlike(DualAxis)]
+    Move,
+    #[actionlike(Button)]
+    Ascend,
+    #[actionlike(Button)]
+    Descend,
+    #[actionlike(Button)]
+    NextPlayer,
+    #[actionlike(Button)]
+    PreviousPlayer,
+}
+
+/// The player the camera follows, or `None` for the free camera
+#[derive(Resource, Default, Debug)]
+pub struct SpectatorCamera {
+    pub following: Option<ClientId>,
+}
+
+#[derive(Component)]
+struct SpectatorText;
+
+const FREE_FLY_SPEED: f32 = 40.0;
+
+/// Where the camera sits relative to the player it follows
+const FOLLOW_OFFSET: Vec3 = Vec3::new(0.0, 30.0, 40.0);
+
+fn spawn_spectator_controls(mut commands: Commands, mut camera: ResMut<SpectatorCamera>) {
+    camera.following = None;
+
+    commands.spawn((
+        InputMap::<SpectatorInput>::default()
+            .with_dual_axis(SpectatorInput::Move, VirtualDPad::wasd())
+            .with(SpectatorInput::Ascend, KeyCode::KeyE)
+            .with(SpectatorInput::Descend, KeyCode::KeyQ)
+            .with(SpectatorInput::NextPlayer, KeyCode::ArrowRight)
+            .with(SpectatorInput::PreviousPlayer, KeyCode::ArrowLeft),
+        ActionState::<SpectatorInput>::default(),
+        StateScoped(InSession),
+    ));
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.),
+                bottom: Val::Px(10.),
+                padding: UiRect::all(Val::Px(8.)),
+                ..default()
+            },
+            BackgroundColor(SLATE_800.with_alpha(0.8).into()),
+            PickingBehavior::IGNORE,
+            StateScoped(InSession),
+        ))
+        .with_children(|hud| {
+            hud.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 14.,
+                    ..default()
+                },
+                SpectatorText,
+            ));
+        });
+}
+
+fn reset_spectator_camera(
+    mut camera: ResMut<SpectatorCamera>,
+    mut q_camera: Query<&mut Transform, With<Camera3d>>,
+) {
+    camera.following = None;
+    for mut transform in &mut q_camera {
+        *transform = default_camera_transform();
+    }
+}
+
+/// Steps through the free camera and then every player, in client id order
+fn cycle_followed_player(
+    q_spectator_input: Query<&ActionState<SpectatorInput>>,
+    q_players: Query<&Player, With<Interpolated>>,
+    mut camera: ResMut<SpectatorCamera>,
+) {
+    for input in &q_spectator_input {
+        let step: isize = match (
+            input.just_pressed(&SpectatorInput::NextPlayer),
+            input.just_pressed(&SpectatorInput::PreviousPlayer),
+        ) {
+            (true, false) => 1,
+            (false, true) => -1,
+            _ => continue,
+        };
+
+        let mut targets: Vec<Option<ClientId>> =
+            q_players.iter().map(|player| Some(player.0)).collect();
+        targets.sort_by_key(|target| target.map(|client_id| client_id.to_bits()));
+        targets.insert(0, None);
+
+        let current = targets
+            .iter()
+            .position(|target| *target == camera.following)
+            .unwrap_or(0);
+        let next = (current as isize + step).rem_euclid(targets.len() as isize) as usize;
+        camera.following = targets[next];
+    }
+}
+
+fn move_spectator_camera(
+    time: Res<Time>,
+    q_spectator_input: Query<&ActionState<SpectatorInput>>,
+    q_players: Query<(&Player, &Position), With<Interpolated>>,
+    mut camera: ResMut<SpectatorCamera>,
+    mut q_camera: Query<&mut Transform, With<Camera3d>>,
+) {
+    if let Some(following) = camera.following {
+        let Some((_, position)) = q_players.iter().find(|(player, _)| player.0 == following) else {
+            // They left, or went out of sight
+            camera.following = None;
+            return;
+        };
+
+        for mut transform in &mut q_camera {
+            *transform = Transform::from_translation(position.0 + FOLLOW_OFFSET)
+                .looking_at(position.0, Vec3::Y);
+        }
+        return;
+    }
+
+    for input in &q_spectator_input {
+        let movement = input.axis_pair(&SpectatorInput::Move);
+        let vertical = input.pressed(&SpectatorInput::Ascend) as i8 as f32
+            - input.pressed(&SpectatorInput::Descend) as i8 as f32;
+
+        for mut transform in &mut q_camera {
+            let forward = transform.forward().with_y(0.0).normalize_or_zero();
+            let right = transform.right().with_y(0.0).normalize_or_zero();
+            let direction = (right * movement.x + forward * movement.y + Vec3::Y * vertical)
+                .normalize_or_zero();
+
+            transform.translation += direction * FREE_FLY_SPEED * time.delta_secs();
+        }
+    }
+}
+
+fn update_spectator_text(
+    camera: Res<SpectatorCamera>,
+    q_names: Query<&ClientNames>,
+    mut q_spectator_text: Query<&mut Text, With<SpectatorText>>,
+) {
+    let no_names = ClientNames::default();
+    let names = q_names.iter().next().unwrap_or(&no_names);
+
+    let message = match camera.following {
+        Some(client_id) => format!(
+            "spectating {}\nleft/right switch player",
+            names.label(client_id)
+        ),
+        None => String::from(
+            "spectating, free camera\nwasd move, q/e down/up, left/right follow a player",
+        ),
+    };
+
+    for mut text in &mut q_spectator_text {
+        if text.0 != message {
+            text.0 = message.clone();
+        }
+    }
+}
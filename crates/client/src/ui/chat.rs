@@ -0,0 +1,214 @@
+use bevy::{
+    color::palettes::tailwind::{AMBER_300, SLATE_800},
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+};
+use lightyear::prelude::{
+    ClientId,
+    client::{ClientConnection, NetClient},
+};
+use protocol::{
+    component::ClientNames,
+    message::{ChatChannel, MAX_CHAT_LENGTH, ServerChat},
+};
+
+use crate::{
+    chat::{ChatFocus, ChatHistory, ChatInput, SendChatExt, parse_chat_input},
+    game_state::{GameState, InSession},
+    ui::system_menu::SystemMenuState,
+};
+
+/// Recent chat in the corner of the screen. Enter starts typing, Enter again sends,
+/// Escape gives up.
+pub struct ChatUiPlugin;
+
+impl Plugin for ChatUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(InSession), spawn_chat_box)
+            .add_systems(
+                PreUpdate,
+                type_chat_message.in_set(ChatFocus).run_if(
+                    in_state(GameState::Lobby)
+                        .or(in_state(GameState::Playing))
+                        .or(in_state(GameState::Spectating)),
+                ),
+            )
+            .add_systems(
+                Update,
+                (update_chat_history_text, update_chat_input_text)
+                    .chain()
+                    .run_if(
+                        in_state(GameState::Lobby)
+                            .or(in_state(GameState::Playing))
+                            .or(in_state(GameState::Spectating)),
+                    ),
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct ChatHistoryText;
+
+#[derive(Component)]
+pub struct ChatInputText;
+
+/// Lines of history shown at once
+const VISIBLE_CHAT_LINES: usize = 8;
+
+const CHAT_FONT_SIZE: f32 = 14.;
+
+fn spawn_chat_box(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.),
+                bottom: Val::Px(60.),
+                max_width: Val::Px(400.),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            GlobalZIndex(1),
+            PickingBehavior::IGNORE,
+            StateScoped(InSession),
+        ))
+        .with_children(|chat_box| {
+            chat_box.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: CHAT_FONT_SIZE,
+                    ..default()
+                },
+                ChatHistoryText,
+            ));
+
+            chat_box.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: CHAT_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(AMBER_300.into()),
+                Node {
+                    padding: UiRect::all(Val::Px(4.)),
+                    ..default()
+                },
+                BackgroundColor(SLATE_800.with_alpha(0.8).into()),
+                Visibility::Hidden,
+                ChatInputText,
+            ));
+        });
+}
+
+fn type_chat_message(
+    mut commands: Commands,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    system_menu_state: Res<State<SystemMenuState>>,
+    mut chat_input: ResMut<ChatInput>,
+    mut history: ResMut<ChatHistory>,
+) {
+    for ev in keyboard_events.read() {
+        if ev.state != ButtonState::Pressed {
+            continue;
+        }
+
+        if !chat_input.focused {
+            if ev.logical_key == Key::Enter && *system_menu_state.get() == SystemMenuState::Closed {
+                chat_input.focused = true;
+            }
+            continue;
+        }
+
+        match &ev.logical_key {
+            Key::Enter => {
+                match parse_chat_input(&chat_input.text) {
+                    Ok(message) => commands.send_chat(message),
+                    // Only we see this, the server never heard of it
+                    Err(e) => history.push(ServerChat {
+                        channel: ChatChannel::System,
+                        from: None,
+                        text: e,
+                    }),
+                }
+                *chat_input = ChatInput::default();
+            }
+            Key::Escape => *chat_input = ChatInput::default(),
+            Key::Backspace => {
+                chat_input.text.pop();
+            }
+            Key::Space => chat_input.text.push(' '),
+            Key::Character(characters) => chat_input.text.push_str(characters),
+            _ => {}
+        }
+
+        if chat_input.text.chars().count() > MAX_CHAT_LENGTH {
+            chat_input.text = chat_input.text.chars().take(MAX_CHAT_LENGTH).collect();
+        }
+    }
+}
+
+fn chat_line(message: &ServerChat, local_client_id: ClientId, names: &ClientNames) -> String {
+    let from = message
+        .from
+        .map(|client_id| names.label(client_id))
+        .unwrap_or_default();
+
+    match message.channel {
+        ChatChannel::All => format!("{}: {}", from, message.text),
+        ChatChannel::Team => format!("[team] {}: {}", from, message.text),
+        ChatChannel::Whisper(to) if message.from == Some(local_client_id) => {
+            format!("[to {}] {}", names.label(to), message.text)
+        }
+        ChatChannel::Whisper(_) => format!("[from {}] {}", from, message.text),
+        ChatChannel::System => format!("[server] {}", message.text),
+    }
+}
+
+fn update_chat_history_text(
+    history: Res<ChatHistory>,
+    client: Res<ClientConnection>,
+    q_names: Query<Ref<ClientNames>>,
+    mut q_history_text: Query<(&mut Text, Ref<ChatHistoryText>)>,
+) {
+    let no_names = ClientNames::default();
+    let names = q_names.iter().next();
+    let names_changed = names.as_ref().is_some_and(Ref::is_changed);
+    let names = names.as_deref().unwrap_or(&no_names);
+
+    for (mut text, marker) in &mut q_history_text {
+        if !history.is_changed() && !marker.is_added() && !names_changed {
+            continue;
+        }
+
+        let skip = history.0.len().saturating_sub(VISIBLE_CHAT_LINES);
+        let lines: Vec<String> = history
+            .0
+            .iter()
+            .skip(skip)
+            .map(|message| chat_line(message, client.id(), names))
+            .collect();
+
+        text.0 = lines.join("\n");
+    }
+}
+
+fn update_chat_input_text(
+    chat_input: Res<ChatInput>,
+    mut q_input_text: Query<(&mut Text, &mut Visibility), With<ChatInputText>>,
+) {
+    if !chat_input.is_changed() {
+        return;
+    }
+
+    for (mut text, mut visibility) in &mut q_input_text {
+        text.0 = format!("> {}_", chat_input.text);
+        *visibility = if chat_input.focused {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
@@ -0,0 +1,203 @@
+use bevy::{
+    color::palettes::tailwind::{GREEN_400, SLATE_400, SLATE_700, SLATE_800},
+    prelude::*,
+};
+use lightyear::prelude::client::ClientConnection;
+use protocol::component::{ClientNames, LobbyRoster, MatchState, MatchStatus};
+
+use crate::{
+    game_state::GameState,
+    lobby::{QueuePosition, SetReadyExt, local_lobby_member},
+};
+
+pub struct LobbyUiPlugin;
+
+impl Plugin for LobbyUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Lobby), spawn_lobby_ui)
+            .add_systems(OnExit(GameState::Lobby), despawn_lobby_ui)
+            .add_systems(
+                Update,
+                (
+                    update_lobby_status_text,
+                    update_lobby_roster_list,
+                    update_ready_button,
+                )
+                    .run_if(in_state(GameState::Lobby)),
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct LobbyUi;
+
+#[derive(Component)]
+pub struct LobbyStatusText;
+
+#[derive(Component)]
+pub struct LobbyRosterList;
+
+#[derive(Component)]
+pub struct ReadyButton;
+
+const READY_BUTTON_COLOR: Srgba = SLATE_700;
+
+fn spawn_lobby_ui(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            BackgroundColor(SLATE_800.into()),
+            LobbyUi,
+        ))
+        .with_children(|child_builder| {
+            child_builder.spawn((
+                Text::new("Lobby"),
+                TextFont {
+                    font_size: 30.,
+                    ..default()
+                },
+                Node {
+                    padding: UiRect::bottom(Val::Px(20.)),
+                    ..default()
+                },
+                LobbyStatusText,
+            ));
+
+            child_builder.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Stretch,
+                    min_width: Val::Px(300.),
+                    padding: UiRect::bottom(Val::Px(20.)),
+                    row_gap: Val::Px(4.),
+                    ..default()
+                },
+                LobbyRosterList,
+            ));
+
+            child_builder
+                .spawn((
+                    Text::new("Ready"),
+                    Node {
+                        padding: UiRect::all(Val::Px(10.)),
+                        ..default()
+                    },
+                    BackgroundColor(READY_BUTTON_COLOR.into()),
+                    ReadyButton,
+                ))
+                .observe(
+                    |_click: Trigger<Pointer<Click>>,
+                     mut commands: Commands,
+                     q_roster: Query<&LobbyRoster>,
+                     client: Res<ClientConnection>| {
+                        for roster in &q_roster {
+                            let ready = local_lobby_member(roster, &client)
+                                .is_some_and(|member| member.ready);
+                            commands.set_ready(!ready);
+                        }
+                    },
+                );
+        });
+}
+
+fn despawn_lobby_ui(mut commands: Commands, q_lobby_ui: Query<Entity, With<LobbyUi>>) {
+    for entity in &q_lobby_ui {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn update_lobby_status_text(
+    q_match_status: Query<&MatchStatus>,
+    queue_position: Res<QueuePosition>,
+    mut q_status_text: Query<&mut Text, With<LobbyStatusText>>,
+) {
+    let Some(status) = q_match_status.iter().next() else {
+        return;
+    };
+
+    let message = match (queue_position.0, status.state) {
+        (Some(queued), _) => format!(
+            "Server full, you are {} of {} in the queue",
+            queued.position, queued.queue_length
+        ),
+        (None, MatchState::Lobby) => String::from("Waiting for everyone to ready up"),
+        (None, MatchState::Countdown) => format!("Starting in {}", status.remaining_secs),
+        (None, MatchState::InProgress) => String::from("Joining the match"),
+        (None, MatchState::PostMatch) => format!("Next match in {}", status.remaining_secs),
+    };
+
+    for mut text in &mut q_status_text {
+        if text.0 != message {
+            text.0 = message.clone();
+        }
+    }
+}
+
+fn update_lobby_roster_list(
+    mut commands: Commands,
+    q_roster: Query<(Ref<LobbyRoster>, Ref<ClientNames>)>,
+    q_list: Query<(Entity, Ref<LobbyRosterList>)>,
+) {
+    let Some((roster, names)) = q_roster.iter().next() else {
+        return;
+    };
+
+    for (list, marker) in &q_list {
+        if !roster.is_changed() && !names.is_changed() && !marker.is_added() {
+            continue;
+        }
+
+        commands
+            .entity(list)
+            .despawn_descendants()
+            .with_children(|list| {
+                for member in &roster.0 {
+                    let (label, color) = if member.ready {
+                        ("Ready", GREEN_400)
+                    } else {
+                        ("Not ready", SLATE_400)
+                    };
+
+                    list.spawn(Node {
+                        justify_content: JustifyContent::SpaceBetween,
+                        column_gap: Val::Px(20.),
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        row.spawn(Text::new(format!(
+                            "{} (Team {})",
+                            names.label(member.client_id),
+                            member.team.0 + 1
+                        )));
+                        row.spawn((Text::new(label), TextColor(color.into())));
+                    });
+                }
+            });
+    }
+}
+
+fn update_ready_button(
+    q_roster: Query<&LobbyRoster>,
+    client: Res<ClientConnection>,
+    mut q_ready_button: Query<&mut Text, With<ReadyButton>>,
+) {
+    let ready = q_roster
+        .iter()
+        .filter_map(|roster| local_lobby_member(roster, &client))
+        .any(|member| member.ready);
+
+    let label = if ready { "Not ready" } else { "Ready" };
+
+    for mut text in &mut q_ready_button {
+        if text.0 != label {
+            text.0 = String::from(label);
+        }
+    }
+}
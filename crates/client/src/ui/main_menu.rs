@@ -1,7 +1,21 @@
-use bevy::{color::palettes::tailwind::SLATE_800, prelude::*};
+use bevy::{
+    color::palettes::tailwind::{RED_400, SLATE_600, SLATE_800},
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+};
 use lightyear::prelude::client::ClientCommandsExt;
+use protocol::component::MAX_PLAYER_NAME_LENGTH;
 
-use crate::game_state::GameState;
+use crate::{
+    game_state::GameState,
+    network::{Disconnection, MAX_RECONNECT_ATTEMPTS, Reconnect},
+    profile::Profile,
+    spectator::Spectate,
+    ui::server_browser::{ServerBrowser, spawn_server_browser},
+};
 
 pub struct MainMenuPlugin;
 
@@ -15,7 +29,14 @@ impl Plugin for MainMenuPlugin {
         );
 
         app.add_systems(OnEnter(GameState::Loading), on_client_begin_loading);
+        app.add_systems(
+            Update,
+            (update_reconnect_text, type_into_name_field).run_if(in_state(GameState::MainMenu)),
+        );
+        app.add_systems(OnEnter(GameState::Lobby), despawn_main_menu_ui);
         app.add_systems(OnEnter(GameState::Playing), despawn_main_menu_ui);
+        app.add_systems(OnEnter(GameState::Spectating), despawn_main_menu_ui);
+        app.add_systems(OnEnter(GameState::Replay), despawn_main_menu_ui);
     }
 }
 
@@ -28,7 +49,37 @@ pub struct MainMenuStatusText;
 #[derive(Component)]
 pub struct ConnectButton;
 
-fn spawn_main_menu_ui(mut commands: Commands, q_main_menu: Query<Entity, With<MainMenu>>) {
+#[derive(Component)]
+pub struct ReconnectText;
+
+/// Switches between joining as a player and as a spectator
+#[derive(Component)]
+pub struct SpectateToggle;
+
+/// Edits the name in our `Profile`. Enter keeps the change, Escape drops it.
+#[derive(Component, Default)]
+pub struct NameField {
+    text: String,
+    focused: bool,
+}
+
+const NAME_FIELD_COLOR: Srgba = SLATE_600;
+
+fn spectate_label(spectate: Spectate) -> String {
+    if spectate.0 {
+        String::from("Joining as: Spectator")
+    } else {
+        String::from("Joining as: Player")
+    }
+}
+
+fn spawn_main_menu_ui(
+    mut commands: Commands,
+    q_main_menu: Query<Entity, With<MainMenu>>,
+    disconnection: Res<Disconnection>,
+    spectate: Res<Spectate>,
+    profile: Res<Profile>,
+) {
     // Despawn any existing copies of the menu
     for entity in &q_main_menu {
         commands.entity(entity).despawn_recursive();
@@ -56,36 +107,143 @@ fn spawn_main_menu_ui(mut commands: Commands, q_main_menu: Query<Entity, With<Ma
                         ..default()
                     },
                     Node {
-                        padding: UiRect::bottom(Val::Px(200.)),
+                        padding: UiRect::bottom(Val::Px(100.)),
                         ..default()
                     },
                 ))
                 .insert(MainMenuStatusText);
 
+            if let Some(reason) = &disconnection.last_reason {
+                child_builder.spawn((
+                    Text::new(reason.to_string()),
+                    TextColor(RED_400.into()),
+                    Node {
+                        padding: UiRect::bottom(Val::Px(20.)),
+                        ..default()
+                    },
+                ));
+            }
+
+            child_builder.spawn((
+                Text::default(),
+                Node {
+                    padding: UiRect::bottom(Val::Px(20.)),
+                    ..default()
+                },
+                ReconnectText,
+            ));
+
             child_builder
                 .spawn((
-                    Text::new("Connect"),
+                    Text::new(format!("Name: {}", profile.name)),
+                    Node {
+                        padding: UiRect::all(Val::Px(6.)),
+                        margin: UiRect::bottom(Val::Px(20.)),
+                        ..default()
+                    },
+                    BackgroundColor(NAME_FIELD_COLOR.into()),
+                    NameField::default(),
+                ))
+                .observe(
+                    |click: Trigger<Pointer<Click>>,
+                     profile: Res<Profile>,
+                     mut q_fields: Query<&mut NameField>| {
+                        if let Ok(mut field) = q_fields.get_mut(click.entity()) {
+                            field.text = profile.name.clone();
+                            field.focused = true;
+                        }
+                    },
+                );
+
+            child_builder
+                .spawn((
+                    Text::new(spectate_label(*spectate)),
                     Node {
                         padding: UiRect::bottom(Val::Px(20.)),
                         ..default()
                     },
+                    SpectateToggle,
                 ))
-                .insert(ConnectButton)
-                .observe(|_click: Trigger<Pointer<Click>>, mut commands: Commands| {
-                    commands.set_state(GameState::ConnectingRemote);
-                });
+                .observe(
+                    |click: Trigger<Pointer<Click>>,
+                     mut spectate: ResMut<Spectate>,
+                     mut q_text: Query<&mut Text, With<SpectateToggle>>| {
+                        spectate.0 = !spectate.0;
+                        if let Ok(mut text) = q_text.get_mut(click.entity()) {
+                            text.0 = spectate_label(*spectate);
+                        }
+                    },
+                );
+
+            spawn_server_browser(child_builder);
         });
 }
 
 fn despawn_main_menu_buttons(
     mut commands: Commands,
-    q_connect_buttons: Query<Entity, With<ConnectButton>>,
+    q_connect_buttons: Query<
+        Entity,
+        Or<(
+            With<ConnectButton>,
+            With<ServerBrowser>,
+            With<SpectateToggle>,
+            With<NameField>,
+        )>,
+    >,
 ) {
     for entity in &q_connect_buttons {
         commands.entity(entity).despawn_recursive();
     }
 }
 
+fn type_into_name_field(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut q_fields: Query<(&mut NameField, &mut Text)>,
+    mut profile: ResMut<Profile>,
+) {
+    let events: Vec<KeyboardInput> = keyboard_events
+        .read()
+        .filter(|event| event.state == ButtonState::Pressed)
+        .cloned()
+        .collect();
+
+    for (mut field, mut text) in &mut q_fields {
+        if field.focused {
+            for event in &events {
+                match &event.logical_key {
+                    Key::Character(characters) => field.text.push_str(characters),
+                    Key::Space => field.text.push(' '),
+                    Key::Backspace => {
+                        field.text.pop();
+                    }
+                    Key::Escape => field.focused = false,
+                    Key::Enter => {
+                        field.focused = false;
+                        if field.text.trim() != profile.name {
+                            profile.set_name(field.text.trim());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            if field.text.chars().count() > MAX_PLAYER_NAME_LENGTH {
+                field.text = field.text.chars().take(MAX_PLAYER_NAME_LENGTH).collect();
+            }
+        }
+
+        let display = if field.focused {
+            format!("Name: {}_", field.text)
+        } else {
+            format!("Name: {}", profile.name)
+        };
+
+        if text.0 != display {
+            text.0 = display;
+        }
+    }
+}
+
 fn on_client_begin_loading(mut q_status_text: Query<&mut Text, With<MainMenuStatusText>>) {
     for mut text in q_status_text.iter_mut() {
         text.0 = String::from("Loading");
@@ -103,3 +261,24 @@ fn despawn_main_menu_ui(mut commands: Commands, q_main_menu: Query<Entity, With<
         commands.entity(entity).despawn_recursive();
     }
 }
+
+fn update_reconnect_text(
+    reconnect: Res<Reconnect>,
+    mut q_reconnect_text: Query<&mut Text, With<ReconnectText>>,
+) {
+    let message = match &reconnect.timer {
+        Some(timer) => format!(
+            "Reconnecting in {:.0}s (attempt {}/{})",
+            timer.remaining_secs().ceil(),
+            reconnect.attempts,
+            MAX_RECONNECT_ATTEMPTS
+        ),
+        None => String::new(),
+    };
+
+    for mut text in q_reconnect_text.iter_mut() {
+        if text.0 != message {
+            text.0 = message.clone();
+        }
+    }
+}
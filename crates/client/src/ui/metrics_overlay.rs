@@ -0,0 +1,130 @@
+use bevy::{color::palettes::tailwind::SLATE_800, prelude::*};
+use leafwing_input_manager::prelude::ActionState;
+use protocol::metrics::ConnectionMetrics;
+
+use crate::{game_state::GameState, input::LocalInput, metrics::NetworkMetrics};
+
+/// Debug overlay with the connection metrics from both ends, toggled with `LocalInput::NetworkMetrics`
+pub struct MetricsOverlayPlugin;
+
+impl Plugin for MetricsOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), spawn_metrics_overlay)
+            .add_systems(OnExit(GameState::Playing), despawn_metrics_overlay)
+            .add_systems(
+                Update,
+                (toggle_metrics_overlay, update_metrics_text).run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct MetricsOverlay;
+
+#[derive(Component)]
+pub struct MetricsText;
+
+fn spawn_metrics_overlay(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.),
+                bottom: Val::Px(10.),
+                padding: UiRect::all(Val::Px(8.)),
+                ..default()
+            },
+            BackgroundColor(SLATE_800.with_alpha(0.8).into()),
+            PickingBehavior::IGNORE,
+            Visibility::Hidden,
+            MetricsOverlay,
+        ))
+        .with_children(|overlay| {
+            overlay.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 14.,
+                    ..default()
+                },
+                MetricsText,
+            ));
+        });
+}
+
+fn despawn_metrics_overlay(
+    mut commands: Commands,
+    q_metrics_overlay: Query<Entity, With<MetricsOverlay>>,
+) {
+    for entity in &q_metrics_overlay {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn toggle_metrics_overlay(
+    q_local_inputs: Query<&ActionState<LocalInput>>,
+    mut q_overlay: Query<&mut Visibility, With<MetricsOverlay>>,
+) {
+    let toggled = q_local_inputs
+        .iter()
+        .any(|local_input| local_input.just_pressed(&LocalInput::NetworkMetrics));
+    if !toggled {
+        return;
+    }
+
+    for mut visibility in &mut q_overlay {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+fn describe(metrics: &ConnectionMetrics) -> String {
+    let mut lines = vec![
+        format!(
+            "  in  {:>8.0} B/s {:>5.0} pkt/s",
+            metrics.bytes_in_per_sec, metrics.packets_in_per_sec
+        ),
+        format!(
+            "  out {:>8.0} B/s {:>5.0} pkt/s",
+            metrics.bytes_out_per_sec, metrics.packets_out_per_sec
+        ),
+        format!(
+            "  rtt {:.0} ms, jitter {:.0} ms, loss {:.1}%",
+            metrics.rtt_ms,
+            metrics.jitter_ms,
+            metrics.packet_loss * 100.0
+        ),
+    ];
+
+    for (channel, received) in metrics.channel_totals() {
+        lines.push(format!("  {}: {} received", channel, received));
+        for count in metrics
+            .messages
+            .iter()
+            .filter(|count| count.channel == channel)
+        {
+            lines.push(format!("    {}: {}", count.message, count.received));
+        }
+    }
+
+    lines.join("\n")
+}
+
+fn update_metrics_text(
+    metrics: Res<NetworkMetrics>,
+    mut q_metrics_text: Query<&mut Text, With<MetricsText>>,
+) {
+    if !metrics.is_changed() {
+        return;
+    }
+
+    let mut message = format!("Client\n{}", describe(&metrics.local));
+    if let Some(server) = &metrics.server {
+        message.push_str(&format!("\nServer\n{}", describe(server)));
+    }
+
+    for mut text in &mut q_metrics_text {
+        text.0 = message.clone();
+    }
+}
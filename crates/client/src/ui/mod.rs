@@ -1,11 +1,28 @@
 use bevy::prelude::*;
 
+mod chat;
+mod lobby;
 mod main_menu;
+mod metrics_overlay;
+mod nameplate;
+mod netcode_hud;
+mod scoreboard;
+pub mod server_browser;
 pub mod system_menu;
 
 pub struct UiPlugin;
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((main_menu::MainMenuPlugin, system_menu::SystemMenuPlugin));
+        app.add_plugins((
+            chat::ChatUiPlugin,
+            lobby::LobbyUiPlugin,
+            main_menu::MainMenuPlugin,
+            metrics_overlay::MetricsOverlayPlugin,
+            nameplate::NameplatePlugin,
+            netcode_hud::NetcodeHudPlugin,
+            scoreboard::ScoreboardPlugin,
+            server_browser::ServerBrowserPlugin,
+            system_menu::SystemMenuPlugin,
+        ));
     }
 }
@@ -0,0 +1,117 @@
+use bevy::{prelude::*, transform::TransformSystem};
+use lightyear::prelude::client::Interpolated;
+use protocol::component::PlayerName;
+
+use crate::game_state::InSession;
+
+/// Names floating above other players' characters
+pub struct NameplatePlugin;
+
+impl Plugin for NameplatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                spawn_nameplates,
+                despawn_orphaned_nameplates,
+                update_nameplate_text,
+            ),
+        )
+        .add_systems(
+            PostUpdate,
+            position_nameplates.after(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// Follows the player entity it names
+#[derive(Component)]
+pub struct Nameplate(Entity);
+
+/// How far above the character's origin the name sits
+const NAMEPLATE_HEIGHT: f32 = 4.0;
+
+fn spawn_nameplates(
+    mut commands: Commands,
+    q_named: Query<(Entity, &PlayerName), (Added<PlayerName>, With<Interpolated>)>,
+) {
+    for (entity, name) in &q_named {
+        commands.spawn((
+            Text::new(name.0.clone()),
+            TextFont {
+                font_size: 14.,
+                ..default()
+            },
+            TextLayout::new_with_justify(JustifyText::Center),
+            Node {
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            Visibility::Hidden,
+            PickingBehavior::IGNORE,
+            Nameplate(entity),
+            StateScoped(InSession),
+        ));
+    }
+}
+
+fn despawn_orphaned_nameplates(
+    mut commands: Commands,
+    q_nameplates: Query<(Entity, &Nameplate)>,
+    q_named: Query<(), With<PlayerName>>,
+) {
+    for (entity, nameplate) in &q_nameplates {
+        if q_named.get(nameplate.0).is_err() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// The server renames players when two ask for the same name
+fn update_nameplate_text(
+    mut q_nameplates: Query<(&Nameplate, &mut Text)>,
+    q_named: Query<&PlayerName, Changed<PlayerName>>,
+) {
+    for (nameplate, mut text) in &mut q_nameplates {
+        if let Ok(name) = q_named.get(nameplate.0) {
+            text.0 = name.0.clone();
+        }
+    }
+}
+
+fn position_nameplates(
+    q_camera: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    q_targets: Query<&GlobalTransform, With<PlayerName>>,
+    mut q_nameplates: Query<(&Nameplate, &ComputedNode, &mut Node, &mut Visibility)>,
+) {
+    let Some((camera, camera_transform)) = q_camera.iter().find(|(camera, _)| camera.is_active)
+    else {
+        return;
+    };
+
+    for (nameplate, computed, mut node, mut visibility) in &mut q_nameplates {
+        let viewport_position = q_targets.get(nameplate.0).ok().and_then(|target| {
+            camera
+                .world_to_viewport(
+                    camera_transform,
+                    target.translation() + Vec3::Y * NAMEPLATE_HEIGHT,
+                )
+                .ok()
+        });
+
+        let shown = match viewport_position {
+            Some(position) => {
+                let size = computed.size() * computed.inverse_scale_factor();
+                node.left = Val::Px(position.x - size.x / 2.0);
+                node.top = Val::Px(position.y - size.y);
+                Visibility::Inherited
+            }
+            // Behind the camera, or not placed yet
+            None => Visibility::Hidden,
+        };
+
+        if *visibility != shown {
+            *visibility = shown;
+        }
+    }
+}
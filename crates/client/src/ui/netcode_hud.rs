@@ -0,0 +1,214 @@
+use std::time::Duration;
+
+use avian3d::prelude::Position;
+use bevy::{color::palettes::tailwind::SLATE_800, prelude::*, time::common_conditions::on_timer};
+use leafwing_input_manager::prelude::ActionState;
+use lightyear::{
+    client::config::ClientConfig,
+    prelude::{
+        TickManager,
+        client::{Confirmed, Correction},
+    },
+};
+
+use crate::{
+    conditioner::{ClientConditions, SetLinkConditioner},
+    game_state::GameState,
+    input::LocalInput,
+    metrics::NetworkMetrics,
+    prediction::PredictionCounts,
+};
+
+/// How prediction and interpolation are doing, toggled with `LocalInput::NetcodeHud`
+pub struct NetcodeHudPlugin;
+
+impl Plugin for NetcodeHudPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PredictionStats>()
+            .add_systems(OnEnter(GameState::Playing), spawn_netcode_hud)
+            .add_systems(OnExit(GameState::Playing), despawn_netcode_hud)
+            .add_systems(
+                Update,
+                (
+                    measure_corrections,
+                    roll_prediction_stats.run_if(on_timer(STATS_WINDOW)),
+                    toggle_netcode_hud,
+                    cycle_conditioner_preset,
+                    update_netcode_hud_text,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+const STATS_WINDOW: Duration = Duration::from_secs(1);
+
+#[derive(Component)]
+pub struct NetcodeHud;
+
+#[derive(Component)]
+pub struct NetcodeHudText;
+
+/// Rollbacks and corrections over the last complete window, and the largest correction so far
+/// in the current one
+#[derive(Resource, Default)]
+struct PredictionStats {
+    /// `PredictionCounts` when the current window started
+    window_start: (u32, u32),
+    max_correction: f32,
+    rollbacks_per_sec: u32,
+    corrections_per_sec: u32,
+    last_max_correction: f32,
+}
+
+fn spawn_netcode_hud(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(10.),
+                top: Val::Px(10.),
+                padding: UiRect::all(Val::Px(8.)),
+                ..default()
+            },
+            BackgroundColor(SLATE_800.with_alpha(0.8).into()),
+            PickingBehavior::IGNORE,
+            Visibility::Hidden,
+            NetcodeHud,
+        ))
+        .with_children(|hud| {
+            hud.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 14.,
+                    ..default()
+                },
+                NetcodeHudText,
+            ));
+        });
+}
+
+fn despawn_netcode_hud(mut commands: Commands, q_netcode_hud: Query<Entity, With<NetcodeHud>>) {
+    for entity in &q_netcode_hud {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// A correction is added when a rollback moved a predicted entity away from where we had it
+fn measure_corrections(
+    mut stats: ResMut<PredictionStats>,
+    q_corrections: Query<(&Position, &Correction<Position>), Added<Correction<Position>>>,
+) {
+    for (position, correction) in &q_corrections {
+        let magnitude = position.0.distance(correction.original_prediction.0);
+        stats.max_correction = stats.max_correction.max(magnitude);
+    }
+}
+
+fn roll_prediction_stats(mut stats: ResMut<PredictionStats>, counts: Res<PredictionCounts>) {
+    let (rollbacks, corrections) = stats.window_start;
+    stats.rollbacks_per_sec = counts.rollbacks - rollbacks;
+    stats.corrections_per_sec = counts.corrections - corrections;
+    stats.window_start = (counts.rollbacks, counts.corrections);
+    stats.last_max_correction = std::mem::take(&mut stats.max_correction);
+}
+
+fn toggle_netcode_hud(
+    q_local_inputs: Query<&ActionState<LocalInput>>,
+    mut q_hud: Query<&mut Visibility, With<NetcodeHud>>,
+) {
+    let toggled = q_local_inputs
+        .iter()
+        .any(|local_input| local_input.just_pressed(&LocalInput::NetcodeHud));
+    if !toggled {
+        return;
+    }
+
+    for mut visibility in &mut q_hud {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+fn cycle_conditioner_preset(
+    q_local_inputs: Query<&ActionState<LocalInput>>,
+    client_conditions: Res<ClientConditions>,
+    mut set_conditioner: EventWriter<SetLinkConditioner>,
+) {
+    let cycled = q_local_inputs
+        .iter()
+        .any(|local_input| local_input.just_pressed(&LocalInput::CycleConditioner));
+    if !cycled {
+        return;
+    }
+
+    let preset = client_conditions.preset.unwrap_or_default().next();
+    info!("switching to the {} link conditioner preset", preset);
+    set_conditioner.send(SetLinkConditioner(preset.conditions()));
+}
+
+fn describe_conditioner(client_conditions: &ClientConditions) -> String {
+    let conditioner = match client_conditions.conditions {
+        Some(conditions) => conditions.to_string(),
+        None => String::from("off"),
+    };
+
+    match client_conditions.preset {
+        Some(preset) => format!("{} ({})", preset, conditioner),
+        None => conditioner,
+    }
+}
+
+fn update_netcode_hud_text(
+    tick_manager: Res<TickManager>,
+    client_config: Res<ClientConfig>,
+    client_conditions: Res<ClientConditions>,
+    metrics: Res<NetworkMetrics>,
+    stats: Res<PredictionStats>,
+    q_confirmed: Query<&Confirmed>,
+    mut q_hud_text: Query<&mut Text, With<NetcodeHudText>>,
+) {
+    let tick = tick_manager.tick();
+
+    // How far ahead of the latest server state we are predicting
+    let server_tick_offset = match q_confirmed.iter().map(|confirmed| confirmed.tick).max() {
+        Some(server_tick) => format!("{}", tick - server_tick),
+        None => String::from("-"),
+    };
+
+    let delay = &client_config.interpolation.delay;
+    let interpolation_delay = delay.min_delay.max(
+        client_config
+            .shared
+            .server_replication_send_interval
+            .mul_f32(delay.send_interval_ratio),
+    );
+
+    let conditioner = describe_conditioner(&client_conditions);
+
+    let message = [
+        format!("tick {}", tick.0),
+        format!("server tick offset {}", server_tick_offset),
+        format!(
+            "rtt {:.0} ms, jitter {:.0} ms",
+            metrics.local.rtt_ms, metrics.local.jitter_ms
+        ),
+        format!("rollbacks {}/s", stats.rollbacks_per_sec),
+        format!(
+            "corrections {}/s, largest {:.2} m",
+            stats.corrections_per_sec, stats.last_max_correction
+        ),
+        format!("interpolation delay {} ms", interpolation_delay.as_millis()),
+        format!("conditioner {}", conditioner),
+    ]
+    .join("\n");
+
+    for mut text in &mut q_hud_text {
+        if text.0 != message {
+            text.0 = message.clone();
+        }
+    }
+}
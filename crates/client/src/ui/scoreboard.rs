@@ -0,0 +1,245 @@
+use bevy::{
+    color::palettes::tailwind::{AMBER_300, SLATE_800},
+    prelude::*,
+};
+use leafwing_input_manager::prelude::ActionState;
+use lightyear::prelude::client::{ClientConnection, NetClient};
+use protocol::component::{ClientNames, MatchState, MatchStatus, Player, Score};
+
+use crate::{game_state::GameState, input::LocalInput};
+
+/// Round timer while playing, and a scoreboard shown on request or once the round is over
+pub struct ScoreboardPlugin;
+
+impl Plugin for ScoreboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), spawn_match_hud)
+            .add_systems(OnExit(GameState::Playing), despawn_match_hud)
+            .add_systems(
+                Update,
+                (
+                    update_round_timer_text,
+                    toggle_scoreboard,
+                    update_scoreboard_title,
+                    update_scoreboard_rows,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct MatchHud;
+
+#[derive(Component)]
+pub struct RoundTimerText;
+
+#[derive(Component)]
+pub struct Scoreboard;
+
+#[derive(Component)]
+pub struct ScoreboardTitle;
+
+#[derive(Component)]
+pub struct ScoreboardRows;
+
+const LOCAL_PLAYER_COLOR: Srgba = AMBER_300;
+
+fn spawn_match_hud(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::top(Val::Px(10.)),
+                ..default()
+            },
+            PickingBehavior::IGNORE,
+            MatchHud,
+        ))
+        .with_children(|child_builder| {
+            child_builder.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 24.,
+                    ..default()
+                },
+                RoundTimerText,
+            ));
+
+            child_builder
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Stretch,
+                        min_width: Val::Px(300.),
+                        margin: UiRect::top(Val::Px(40.)),
+                        padding: UiRect::all(Val::Px(10.)),
+                        row_gap: Val::Px(4.),
+                        ..default()
+                    },
+                    BackgroundColor(SLATE_800.with_alpha(0.9).into()),
+                    Visibility::Hidden,
+                    Scoreboard,
+                ))
+                .with_children(|scoreboard| {
+                    scoreboard.spawn((
+                        Text::default(),
+                        TextFont {
+                            font_size: 24.,
+                            ..default()
+                        },
+                        Node {
+                            padding: UiRect::bottom(Val::Px(10.)),
+                            ..default()
+                        },
+                        ScoreboardTitle,
+                    ));
+
+                    scoreboard.spawn((
+                        Node {
+                            flex_direction: FlexDirection::Column,
+                            row_gap: Val::Px(4.),
+                            ..default()
+                        },
+                        ScoreboardRows,
+                    ));
+                });
+        });
+}
+
+fn despawn_match_hud(mut commands: Commands, q_match_hud: Query<Entity, With<MatchHud>>) {
+    for entity in &q_match_hud {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn update_round_timer_text(
+    q_match_status: Query<&MatchStatus>,
+    mut q_timer_text: Query<&mut Text, With<RoundTimerText>>,
+) {
+    let message = match q_match_status.iter().next() {
+        Some(status) if status.state == MatchState::InProgress && status.remaining_secs > 0 => {
+            format!(
+                "{}:{:02}",
+                status.remaining_secs / 60,
+                status.remaining_secs % 60
+            )
+        }
+        _ => String::new(),
+    };
+
+    for mut text in &mut q_timer_text {
+        if text.0 != message {
+            text.0 = message.clone();
+        }
+    }
+}
+
+/// Held open with `LocalInput::Scoreboard`, and always open once the round is over
+fn toggle_scoreboard(
+    q_local_inputs: Query<&ActionState<LocalInput>>,
+    q_match_status: Query<&MatchStatus>,
+    mut q_scoreboard: Query<&mut Visibility, With<Scoreboard>>,
+) {
+    let requested = q_local_inputs
+        .iter()
+        .any(|local_input| local_input.pressed(&LocalInput::Scoreboard));
+    let round_over = q_match_status
+        .iter()
+        .any(|status| status.state == MatchState::PostMatch);
+
+    let visibility = if requested || round_over {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+
+    for mut current in &mut q_scoreboard {
+        if *current != visibility {
+            *current = visibility;
+        }
+    }
+}
+
+/// Names come with the match status, so even players out of view have theirs
+fn update_scoreboard_title(
+    q_match_status: Query<(&MatchStatus, &ClientNames)>,
+    mut q_title: Query<&mut Text, With<ScoreboardTitle>>,
+) {
+    let Some((status, names)) = q_match_status.iter().next() else {
+        return;
+    };
+
+    let title = match (status.state, status.winner) {
+        (MatchState::PostMatch, Some(winner)) => format!(
+            "{} wins! Next match in {}",
+            names.label(winner),
+            status.remaining_secs
+        ),
+        (MatchState::PostMatch, None) => {
+            format!("Draw! Next match in {}", status.remaining_secs)
+        }
+        _ => status.game_mode.clone(),
+    };
+
+    for mut text in &mut q_title {
+        if text.0 != title {
+            text.0 = title.clone();
+        }
+    }
+}
+
+fn update_scoreboard_rows(
+    mut commands: Commands,
+    q_scores: Query<(&Player, Ref<Score>)>,
+    mut removed_scores: RemovedComponents<Score>,
+    q_names: Query<Ref<ClientNames>>,
+    q_rows: Query<(Entity, Ref<ScoreboardRows>)>,
+    client: Res<ClientConnection>,
+) {
+    let Some(names) = q_names.iter().next() else {
+        return;
+    };
+
+    let scores_changed = q_scores.iter().any(|(_, score)| score.is_changed())
+        || removed_scores.read().count() > 0
+        || names.is_changed();
+
+    for (rows, marker) in &q_rows {
+        if !scores_changed && !marker.is_added() {
+            continue;
+        }
+
+        let mut scores: Vec<(&Player, i32)> = q_scores
+            .iter()
+            .map(|(player, score)| (player, score.0))
+            .collect();
+        scores.sort_by(|a, b| b.1.cmp(&a.1));
+
+        commands
+            .entity(rows)
+            .despawn_descendants()
+            .with_children(|rows| {
+                for (player, score) in scores {
+                    let color = if player.0 == client.id() {
+                        LOCAL_PLAYER_COLOR.into()
+                    } else {
+                        Color::WHITE
+                    };
+
+                    rows.spawn(Node {
+                        justify_content: JustifyContent::SpaceBetween,
+                        column_gap: Val::Px(20.),
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        row.spawn((Text::new(names.label(player.0)), TextColor(color)));
+                        row.spawn((Text::new(score.to_string()), TextColor(color)));
+                    });
+                }
+            });
+    }
+}
@@ -0,0 +1,519 @@
+use std::{
+    fs,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+};
+
+use bevy::{
+    color::palettes::tailwind::{RED_400, SLATE_400, SLATE_600, SLATE_700},
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::LaunchConfigurations,
+    discovery::DiscoveredServers,
+    game_state::GameState,
+    network::{ConnectTarget, configured_server_addr},
+};
+
+pub struct ServerBrowserPlugin;
+
+impl Plugin for ServerBrowserPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FavouriteServersPath>()
+            .init_resource::<FavouriteServers>()
+            .init_resource::<ServerList>()
+            .add_systems(Startup, load_favourite_servers)
+            .add_systems(
+                Update,
+                (
+                    rebuild_server_list,
+                    refresh_server_list_ui,
+                    type_into_direct_connect_field,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::MainMenu)),
+            );
+    }
+}
+
+/// Where favourite servers are read from and saved to
+#[derive(Resource, Clone, Deref)]
+pub struct FavouriteServersPath(pub PathBuf);
+
+impl Default for FavouriteServersPath {
+    fn default() -> Self {
+        Self(PathBuf::from("favourite_servers.ron"))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FavouriteServer {
+    pub name: String,
+    /// `host:port`, resolved when picked
+    pub addr: String,
+}
+
+#[derive(Resource, Default)]
+pub struct FavouriteServers(pub Vec<FavouriteServer>);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ServerSource {
+    /// The server from the launch configuration
+    Default,
+    Favourite,
+    /// Found by LAN discovery
+    Lan,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ServerAddr {
+    Resolved(SocketAddr),
+    /// A host name, only looked up once picked since resolving blocks
+    Unresolved(String),
+}
+
+impl std::fmt::Display for ServerAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Resolved(addr) => addr.fmt(f),
+            Self::Unresolved(host) => host.fmt(f),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ServerListEntry {
+    pub name: String,
+    pub addr: ServerAddr,
+    pub source: ServerSource,
+}
+
+/// Every server the main menu offers, rebuilt whenever one of its sources changes
+#[derive(Resource, Default)]
+pub struct ServerList(pub Vec<ServerListEntry>);
+
+/// Root of the server browser, despawned with the rest of the menu buttons on connect
+#[derive(Component)]
+pub struct ServerBrowser;
+
+#[derive(Component)]
+pub struct ServerListContainer;
+
+/// A minimal single line text field for typing a `host:port`
+#[derive(Component, Default)]
+pub struct DirectConnectField {
+    text: String,
+    focused: bool,
+}
+
+#[derive(Component)]
+pub struct DirectConnectError;
+
+const LIST_ENTRY_COLOR: Srgba = SLATE_700;
+const FIELD_COLOR: Srgba = SLATE_600;
+const PLACEHOLDER_COLOR: Srgba = SLATE_400;
+
+fn load_favourite_servers(
+    path: Res<FavouriteServersPath>,
+    mut favourites: ResMut<FavouriteServers>,
+) {
+    let Ok(contents) = fs::read_to_string(&**path) else {
+        info!("no favourite servers at {:?}", **path);
+        return;
+    };
+
+    match ron::de::from_str::<Vec<FavouriteServer>>(&contents) {
+        Ok(servers) => favourites.0 = servers,
+        Err(e) => warn!("unable to parse favourite servers in {:?}: {}", **path, e),
+    }
+}
+
+fn save_favourite_servers(path: &FavouriteServersPath, favourites: &FavouriteServers) {
+    let serialized =
+        match ron::ser::to_string_pretty(&favourites.0, ron::ser::PrettyConfig::default()) {
+            Ok(serialized) => serialized,
+            Err(e) => {
+                warn!("unable to serialize favourite servers: {}", e);
+                return;
+            }
+        };
+
+    if let Err(e) = fs::write(&**path, serialized) {
+        warn!("unable to save favourite servers to {:?}: {}", **path, e);
+    }
+}
+
+/// Parse an ip address, with or without a port, using `default_port` when there is none.
+/// Never blocks, unlike `parse_server_addr`.
+pub fn parse_ip_addr(input: &str, default_port: u16) -> Option<SocketAddr> {
+    let input = input.trim();
+
+    if let Ok(addr) = input.parse::<SocketAddr>() {
+        return Some(addr);
+    }
+
+    input
+        .parse::<IpAddr>()
+        .ok()
+        .map(|ip| SocketAddr::new(ip, default_port))
+}
+
+/// Parse `host:port`, `host` or an ip address, using `default_port` when there is no port.
+/// Host names are resolved, which blocks, so this is only done in response to input.
+pub fn parse_server_addr(input: &str, default_port: u16) -> Option<SocketAddr> {
+    let input = input.trim();
+
+    if let Some(addr) = parse_ip_addr(input, default_port) {
+        return Some(addr);
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    {
+        use std::net::ToSocketAddrs;
+
+        let with_port = if input.contains(':') {
+            input.to_string()
+        } else {
+            format!("{}:{}", input, default_port)
+        };
+
+        with_port.to_socket_addrs().ok()?.next()
+    }
+
+    #[cfg(target_family = "wasm")]
+    None
+}
+
+fn default_port(launch_configurations: &LaunchConfigurations) -> u16 {
+    launch_configurations
+        .client_remote_config
+        .as_ref()
+        .and_then(configured_server_addr)
+        .map_or(0, |addr| addr.port())
+}
+
+fn rebuild_server_list(
+    favourites: Res<FavouriteServers>,
+    discovered: Res<DiscoveredServers>,
+    launch_configurations: Res<LaunchConfigurations>,
+    mut server_list: ResMut<ServerList>,
+) {
+    if !favourites.is_changed() && !discovered.is_changed() && !launch_configurations.is_changed() {
+        return;
+    }
+
+    let mut entries = Vec::new();
+
+    if let Some(addr) = launch_configurations
+        .client_remote_config
+        .as_ref()
+        .and_then(configured_server_addr)
+    {
+        entries.push(ServerListEntry {
+            name: String::from("Default"),
+            addr: ServerAddr::Resolved(addr),
+            source: ServerSource::Default,
+        });
+    }
+
+    let port = default_port(&launch_configurations);
+    for favourite in &favourites.0 {
+        let addr = match parse_ip_addr(&favourite.addr, port) {
+            Some(addr) => ServerAddr::Resolved(addr),
+            None => ServerAddr::Unresolved(favourite.addr.trim().to_string()),
+        };
+
+        entries.push(ServerListEntry {
+            name: favourite.name.clone(),
+            addr,
+            source: ServerSource::Favourite,
+        });
+    }
+
+    for server in &discovered.0 {
+        let mut name = format!(
+            "{} - {:?} - {} players",
+            server.name, server.current_level, server.player_count
+        );
+        if !server.compatible {
+            name.push_str(" (incompatible version)");
+        }
+
+        entries.push(ServerListEntry {
+            name,
+            addr: ServerAddr::Resolved(server.addr),
+            source: ServerSource::Lan,
+        });
+    }
+
+    server_list.0 = entries;
+}
+
+fn connect_to(commands: &mut Commands, connect_target: &mut ConnectTarget, addr: SocketAddr) {
+    connect_target.0 = Some(addr);
+    commands.set_state(GameState::ConnectingRemote);
+}
+
+pub fn spawn_server_browser(child_builder: &mut ChildBuilder) {
+    child_builder
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ServerBrowser,
+        ))
+        .with_children(|browser| {
+            browser.spawn((
+                Text::new("Servers"),
+                TextFont {
+                    font_size: 24.,
+                    ..default()
+                },
+                Node {
+                    padding: UiRect::bottom(Val::Px(10.)),
+                    ..default()
+                },
+            ));
+
+            browser.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Stretch,
+                    min_width: Val::Px(400.),
+                    padding: UiRect::bottom(Val::Px(20.)),
+                    row_gap: Val::Px(4.),
+                    ..default()
+                },
+                ServerListContainer,
+            ));
+
+            browser
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(10.),
+                    ..default()
+                })
+                .with_children(|row| {
+                    row.spawn((
+                        Text::new("host:port"),
+                        TextColor(PLACEHOLDER_COLOR.into()),
+                        Node {
+                            min_width: Val::Px(250.),
+                            padding: UiRect::all(Val::Px(6.)),
+                            ..default()
+                        },
+                        BackgroundColor(FIELD_COLOR.into()),
+                        DirectConnectField::default(),
+                    ))
+                    .observe(
+                        |click: Trigger<Pointer<Click>>,
+                         mut q_fields: Query<&mut DirectConnectField>| {
+                            if let Ok(mut field) = q_fields.get_mut(click.entity()) {
+                                field.focused = true;
+                            }
+                        },
+                    );
+
+                    row.spawn(Text::new("Connect")).observe(
+                        |_click: Trigger<Pointer<Click>>,
+                         mut commands: Commands,
+                         q_fields: Query<&DirectConnectField>,
+                         mut q_error: Query<&mut Text, With<DirectConnectError>>,
+                         mut connect_target: ResMut<ConnectTarget>,
+                         launch_configurations: Res<LaunchConfigurations>| {
+                            for field in &q_fields {
+                                if let Some(error) = submit_direct_connect(
+                                    &field.text,
+                                    &mut commands,
+                                    &mut connect_target,
+                                    &launch_configurations,
+                                ) {
+                                    for mut text in q_error.iter_mut() {
+                                        text.0 = error.clone();
+                                    }
+                                }
+                            }
+                        },
+                    );
+
+                    row.spawn(Text::new("Favourite")).observe(
+                        |_click: Trigger<Pointer<Click>>,
+                         q_fields: Query<&DirectConnectField>,
+                         mut favourites: ResMut<FavouriteServers>,
+                         path: Res<FavouriteServersPath>| {
+                            for field in &q_fields {
+                                let addr = field.text.trim();
+                                if addr.is_empty()
+                                    || favourites.0.iter().any(|favourite| favourite.addr == addr)
+                                {
+                                    continue;
+                                }
+
+                                favourites.0.push(FavouriteServer {
+                                    name: addr.to_string(),
+                                    addr: addr.to_string(),
+                                });
+                                save_favourite_servers(&path, &favourites);
+                            }
+                        },
+                    );
+                });
+
+            browser.spawn((
+                Text::default(),
+                TextColor(RED_400.into()),
+                Node {
+                    padding: UiRect::top(Val::Px(10.)),
+                    ..default()
+                },
+                DirectConnectError,
+            ));
+        });
+}
+
+/// Connect to a typed or favourite address, resolving it first, or explain why we can't
+fn submit_direct_connect(
+    input: &str,
+    commands: &mut Commands,
+    connect_target: &mut ConnectTarget,
+    launch_configurations: &LaunchConfigurations,
+) -> Option<String> {
+    match parse_server_addr(input, default_port(launch_configurations)) {
+        Some(addr) => {
+            connect_to(commands, connect_target, addr);
+            None
+        }
+        None => Some(format!("Unable to resolve \"{}\"", input.trim())),
+    }
+}
+
+fn refresh_server_list_ui(
+    mut commands: Commands,
+    server_list: Res<ServerList>,
+    q_container: Query<(Entity, Ref<ServerListContainer>)>,
+) {
+    for (container, marker) in &q_container {
+        if !server_list.is_changed() && !marker.is_added() {
+            continue;
+        }
+
+        commands
+            .entity(container)
+            .despawn_descendants()
+            .with_children(|list| {
+                if server_list.0.is_empty() {
+                    list.spawn((
+                        Text::new("No servers found"),
+                        TextColor(PLACEHOLDER_COLOR.into()),
+                    ));
+                }
+
+                for entry in &server_list.0 {
+                    let addr = entry.addr.clone();
+
+                    list.spawn((
+                        Text::new(format!("{}  {}", entry.name, entry.addr)),
+                        Node {
+                            padding: UiRect::all(Val::Px(6.)),
+                            ..default()
+                        },
+                        BackgroundColor(LIST_ENTRY_COLOR.into()),
+                    ))
+                    .observe(
+                        move |_click: Trigger<Pointer<Click>>,
+                              mut commands: Commands,
+                              mut q_error: Query<&mut Text, With<DirectConnectError>>,
+                              mut connect_target: ResMut<ConnectTarget>,
+                              launch_configurations: Res<LaunchConfigurations>| {
+                            let error = match &addr {
+                                ServerAddr::Resolved(addr) => {
+                                    connect_to(&mut commands, &mut connect_target, *addr);
+                                    None
+                                }
+                                ServerAddr::Unresolved(host) => submit_direct_connect(
+                                    host,
+                                    &mut commands,
+                                    &mut connect_target,
+                                    &launch_configurations,
+                                ),
+                            };
+
+                            if let Some(error) = error {
+                                for mut text in q_error.iter_mut() {
+                                    text.0 = error.clone();
+                                }
+                            }
+                        },
+                    );
+                }
+            });
+    }
+}
+
+fn type_into_direct_connect_field(
+    mut commands: Commands,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut q_fields: Query<(&mut DirectConnectField, &mut Text, &mut TextColor)>,
+    mut q_error: Query<&mut Text, (With<DirectConnectError>, Without<DirectConnectField>)>,
+    mut connect_target: ResMut<ConnectTarget>,
+    launch_configurations: Res<LaunchConfigurations>,
+) {
+    let events: Vec<KeyboardInput> = keyboard_events
+        .read()
+        .filter(|event| event.state == ButtonState::Pressed)
+        .cloned()
+        .collect();
+
+    for (mut field, mut text, mut color) in &mut q_fields {
+        if field.focused {
+            for event in &events {
+                match &event.logical_key {
+                    Key::Character(characters) => field.text.extend(
+                        characters
+                            .chars()
+                            .filter(|c| c.is_ascii_alphanumeric() || ".:-[]".contains(*c)),
+                    ),
+                    Key::Backspace => {
+                        field.text.pop();
+                    }
+                    Key::Escape => field.focused = false,
+                    Key::Enter => {
+                        if let Some(error) = submit_direct_connect(
+                            &field.text,
+                            &mut commands,
+                            &mut connect_target,
+                            &launch_configurations,
+                        ) {
+                            for mut error_text in q_error.iter_mut() {
+                                error_text.0 = error.clone();
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let (display, display_color) = match (field.text.is_empty(), field.focused) {
+            (true, false) => (String::from("host:port"), PLACEHOLDER_COLOR.into()),
+            (_, true) => (format!("{}_", field.text), Color::WHITE),
+            (false, false) => (field.text.clone(), Color::WHITE),
+        };
+
+        if text.0 != display {
+            text.0 = display;
+        }
+        if color.0 != display_color {
+            color.0 = display_color;
+        }
+    }
+}
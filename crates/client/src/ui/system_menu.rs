@@ -1,22 +1,54 @@
-use bevy::{color::palettes::tailwind::SLATE_800, prelude::*};
+use bevy::{
+    color::palettes::tailwind::{SLATE_600, SLATE_800},
+    prelude::*,
+    window::{PresentMode, PrimaryWindow, WindowMode},
+};
+use leafwing_input_manager::prelude::ActionState;
 
-use crate::game_state::GameState;
+use crate::{game_state::GameState, input::LocalInput, network::DisconnectWithReasonExt};
 
 pub struct SystemMenuPlugin;
 
 impl Plugin for SystemMenuPlugin {
     fn build(&self, app: &mut App) {
-        app.init_state::<SystemMenuState>();
+        app.init_state::<SystemMenuState>()
+            .init_resource::<MenuFocus>()
+            .add_event::<SystemMenuActivated>();
 
         app.add_systems(OnEnter(SystemMenuState::Open), open_system_menu)
+            .add_systems(OnEnter(SystemMenuState::Settings), open_settings_menu)
+            .add_systems(OnEnter(SystemMenuState::Disconnecting), begin_disconnect)
             .add_systems(OnEnter(SystemMenuState::Closed), close_system_menu)
-            .add_systems(OnExit(GameState::Playing), close_system_menu);
+            .add_systems(OnExit(SystemMenuState::Open), close_system_menu)
+            .add_systems(OnExit(SystemMenuState::Settings), close_system_menu)
+            .add_systems(
+                OnExit(GameState::Playing),
+                (close_system_menu, reset_system_menu_state),
+            )
+            .add_systems(
+                OnExit(GameState::Spectating),
+                (close_system_menu, reset_system_menu_state),
+            )
+            .add_systems(
+                Update,
+                (
+                    navigate_system_menu,
+                    on_system_menu_activated,
+                    highlight_focused_item,
+                    update_setting_labels,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing).or(in_state(GameState::Spectating))),
+            );
     }
 }
 
 #[derive(States, Default, Debug, Hash, PartialEq, Eq, Clone)]
 pub enum SystemMenuState {
     Open,
+    Settings,
+    /// Disconnect was chosen, waiting for the `ClientDisconnectEvent`
+    Disconnecting,
     #[default]
     Closed,
 }
@@ -24,7 +56,63 @@ pub enum SystemMenuState {
 #[derive(Component)]
 pub struct SystemMenu;
 
-fn open_system_menu(mut commands: Commands) {
+/// Everything that can be chosen from the system menu or its pages
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SystemMenuAction {
+    Resume,
+    Settings,
+    Disconnect,
+    Exit,
+    ToggleFullscreen,
+    ToggleVsync,
+    Back,
+}
+
+/// Position of a selectable item within the open menu page
+#[derive(Component)]
+pub struct MenuItem(usize);
+
+/// Index of the `MenuItem` that keyboard or gamepad confirm will activate
+#[derive(Resource, Default)]
+pub struct MenuFocus(usize);
+
+#[derive(Event)]
+pub struct SystemMenuActivated(pub SystemMenuAction);
+
+const FOCUSED_COLOR: Srgba = SLATE_600;
+
+fn open_system_menu(commands: Commands, focus: ResMut<MenuFocus>) {
+    let mut actions = vec![
+        SystemMenuAction::Resume,
+        SystemMenuAction::Settings,
+        SystemMenuAction::Disconnect,
+    ];
+
+    #[cfg(not(target_family = "wasm"))]
+    actions.push(SystemMenuAction::Exit);
+
+    spawn_menu_page(commands, focus, &actions);
+}
+
+fn open_settings_menu(commands: Commands, focus: ResMut<MenuFocus>) {
+    spawn_menu_page(
+        commands,
+        focus,
+        &[
+            SystemMenuAction::ToggleFullscreen,
+            SystemMenuAction::ToggleVsync,
+            SystemMenuAction::Back,
+        ],
+    );
+}
+
+fn spawn_menu_page(
+    mut commands: Commands,
+    mut focus: ResMut<MenuFocus>,
+    actions: &[SystemMenuAction],
+) {
+    focus.0 = 0;
+
     commands
         .spawn((
             Node {
@@ -50,36 +138,198 @@ fn open_system_menu(mut commands: Commands) {
                     BackgroundColor(SLATE_800.into()),
                 ))
                 .with_children(|child_child_builder| {
-                    child_child_builder
-                        .spawn((
-                            Text::new("Main Menu"),
-                            TextFont {
-                                font_size: 30.,
-                                ..default()
-                            },
-                            Node {
-                                padding: UiRect::bottom(Val::Px(20.)),
-                                ..default()
-                            },
-                        ))
-                        .observe(|_click: Trigger<Pointer<Click>>, mut commands: Commands| {
-                            commands.set_state(GameState::MainMenu);
-                        });
-
-                    #[cfg(not(target_family = "wasm"))]
-                    child_child_builder.spawn((
-                        Text::new("Exit"),
-                        TextFont {
-                            font_size: 30.,
-                            ..default()
-                        },
-                    ));
+                    for (index, action) in actions.iter().copied().enumerate() {
+                        child_child_builder
+                            .spawn((
+                                Text::new(action_label(action, None)),
+                                TextFont {
+                                    font_size: 30.,
+                                    ..default()
+                                },
+                                Node {
+                                    padding: UiRect::axes(Val::Px(10.), Val::Px(10.)),
+                                    ..default()
+                                },
+                                BackgroundColor(Color::NONE),
+                                action,
+                                MenuItem(index),
+                            ))
+                            .observe(
+                                move |_hover: Trigger<Pointer<Over>>,
+                                      mut focus: ResMut<MenuFocus>| {
+                                    focus.0 = index;
+                                },
+                            )
+                            .observe(
+                                move |_click: Trigger<Pointer<Click>>,
+                                      mut activated: EventWriter<SystemMenuActivated>| {
+                                    activated.send(SystemMenuActivated(action));
+                                },
+                            );
+                    }
                 });
         });
 }
 
+fn action_label(action: SystemMenuAction, window: Option<&Window>) -> String {
+    match action {
+        SystemMenuAction::Resume => String::from("Resume"),
+        SystemMenuAction::Settings => String::from("Settings"),
+        SystemMenuAction::Disconnect => String::from("Disconnect"),
+        SystemMenuAction::Exit => String::from("Exit"),
+        SystemMenuAction::ToggleFullscreen => {
+            let fullscreen = window.is_some_and(|window| window.mode != WindowMode::Windowed);
+            format!("Fullscreen: {}", if fullscreen { "On" } else { "Off" })
+        }
+        SystemMenuAction::ToggleVsync => {
+            let vsync = window.is_none_or(|window| {
+                matches!(
+                    window.present_mode,
+                    PresentMode::AutoVsync | PresentMode::Fifo | PresentMode::FifoRelaxed
+                )
+            });
+            format!("VSync: {}", if vsync { "On" } else { "Off" })
+        }
+        SystemMenuAction::Back => String::from("Back"),
+    }
+}
+
+fn navigate_system_menu(
+    q_local_inputs: Query<&ActionState<LocalInput>>,
+    q_menu_items: Query<(&MenuItem, &SystemMenuAction)>,
+    mut focus: ResMut<MenuFocus>,
+    mut activated: EventWriter<SystemMenuActivated>,
+) {
+    let item_count = q_menu_items.iter().count();
+    if item_count == 0 {
+        return;
+    }
+
+    for local_input in &q_local_inputs {
+        if local_input.just_pressed(&LocalInput::MenuUp) {
+            focus.0 = (focus.0 + item_count - 1) % item_count;
+        }
+
+        if local_input.just_pressed(&LocalInput::MenuDown) {
+            focus.0 = (focus.0 + 1) % item_count;
+        }
+
+        if local_input.just_pressed(&LocalInput::MenuConfirm) {
+            if let Some((_, action)) = q_menu_items.iter().find(|(item, _)| item.0 == focus.0) {
+                activated.send(SystemMenuActivated(*action));
+            }
+        }
+    }
+}
+
+fn on_system_menu_activated(
+    mut activated: EventReader<SystemMenuActivated>,
+    mut next_system_menu_state: ResMut<NextState<SystemMenuState>>,
+    mut q_window: Query<&mut Window, With<PrimaryWindow>>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    for SystemMenuActivated(action) in activated.read() {
+        match action {
+            SystemMenuAction::Resume => next_system_menu_state.set(SystemMenuState::Closed),
+            SystemMenuAction::Settings => next_system_menu_state.set(SystemMenuState::Settings),
+            SystemMenuAction::Back => next_system_menu_state.set(SystemMenuState::Open),
+            SystemMenuAction::Disconnect => {
+                next_system_menu_state.set(SystemMenuState::Disconnecting)
+            }
+            SystemMenuAction::Exit => {
+                app_exit.send(AppExit::Success);
+            }
+            SystemMenuAction::ToggleFullscreen => {
+                for mut window in &mut q_window {
+                    window.mode = match window.mode {
+                        WindowMode::Windowed => {
+                            WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+                        }
+                        _ => WindowMode::Windowed,
+                    };
+                }
+            }
+            SystemMenuAction::ToggleVsync => {
+                for mut window in &mut q_window {
+                    window.present_mode = match window.present_mode {
+                        PresentMode::AutoNoVsync => PresentMode::AutoVsync,
+                        _ => PresentMode::AutoNoVsync,
+                    };
+                }
+            }
+        }
+    }
+}
+
+fn highlight_focused_item(
+    focus: Res<MenuFocus>,
+    mut q_menu_items: Query<(&MenuItem, &mut BackgroundColor)>,
+) {
+    for (item, mut background) in &mut q_menu_items {
+        let color = if item.0 == focus.0 {
+            FOCUSED_COLOR.into()
+        } else {
+            Color::NONE
+        };
+
+        if background.0 != color {
+            background.0 = color;
+        }
+    }
+}
+
+fn update_setting_labels(
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    mut q_menu_items: Query<(&SystemMenuAction, &mut Text)>,
+) {
+    let window = q_window.get_single().ok();
+
+    for (action, mut text) in &mut q_menu_items {
+        let label = action_label(*action, window);
+        if text.0 != label {
+            text.0 = label;
+        }
+    }
+}
+
+/// Swap the menu for a notice, and leave the `ClientDisconnectEvent` observer to
+/// take us back to the main menu once the disconnect has gone through
+fn begin_disconnect(mut commands: Commands, q_system_menu: Query<Entity, With<SystemMenu>>) {
+    for system_menu in &q_system_menu {
+        commands.entity(system_menu).despawn_recursive();
+    }
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(SLATE_800.into()),
+            SystemMenu,
+        ))
+        .with_children(|child_builder| {
+            child_builder.spawn((
+                Text::new("Disconnecting"),
+                TextFont {
+                    font_size: 30.,
+                    ..default()
+                },
+            ));
+        });
+
+    commands.disconnect_by_request();
+}
+
 fn close_system_menu(mut commands: Commands, q_system_menu: Query<Entity, With<SystemMenu>>) {
     for system_menu in &q_system_menu {
         commands.entity(system_menu).despawn_recursive();
     }
 }
+
+fn reset_system_menu_state(mut next_system_menu_state: ResMut<NextState<SystemMenuState>>) {
+    next_system_menu_state.set(SystemMenuState::Closed);
+}
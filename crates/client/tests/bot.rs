@@ -0,0 +1,49 @@
+//! Runs a headless client with `BotPlugin` against an in-process server, and checks that
+//! the bot gets itself into a match and moves its player without any help.
+
+use avian3d::prelude::Position;
+use bevy::prelude::*;
+use client::bot::{BotPlugin, BotStats, MovePattern};
+use harness::TestHarness;
+use lightyear::prelude::ClientId;
+use protocol::component::Player;
+
+const CLIENT_ID: u64 = 1;
+
+/// Where the server has the bot's player, once it is spawned
+fn player_position(harness: &TestHarness) -> Option<Vec3> {
+    harness
+        .server
+        .world()
+        .iter_entities()
+        .filter(|entity| {
+            entity
+                .get::<Player>()
+                .is_some_and(|player| player.0 == ClientId::Netcode(CLIENT_ID))
+        })
+        .find_map(|entity| entity.get::<Position>())
+        .map(|position| position.0)
+}
+
+#[test]
+fn bot_plays_on_its_own() {
+    let mut harness = TestHarness::new(&[CLIENT_ID]);
+    harness.client_mut(CLIENT_ID).add_plugins(BotPlugin {
+        pattern: MovePattern::Circle,
+        phase: 0.0,
+    });
+
+    harness.run_until("the bot to play", |harness| {
+        harness
+            .client(CLIENT_ID)
+            .world()
+            .resource::<BotStats>()
+            .played
+            && harness.player_spawned(CLIENT_ID)
+    });
+
+    let start = player_position(&harness).expect("the bot's player should be on the server");
+    harness.run_until("the bot's player to move", |harness| {
+        player_position(harness).is_some_and(|position| position.xz().distance(start.xz()) > 1.0)
+    });
+}
@@ -0,0 +1,43 @@
+//! Logs a client's inputs while it plays on an in-process server, then resimulates the log
+//! and checks that it agrees with both the client's prediction and the server.
+
+use std::{fs::File, io::BufReader};
+
+use client::input_log::InputLogConfig;
+use common::resim::resimulate;
+use harness::{ASSET_PATH, TestHarness};
+use lightyear::prelude::ClientId;
+use protocol::input_log::read_input_log;
+
+const CLIENT_ID: u64 = 1;
+
+#[test]
+fn input_log_resimulates_without_diverging() {
+    let path = std::env::temp_dir().join(format!("input_resim_{}.inputs", std::process::id()));
+
+    let mut harness = TestHarness::new(&[CLIENT_ID]);
+    harness
+        .client_mut(CLIENT_ID)
+        .insert_resource(InputLogConfig {
+            path: Some(path.clone()),
+        });
+
+    harness.start_match();
+    harness.steps(120);
+
+    // Dropping the client flushes the log
+    drop(harness);
+
+    let log = read_input_log(BufReader::new(File::open(&path).unwrap())).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!(log.header.client_id, ClientId::Netcode(CLIENT_ID));
+    assert!(log.frames.len() >= 100);
+    assert!(log.frames.iter().any(|frame| frame.confirmed.is_some()));
+
+    let report = resimulate(&log, ASSET_PATH.to_string());
+    assert!(report.ticks > 0);
+    assert!(report.confirmed_ticks > 0);
+    assert_eq!(report.first_divergence, None);
+    assert_eq!(report.first_prediction_mismatch, None);
+}
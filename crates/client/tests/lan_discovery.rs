@@ -0,0 +1,201 @@
+//! Runs two servers on loopback and checks that a client's discovery finds both of them,
+//! with their game ports and what they report about themselves, whether queried directly
+//! or through the shared well known port with the default targets.
+
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+    thread,
+    time::{Duration, Instant},
+};
+
+use bevy::prelude::*;
+use client::{
+    app::{ClientMode, build_client_app},
+    discovery::{DiscoveredServers, DiscoveryTargets},
+};
+use lightyear::{
+    client::config::{ClientConfig, NetcodeConfig as ClientNetcodeConfig},
+    connection::client::NetConfig as ClientNetConfig,
+    prelude::{
+        SharedConfig,
+        client::{Authentication, ClientTransport, IoConfig as ClientIoConfig},
+        server::{IoConfig as ServerIoConfig, NetConfig as ServerNetConfig, ServerTransport},
+    },
+    server::config::{NetcodeConfig as ServerNetcodeConfig, ServerConfig},
+};
+use protocol::{discovery::DISCOVERY_PORT, message::Level};
+use server::{
+    app::{ServerMode, build_server_app},
+    discovery::{DiscoveryConfig, DiscoverySocket},
+};
+
+const ASSET_PATH: &str = "../assets/assets";
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A port nothing is listening on right now
+fn free_udp_port() -> u16 {
+    UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))
+        .and_then(|socket| socket.local_addr())
+        .expect("unable to find a free port")
+        .port()
+}
+
+/// A server whose game runs on `udp_listen_port` on loopback, answering discovery on
+/// `discovery_port`
+fn build_server(name: &str, udp_listen_port: u16, discovery_port: u16) -> App {
+    let server_config = ServerConfig {
+        shared: SharedConfig::default(),
+        net: vec![ServerNetConfig::Netcode {
+            config: ServerNetcodeConfig::default(),
+            io: ServerIoConfig::from_transport(ServerTransport::UdpSocket(SocketAddr::new(
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+                udp_listen_port,
+            ))),
+        }],
+        ..default()
+    };
+
+    let mut server = build_server_app(
+        server_config,
+        ASSET_PATH.to_string(),
+        ServerMode::Headless,
+        false,
+    );
+    server.insert_resource(DiscoveryConfig {
+        name: name.to_string(),
+        port: discovery_port,
+    });
+
+    server
+}
+
+/// Queries the default `DiscoveryTargets`
+fn build_client() -> App {
+    let client_config = ClientConfig {
+        shared: SharedConfig::default(),
+        net: ClientNetConfig::Netcode {
+            auth: Authentication::Manual {
+                server_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
+                client_id: 1,
+                private_key: [0; 32],
+                protocol_id: 0,
+            },
+            config: ClientNetcodeConfig::default(),
+            io: ClientIoConfig::from_transport(ClientTransport::UdpSocket(SocketAddr::new(
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+                0,
+            ))),
+        },
+        ..default()
+    };
+
+    build_client_app(
+        client_config,
+        ASSET_PATH.to_string(),
+        false,
+        ClientMode::Headless,
+    )
+}
+
+fn discovery_addr(server: &App) -> SocketAddr {
+    let port = server
+        .world()
+        .get_resource::<DiscoverySocket>()
+        .and_then(DiscoverySocket::local_addr)
+        .expect("server should have bound a discovery socket")
+        .port();
+
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port)
+}
+
+/// Step everything until the client has discovered a server called each of the `expected` names,
+/// then check what it found under those names
+fn assert_discovers(servers: &mut [App], client: &mut App, expected: [(&str, u16); 2]) {
+    let found = |client: &App| {
+        let discovered = &client.world().resource::<DiscoveredServers>().0;
+        expected
+            .iter()
+            .all(|(name, _)| discovered.iter().any(|server| server.name == *name))
+    };
+
+    let deadline = Instant::now() + TIMEOUT;
+    while !found(client) {
+        assert!(
+            Instant::now() < deadline,
+            "timed out waiting for discovery responses"
+        );
+        for server in servers.iter_mut() {
+            server.update();
+        }
+        client.update();
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    // Anything else running on this machine may answer on the well known port too
+    let discovered = &client.world().resource::<DiscoveredServers>().0;
+    for (name, port) in expected {
+        let matching: Vec<_> = discovered
+            .iter()
+            .filter(|server| server.name == name)
+            .collect();
+        assert_eq!(matching.len(), 1, "{} was listed more than once", name);
+        let server = matching[0];
+        assert_eq!(server.name, name);
+        assert_eq!(
+            server.addr,
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port)
+        );
+        assert_eq!(server.current_level, Level::Example);
+        assert_eq!(server.player_count, 0);
+        assert!(server.compatible, "{} should share our protocol", name);
+    }
+}
+
+#[test]
+fn finds_both_servers_on_loopback() {
+    let first_port = free_udp_port();
+    let second_port = free_udp_port();
+    assert_ne!(first_port, second_port);
+
+    let mut servers = [
+        build_server("First", first_port, 0),
+        build_server("Second", second_port, 0),
+    ];
+    for server in &mut servers {
+        server.update();
+    }
+
+    let mut client = build_client();
+    client.insert_resource(DiscoveryTargets(
+        servers.iter().map(discovery_addr).collect(),
+    ));
+
+    assert_discovers(
+        &mut servers,
+        &mut client,
+        [("First", first_port), ("Second", second_port)],
+    );
+}
+
+#[test]
+fn finds_both_servers_on_the_well_known_port() {
+    let first_port = free_udp_port();
+    let second_port = free_udp_port();
+    assert_ne!(first_port, second_port);
+
+    // Both share the port, and each must still get every query
+    let mut servers = [
+        build_server("Shared first", first_port, DISCOVERY_PORT),
+        build_server("Shared second", second_port, DISCOVERY_PORT),
+    ];
+    for server in &mut servers {
+        server.update();
+    }
+
+    let mut client = build_client();
+    assert_discovers(
+        &mut servers,
+        &mut client,
+        [("Shared first", first_port), ("Shared second", second_port)],
+    );
+}
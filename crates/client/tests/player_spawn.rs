@@ -0,0 +1,48 @@
+//! Walks clients through joining a server: `ServerWelcome` puts them in `Loading`,
+//! `ClientLevelLoadComplete` gets them into the lobby, and readying up spawns their `Player`.
+
+use assets::CurrentLevel;
+use client::game_state::GameState;
+use harness::TestHarness;
+use protocol::message::Level;
+
+#[test]
+fn welcome_then_load_complete_then_player() {
+    let client_id = 1;
+    let mut harness = TestHarness::new(&[client_id]);
+
+    harness.connect(client_id);
+
+    harness.run_until("server welcome", |harness| {
+        harness.game_state(client_id) == GameState::Loading
+    });
+    assert_ne!(
+        **harness.client(client_id).world().resource::<CurrentLevel>(),
+        Level::Void
+    );
+
+    harness.run_until("level load complete", |harness| harness.in_lobby(client_id));
+    harness.run_until("lobby", |harness| {
+        harness.game_state(client_id) == GameState::Lobby
+    });
+    assert!(!harness.player_spawned(client_id));
+
+    harness.set_ready(client_id, true);
+
+    harness.run_until("player", |harness| {
+        harness.game_state(client_id) == GameState::Playing
+    });
+    harness.assert_player_spawned(client_id);
+}
+
+#[test]
+fn every_client_gets_a_player() {
+    let client_ids = [1, 2, 3];
+    let mut harness = TestHarness::new(&client_ids);
+
+    harness.start_match();
+
+    for client_id in client_ids {
+        harness.assert_player_spawned(client_id);
+    }
+}
@@ -0,0 +1,70 @@
+//! Connects a client to a server whose protocol schema has an extra message, and checks that
+//! the client leaves on the `ServerHello` with a mismatch the main menu can show, rather than
+//! timing out and trying to reconnect.
+
+use client::{
+    game_state::GameState,
+    network::{Disconnection, Reconnect},
+};
+use harness::TestHarness;
+use protocol::{
+    fingerprint::{ProtocolFingerprint, ProtocolSchema},
+    message::DisconnectReason,
+};
+use serde::Deserialize;
+
+const CLIENT_ID: u64 = 1;
+
+/// Only the server knows about it
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct ServerOnlyMessage {
+    value: u32,
+}
+
+#[test]
+fn different_protocols_are_told_apart() {
+    let mut harness = TestHarness::new(&[CLIENT_ID]);
+
+    let client_fingerprint = *harness
+        .client(CLIENT_ID)
+        .world()
+        .resource::<ProtocolFingerprint>();
+
+    let world = harness.server.world_mut();
+    world
+        .resource_mut::<ProtocolSchema>()
+        .add::<ServerOnlyMessage>("message ServerToClient");
+    let server_fingerprint = ProtocolFingerprint::from_schema(world.resource::<ProtocolSchema>());
+    world.insert_resource(server_fingerprint);
+    assert_ne!(server_fingerprint, client_fingerprint);
+
+    harness.connect(CLIENT_ID);
+    harness.run_until("the client to leave", |harness| {
+        assert!(
+            !matches!(
+                harness.game_state(CLIENT_ID),
+                GameState::Loading | GameState::Lobby
+            ),
+            "the client was welcomed"
+        );
+
+        harness.game_state(CLIENT_ID) == GameState::MainMenu
+            && harness
+                .client(CLIENT_ID)
+                .world()
+                .resource::<Disconnection>()
+                .last_reason
+                .is_some()
+    });
+
+    let client = harness.client(CLIENT_ID).world();
+    assert_eq!(
+        client.resource::<Disconnection>().last_reason,
+        Some(DisconnectReason::ProtocolMismatch {
+            server: *server_fingerprint,
+            client: *client_fingerprint,
+        })
+    );
+    assert!(client.resource::<Reconnect>().timer.is_none());
+}
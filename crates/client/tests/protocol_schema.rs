@@ -0,0 +1,104 @@
+//! The protocol fingerprint is built from `schema::describe`, so a change to a field or a
+//! variant of a registered type has to change its description.
+
+// The types are only ever described, never read
+#![allow(dead_code)]
+
+use protocol::schema::describe;
+use serde::Deserialize;
+
+mod before {
+    use super::*;
+
+    #[derive(Deserialize)]
+    pub enum Reason {
+        Timeout,
+        Kicked(String),
+    }
+
+    #[derive(Deserialize)]
+    pub struct LoadComplete {
+        pub level: u8,
+    }
+}
+
+mod after {
+    use super::*;
+
+    #[derive(Deserialize)]
+    pub enum Reason {
+        Timeout,
+        Kicked(String),
+        ServerFull(String),
+    }
+
+    #[derive(Deserialize)]
+    pub struct LoadComplete {
+        pub level: u8,
+        pub spectator: bool,
+    }
+}
+
+mod untagged {
+    use super::*;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    pub enum Loose {
+        Number(u32),
+        Text(String),
+    }
+}
+
+mod again {
+    use super::*;
+
+    #[derive(Deserialize)]
+    pub struct LoadComplete {
+        pub level: u8,
+    }
+
+    #[derive(Deserialize)]
+    pub enum Reason {
+        Timeout,
+        Kicked(u32),
+    }
+}
+
+#[test]
+fn added_variants_and_fields_change_the_description() {
+    assert_ne!(
+        describe::<before::Reason>().unwrap(),
+        describe::<after::Reason>().unwrap()
+    );
+    assert_ne!(
+        describe::<before::LoadComplete>().unwrap(),
+        describe::<after::LoadComplete>().unwrap()
+    );
+}
+
+#[test]
+fn variant_payloads_are_described() {
+    assert_ne!(
+        describe::<before::Reason>().unwrap(),
+        describe::<again::Reason>().unwrap()
+    );
+}
+
+#[test]
+fn identical_shapes_describe_the_same() {
+    assert_eq!(
+        describe::<before::LoadComplete>().unwrap(),
+        describe::<again::LoadComplete>().unwrap()
+    );
+    assert_eq!(
+        describe::<Option<Vec<(u8, String)>>>().unwrap(),
+        describe::<Option<Vec<(u8, String)>>>().unwrap()
+    );
+}
+
+#[test]
+fn types_shaped_by_the_data_are_refused() {
+    assert!(describe::<untagged::Loose>().is_err());
+    assert!(describe::<Option<untagged::Loose>>().is_err());
+}
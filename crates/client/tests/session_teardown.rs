@@ -0,0 +1,77 @@
+//! Connects a headless client to an in-process server over channels, repeatedly,
+//! and checks that leaving the session always puts the client back where it started.
+
+use assets::{CurrentLevel, LevelState, LoadingAssets};
+use bevy::prelude::*;
+use client::{
+    game_state::{GameState, InSession},
+    network::DisconnectWithReasonExt,
+};
+use common::level::LevelRoot;
+use harness::{TestHarness, count};
+use lightyear::prelude::Replicated;
+use protocol::message::Level;
+
+const CLIENT_ID: u64 = 1;
+
+/// Where each session drops out from, so teardown is exercised from several states
+const LEAVE_FROM: [GameState; 3] = [GameState::Playing, GameState::Loading, GameState::Spawning];
+
+fn assert_clean(harness: &mut TestHarness, baseline_entities: u32) {
+    assert_eq!(harness.game_state(CLIENT_ID), GameState::MainMenu);
+
+    let client = harness.client_mut(CLIENT_ID);
+    assert_eq!(
+        *client.world().resource::<State<LevelState>>().get(),
+        LevelState::Unloaded
+    );
+    assert_eq!(**client.world().resource::<CurrentLevel>(), Level::Void);
+    assert!(
+        client
+            .world()
+            .resource::<LoadingAssets>()
+            .handles
+            .is_empty()
+    );
+    assert_eq!(count::<With<Replicated>>(client), 0);
+    assert_eq!(count::<With<LevelRoot>>(client), 0);
+    assert_eq!(count::<With<StateScoped<InSession>>>(client), 0);
+    assert_eq!(client.world().entities().len(), baseline_entities);
+}
+
+#[test]
+fn repeated_sessions_leave_no_trace() {
+    let mut harness = TestHarness::new(&[CLIENT_ID]);
+
+    harness.steps(10);
+    let baseline_entities = harness.client(CLIENT_ID).world().entities().len();
+
+    for leave_from in LEAVE_FROM {
+        harness.connect(CLIENT_ID);
+
+        if leave_from != GameState::Loading {
+            harness.run_until("lobby", |harness| {
+                harness.game_state(CLIENT_ID) == GameState::Lobby && harness.in_lobby(CLIENT_ID)
+            });
+
+            harness.set_ready(CLIENT_ID, true);
+        }
+
+        harness.run_until("session to start", |harness| {
+            harness.game_state(CLIENT_ID) == leave_from
+        });
+
+        let client = harness.client_mut(CLIENT_ID).world_mut();
+        client.commands().disconnect_by_request();
+        client.flush();
+
+        harness.run_until("return to menu", |harness| {
+            harness.game_state(CLIENT_ID) == GameState::MainMenu
+        });
+
+        // Let state transitions and despawns settle
+        harness.steps(10);
+
+        assert_clean(&mut harness, baseline_entities);
+    }
+}
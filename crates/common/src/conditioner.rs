@@ -0,0 +1,147 @@
+use std::{fmt, str::FromStr, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+/// Named network conditions to test with, from a clean LAN to barely playable
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConditionerPreset {
+    #[default]
+    Off,
+    Lan,
+    Broadband,
+    Mobile,
+    Awful,
+}
+
+impl ConditionerPreset {
+    pub const ALL: [ConditionerPreset; 5] = [
+        ConditionerPreset::Off,
+        ConditionerPreset::Lan,
+        ConditionerPreset::Broadband,
+        ConditionerPreset::Mobile,
+        ConditionerPreset::Awful,
+    ];
+
+    /// `None` when the conditioner should be off. The latency is each way's, so the round
+    /// trip is twice that.
+    pub fn conditions(self) -> Option<NetworkConditions> {
+        let conditions = |latency_ms, jitter_ms, loss, duplication| {
+            NetworkConditions::symmetric(
+                Duration::from_millis(latency_ms),
+                Duration::from_millis(jitter_ms),
+                loss,
+                duplication,
+            )
+        };
+
+        match self {
+            ConditionerPreset::Off => None,
+            ConditionerPreset::Lan => Some(conditions(1, 0, 0.0, 0.0)),
+            ConditionerPreset::Broadband => Some(conditions(20, 5, 0.005, 0.0)),
+            ConditionerPreset::Mobile => Some(conditions(60, 20, 0.02, 0.01)),
+            ConditionerPreset::Awful => Some(conditions(150, 60, 0.1, 0.05)),
+        }
+    }
+
+    /// The following preset, wrapping back around to `Off`
+    pub fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|preset| *preset == self)
+            .unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+impl fmt::Display for ConditionerPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl FromStr for ConditionerPreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown conditioner preset {}", s))
+    }
+}
+
+/// Latency, jitter and loss added to the packets one side of a connection receives and
+/// sends, and how many of them arrive twice. Applied by a `ConditionedRelay`, which takes
+/// new conditions while the connection is up.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct NetworkConditions {
+    pub incoming_latency: Duration,
+    pub incoming_jitter: Duration,
+    /// Fraction of incoming packets dropped
+    pub incoming_loss: f32,
+    pub outgoing_latency: Duration,
+    pub outgoing_jitter: Duration,
+    /// Fraction of outgoing packets dropped
+    pub outgoing_loss: f32,
+    /// Fraction of packets delivered a second time, either way
+    pub duplication: f32,
+}
+
+/// What happens to the packets going one way
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LinkConditions {
+    pub latency: Duration,
+    /// Each packet is delayed by the latency plus or minus up to this much
+    pub jitter: Duration,
+    pub loss: f32,
+    pub duplication: f32,
+}
+
+impl NetworkConditions {
+    /// The same latency, jitter and loss both ways
+    pub fn symmetric(latency: Duration, jitter: Duration, loss: f32, duplication: f32) -> Self {
+        Self {
+            incoming_latency: latency,
+            incoming_jitter: jitter,
+            incoming_loss: loss,
+            outgoing_latency: latency,
+            outgoing_jitter: jitter,
+            outgoing_loss: loss,
+            duplication,
+        }
+    }
+
+    pub fn incoming(&self) -> LinkConditions {
+        LinkConditions {
+            latency: self.incoming_latency,
+            jitter: self.incoming_jitter,
+            loss: self.incoming_loss,
+            duplication: self.duplication,
+        }
+    }
+
+    pub fn outgoing(&self) -> LinkConditions {
+        LinkConditions {
+            latency: self.outgoing_latency,
+            jitter: self.outgoing_jitter,
+            loss: self.outgoing_loss,
+            duplication: self.duplication,
+        }
+    }
+}
+
+impl fmt::Display for NetworkConditions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "latency {}/{} ms, jitter {}/{} ms, loss {:.1}/{:.1}% in/out, duplication {:.1}%",
+            self.incoming_latency.as_millis(),
+            self.outgoing_latency.as_millis(),
+            self.incoming_jitter.as_millis(),
+            self.outgoing_jitter.as_millis(),
+            self.incoming_loss * 100.0,
+            self.outgoing_loss * 100.0,
+            self.duplication * 100.0
+        )
+    }
+}
@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use protocol::component::Score;
+
+use super::{GameMode, PlayerDied, PlayerJoined, RoundResult, add_score, score_leader};
+
+/// Everyone for themselves. A point per kill, a point lost for dying by your own hand
+/// or the world's. First to the score limit wins, otherwise the leader when time runs out.
+pub struct FreeForAll {
+    pub score_limit: i32,
+    pub round_duration: Duration,
+}
+
+impl Default for FreeForAll {
+    fn default() -> Self {
+        Self {
+            score_limit: 10,
+            round_duration: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+impl GameMode for FreeForAll {
+    fn name(&self) -> &'static str {
+        "Free for all"
+    }
+
+    fn round_duration(&self) -> Option<Duration> {
+        Some(self.round_duration)
+    }
+
+    fn on_player_join(&mut self, world: &mut World, joined: &PlayerJoined) {
+        if let Ok(mut player) = world.get_entity_mut(joined.player) {
+            player.insert(Score::default());
+        }
+    }
+
+    fn on_death(&mut self, world: &mut World, death: &PlayerDied) {
+        match death.killer {
+            Some(killer) if killer != death.victim => add_score(world, killer, 1),
+            _ => add_score(world, death.victim, -1),
+        }
+    }
+
+    fn check_win(&mut self, world: &mut World) -> Option<RoundResult> {
+        score_leader(world)
+            .filter(|(_, score)| *score >= self.score_limit)
+            .map(|(winner, _)| RoundResult {
+                winner: Some(winner),
+            })
+    }
+
+    fn on_time_up(&mut self, world: &mut World) -> RoundResult {
+        RoundResult {
+            winner: score_leader(world).map(|(winner, _)| winner),
+        }
+    }
+}
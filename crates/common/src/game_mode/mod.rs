@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use lightyear::prelude::ClientId;
+use protocol::component::Score;
+use serde::{Deserialize, Serialize};
+
+pub mod free_for_all;
+
+/// The game modes a server can be launched with
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GameModeKind {
+    #[default]
+    FreeForAll,
+}
+
+impl GameModeKind {
+    pub fn create(self) -> Box<dyn GameMode> {
+        match self {
+            GameModeKind::FreeForAll => Box::new(free_for_all::FreeForAll::default()),
+        }
+    }
+}
+
+/// A player was spawned into the round, at the start or by joining late
+#[derive(Event, Clone, Debug)]
+pub struct PlayerJoined {
+    pub player: Entity,
+    pub client_id: ClientId,
+}
+
+/// Sent by whatever gameplay killed a player. The server respawns the victim.
+#[derive(Event, Clone, Debug)]
+pub struct PlayerDied {
+    pub victim: Entity,
+    /// `None` when nobody else was responsible
+    pub killer: Option<Entity>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoundResult {
+    /// The winning player entity, `None` for a draw
+    pub winner: Option<Entity>,
+}
+
+/// The rules of a match. The server owns one, selected from its launch options,
+/// and calls these hooks with exclusive world access while a round is in progress.
+pub trait GameMode: Send + Sync + 'static {
+    fn name(&self) -> &'static str;
+
+    /// How long a round lasts, `None` to only end it from `check_win`
+    fn round_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    fn on_round_start(&mut self, _world: &mut World) {}
+
+    fn on_player_join(&mut self, _world: &mut World, _joined: &PlayerJoined) {}
+
+    fn on_death(&mut self, _world: &mut World, _death: &PlayerDied) {}
+
+    /// Checked every frame of the round. Returning a result ends the round.
+    fn check_win(&mut self, world: &mut World) -> Option<RoundResult>;
+
+    /// The round timer ran out without `check_win` ending the round
+    fn on_time_up(&mut self, world: &mut World) -> RoundResult;
+}
+
+/// Add points to a player's `Score`, for use from game mode hooks
+pub fn add_score(world: &mut World, player: Entity, points: i32) {
+    if let Some(mut score) = world.get_mut::<Score>(player) {
+        score.0 += points;
+    }
+}
+
+/// The player with the highest score, or `None` if nobody has scored or the lead is tied
+pub fn score_leader(world: &mut World) -> Option<(Entity, i32)> {
+    let mut scores: Vec<(Entity, i32)> = world
+        .query::<(Entity, &Score)>()
+        .iter(world)
+        .map(|(entity, score)| (entity, score.0))
+        .collect();
+    scores.sort_by(|a, b| b.1.cmp(&a.1));
+
+    match scores.as_slice() {
+        [] => None,
+        [first, second, ..] if first.1 == second.1 => None,
+        [first, ..] => Some(*first),
+    }
+}
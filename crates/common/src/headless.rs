@@ -0,0 +1,59 @@
+use bevy::{
+    app::PanicHandlerPlugin,
+    asset::AssetPlugin,
+    diagnostic::DiagnosticsPlugin,
+    gltf::GltfPlugin,
+    input::InputPlugin,
+    pbr::PbrPlugin,
+    prelude::*,
+    render::{
+        RenderPlugin as BevyRenderPlugin,
+        settings::{RenderCreation, WgpuSettings},
+    },
+    scene::ScenePlugin,
+    state::app::StatesPlugin,
+    window::ExitCondition,
+};
+
+/// Everything an app needs to load and simulate levels without a window or a GPU.
+/// Used by the headless server, and by clients that run without rendering.
+/// Logging and the schedule runner are left to the caller.
+pub struct HeadlessPlugin {
+    pub asset_path: String,
+    pub hot_reload: bool,
+}
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            AssetPlugin {
+                file_path: self.asset_path.clone(),
+                watch_for_changes_override: Some(self.hot_reload),
+                ..default()
+            },
+            WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                ..default()
+            },
+            BevyRenderPlugin {
+                render_creation: RenderCreation::Automatic(WgpuSettings {
+                    backends: None,
+                    ..default()
+                }),
+                ..default()
+            },
+            PanicHandlerPlugin,
+            TransformPlugin,
+            HierarchyPlugin,
+            DiagnosticsPlugin,
+            StatesPlugin,
+            ScenePlugin,
+            GltfPlugin::default(),
+            PbrPlugin::default(),
+            InputPlugin,
+        ));
+
+        app.init_asset::<Image>(); // or add ImagePlugin
+    }
+}
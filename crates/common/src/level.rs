@@ -12,10 +12,15 @@ impl Plugin for LevelPlugin {
         app.add_systems(
             OnEnter(LevelState::Loaded),
             (level_loaded, add_level_gameplay_components).chain(),
-        );
+        )
+        .add_systems(OnExit(LevelState::Loaded), despawn_level);
     }
 }
 
+/// Tag component for the root of the spawned level scene
+#[derive(Component)]
+pub struct LevelRoot;
+
 fn level_loaded(
     mut commands: Commands,
     current_level: Res<CurrentLevel>,
@@ -23,7 +28,7 @@ fn level_loaded(
 ) {
     match **current_level {
         Level::Example => {
-            commands.spawn(SceneRoot(level_assets.example_level.clone()));
+            commands.spawn((SceneRoot(level_assets.example_level.clone()), LevelRoot));
         }
         Level::Void => {}
     }
@@ -37,3 +42,10 @@ fn add_level_gameplay_components(
         commands.entity(geo).insert(RigidBody::Static);
     }
 }
+
+/// Leaving `LevelState::Loaded` means the level is being replaced or rebuilt
+fn despawn_level(mut commands: Commands, q_level_root: Query<Entity, With<LevelRoot>>) {
+    for level_root in &q_level_root {
+        commands.entity(level_root).despawn_recursive();
+    }
+}
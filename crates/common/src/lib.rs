@@ -1,14 +1,20 @@
 use assets::AssetPlugin;
 use avian3d::{PhysicsPlugins, prelude::PhysicsInterpolationPlugin};
-use bevy::prelude::*;
+use bevy::{app::PluginGroupBuilder, prelude::*};
 use lightyear::prelude::{
     client::{Interpolated, Predicted, VisualInterpolateStatus},
     server::ReplicationTarget,
 };
 use protocol::ProtocolPlugin;
 
+pub mod conditioner;
+pub mod game_mode;
+pub mod headless;
 pub mod level;
 pub mod player;
+#[cfg(not(target_family = "wasm"))]
+pub mod relay;
+pub mod resim;
 
 pub struct CommonPlugin;
 
@@ -17,14 +23,27 @@ impl Plugin for CommonPlugin {
         app.add_plugins((
             AssetPlugin,
             ProtocolPlugin,
-            PhysicsPlugins::new(FixedPostUpdate)
-                .build()
-                .disable::<PhysicsInterpolationPlugin>(),
+            physics_plugins(),
             level::LevelPlugin,
             player::PlayerPlugin,
         ));
     }
 }
 
-pub type Simulated = Or<(With<Predicted>, With<ReplicationTarget>)>;
-pub type Rendered = Or<(Simulated, With<Interpolated>)>;
+/// Shared with the resimulation, which must step physics the same way
+fn physics_plugins() -> PluginGroupBuilder {
+    PhysicsPlugins::new(FixedPostUpdate)
+        .build()
+        .disable::<PhysicsInterpolationPlugin>()
+}
+
+pub type Simulated = Or<(With<Predicted>, With<ReplicationTarget>, With<Resimulated>)>;
+pub type Rendered = Or<(Simulated, With<Interpolated>, With<Replayed>)>;
+
+/// Played back from a replay file, neither simulated nor replicated
+#[derive(Component)]
+pub struct Replayed;
+
+/// Stepped by `resim::resimulate`, outside of any client or server
+#[derive(Component)]
+pub struct Resimulated;
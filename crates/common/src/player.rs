@@ -6,7 +6,10 @@ use lightyear::prelude::{
     client::{Confirmed, Interpolated, Predicted},
     server::ReplicationTarget,
 };
-use protocol::{component::Player, input::NetworkedInput};
+use protocol::{
+    component::{Npc, Player},
+    input::NetworkedInput,
+};
 
 use crate::{Rendered, Simulated};
 
@@ -16,10 +19,11 @@ impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             Update,
-            (add_player_gameplay_components).run_if(in_state(LevelState::Loaded)),
+            (add_player_gameplay_components, add_npc_gameplay_components)
+                .run_if(in_state(LevelState::Loaded)),
         );
 
-        app.add_systems(FixedUpdate, move_player);
+        app.add_systems(FixedUpdate, move_player.in_set(CharacterMovement));
     }
 }
 
@@ -35,7 +39,7 @@ fn add_player_gameplay_components(
     for player_entity in &q_rendered_player {
         commands.entity(player_entity).insert((
             RigidBody::Kinematic,
-            Collider::capsule(3.0, 4.0),
+            character_collider(),
             InputMap::<NetworkedInput>::default()
                 .with_dual_axis(NetworkedInput::Move, VirtualDPad::wasd()),
             SceneRoot(global_assets.character.clone()),
@@ -43,17 +47,49 @@ fn add_player_gameplay_components(
     }
 }
 
+/// NPCs share the player's body, but are driven by the server rather than an input map
+fn add_npc_gameplay_components(
+    mut commands: Commands,
+    q_rendered_npc: Query<Entity, (Rendered, Without<RigidBody>, With<Npc>)>,
+    global_assets: Res<GlobalAssets>,
+) {
+    for npc_entity in &q_rendered_npc {
+        commands.entity(npc_entity).insert((
+            RigidBody::Kinematic,
+            character_collider(),
+            SceneRoot(global_assets.character.clone()),
+        ));
+    }
+}
+
+/// Applies `NetworkedInput::Move` to players and NPCs. Systems writing an
+/// `ActionState<NetworkedInput>` themselves should run before it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CharacterMovement;
+
+/// A `NetworkedInput::Move` that looks random but is the same for the same `seed`,
+/// for scripted movement like wandering NPCs and bots
+pub fn wander_direction(seed: f32) -> Vec2 {
+    // Cheap hash, plenty to pick a heading
+    let noise = (seed.sin() * 43_758.547).fract();
+    Vec2::from_angle(noise * std::f32::consts::TAU)
+}
+
 const PLAYER_MOVE_SPEED: f32 = 30.0;
 
-fn move_player(
+pub(crate) fn character_collider() -> Collider {
+    Collider::capsule(3.0, 4.0)
+}
+
+pub(crate) fn move_player(
     mut q_player: Query<
         (&ActionState<NetworkedInput>, &mut LinearVelocity),
-        (Simulated, With<Player>),
+        (Simulated, Or<(With<Player>, With<Npc>)>),
     >,
 ) {
     for (action_state, mut velocity) in q_player.iter_mut() {
         if let Some(movement) = action_state.dual_axis_data(&NetworkedInput::Move) {
-            let move_vec = Vec3::new(movement.pair.x, 0.0, -movement.pair.y).normalize();
+            let move_vec = Vec3::new(movement.pair.x, 0.0, -movement.pair.y).normalize_or_zero();
             velocity.0 = move_vec * PLAYER_MOVE_SPEED;
         }
     }
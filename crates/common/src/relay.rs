@@ -0,0 +1,343 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+    io::{self, ErrorKind},
+    net::{SocketAddr, UdpSocket},
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{self, AtomicBool},
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::log::warn;
+
+use crate::conditioner::{LinkConditions, NetworkConditions};
+
+/// How long the relay sleeps between polls, which bounds how late a packet can be
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// A peer that has sent nothing for this long is forgotten, along with its upstream socket
+const PEER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Larger than any packet netcode sends
+const MAX_PACKET_SIZE: usize = 2048;
+
+/// Which end of a `ConditionedRelay` the conditioned app is on, which decides what counts
+/// as incoming
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConditionedEnd {
+    /// The app listens at the target, with the relay facing its peers, as for a server
+    Target,
+    /// The app is the relay's only peer, with the relay facing the target, as for a client
+    Peers,
+}
+
+/// Forwards UDP between peers and a target, holding back, dropping and duplicating
+/// packets both ways. Each peer gets its own socket towards the target, so the target
+/// can still tell them apart. The conditions can change while packets are flowing.
+/// Stops when dropped.
+pub struct ConditionedRelay {
+    listen_addr: SocketAddr,
+    conditions: Arc<Mutex<Option<NetworkConditions>>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl ConditionedRelay {
+    /// Listen for peers on `listen_addr`, and reach `target` from sockets bound to `upstream_addr`
+    pub fn spawn(
+        listen_addr: SocketAddr,
+        target: SocketAddr,
+        upstream_addr: SocketAddr,
+        end: ConditionedEnd,
+        conditions: Option<NetworkConditions>,
+    ) -> io::Result<Self> {
+        let socket = UdpSocket::bind(listen_addr)?;
+        socket.set_nonblocking(true)?;
+
+        let relay = Self {
+            listen_addr: socket.local_addr()?,
+            conditions: Arc::new(Mutex::new(conditions)),
+            stop: Arc::new(AtomicBool::new(false)),
+        };
+
+        let mut worker = RelayWorker {
+            socket,
+            target,
+            upstream_addr,
+            end,
+            conditions: relay.conditions.clone(),
+            stop: relay.stop.clone(),
+            peers: HashMap::new(),
+            to_target: PacketConditioner::new(seed()),
+            to_peers: PacketConditioner::new(seed().rotate_left(32)),
+        };
+        thread::Builder::new()
+            .name(format!("relay {}", relay.listen_addr))
+            .spawn(move || worker.run())?;
+
+        Ok(relay)
+    }
+
+    /// Where peers send to
+    pub fn listen_addr(&self) -> SocketAddr {
+        self.listen_addr
+    }
+
+    pub fn conditions(&self) -> Option<NetworkConditions> {
+        *self
+            .conditions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Applies to every packet the relay receives from now on
+    pub fn set_conditions(&self, conditions: Option<NetworkConditions>) {
+        *self
+            .conditions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = conditions;
+    }
+}
+
+impl Drop for ConditionedRelay {
+    fn drop(&mut self) {
+        self.stop.store(true, atomic::Ordering::Relaxed);
+    }
+}
+
+/// Different for every relay, so two of them don't drop the same packets
+fn seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+}
+
+struct Peer {
+    upstream: UdpSocket,
+    last_seen: Instant,
+}
+
+struct RelayWorker {
+    socket: UdpSocket,
+    target: SocketAddr,
+    upstream_addr: SocketAddr,
+    end: ConditionedEnd,
+    conditions: Arc<Mutex<Option<NetworkConditions>>>,
+    stop: Arc<AtomicBool>,
+    peers: HashMap<SocketAddr, Peer>,
+    to_target: PacketConditioner<(SocketAddr, Vec<u8>)>,
+    to_peers: PacketConditioner<(SocketAddr, Vec<u8>)>,
+}
+
+impl RelayWorker {
+    fn run(&mut self) {
+        let mut buffer = [0; MAX_PACKET_SIZE];
+
+        while !self.stop.load(atomic::Ordering::Relaxed) {
+            let now = Instant::now();
+            let conditions = *self
+                .conditions
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            let (towards_target, towards_peers) = match (conditions, self.end) {
+                (None, _) => (None, None),
+                (Some(conditions), ConditionedEnd::Target) => {
+                    (Some(conditions.incoming()), Some(conditions.outgoing()))
+                }
+                (Some(conditions), ConditionedEnd::Peers) => {
+                    (Some(conditions.outgoing()), Some(conditions.incoming()))
+                }
+            };
+
+            self.receive_from_peers(&mut buffer, now, towards_target);
+            self.receive_from_target(&mut buffer, now, towards_peers);
+
+            while let Some((from, packet)) = self.to_target.pop_due(now) {
+                if let Some(peer) = self.peers.get(&from) {
+                    let _ = peer.upstream.send(&packet);
+                }
+            }
+            while let Some((to, packet)) = self.to_peers.pop_due(now) {
+                let _ = self.socket.send_to(&packet, to);
+            }
+
+            self.peers
+                .retain(|_, peer| now.duration_since(peer.last_seen) < PEER_TIMEOUT);
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn receive_from_peers(
+        &mut self,
+        buffer: &mut [u8],
+        now: Instant,
+        conditions: Option<LinkConditions>,
+    ) {
+        loop {
+            let (len, from) = match self.socket.recv_from(buffer) {
+                Ok(received) => received,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return,
+                // A peer that went away, which is left to time out
+                Err(_) => return,
+            };
+
+            if !self.peers.contains_key(&from) {
+                match self.connect_upstream() {
+                    Ok(upstream) => {
+                        self.peers.insert(
+                            from,
+                            Peer {
+                                upstream,
+                                last_seen: now,
+                            },
+                        );
+                    }
+                    Err(e) => {
+                        warn!("unable to relay {} to {}: {}", from, self.target, e);
+                        continue;
+                    }
+                }
+            }
+            if let Some(peer) = self.peers.get_mut(&from) {
+                peer.last_seen = now;
+            }
+
+            self.to_target
+                .push((from, buffer[..len].to_vec()), now, conditions);
+        }
+    }
+
+    fn receive_from_target(
+        &mut self,
+        buffer: &mut [u8],
+        now: Instant,
+        conditions: Option<LinkConditions>,
+    ) {
+        for (addr, peer) in &self.peers {
+            while let Ok(len) = peer.upstream.recv(buffer) {
+                self.to_peers
+                    .push((*addr, buffer[..len].to_vec()), now, conditions);
+            }
+        }
+    }
+
+    fn connect_upstream(&self) -> io::Result<UdpSocket> {
+        let upstream = UdpSocket::bind(self.upstream_addr)?;
+        upstream.connect(self.target)?;
+        upstream.set_nonblocking(true)?;
+        Ok(upstream)
+    }
+}
+
+/// Holds packets back until they are due, and drops and duplicates them, according to
+/// the `LinkConditions` in effect when each one was pushed
+pub struct PacketConditioner<P> {
+    pending: BinaryHeap<Pending<P>>,
+    sequence: u64,
+    rng: u64,
+}
+
+impl<P: Clone> PacketConditioner<P> {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            pending: BinaryHeap::new(),
+            sequence: 0,
+            rng: seed,
+        }
+    }
+
+    /// `None` passes the packet through as soon as it is popped
+    pub fn push(&mut self, packet: P, now: Instant, conditions: Option<LinkConditions>) {
+        let Some(conditions) = conditions else {
+            self.queue(packet, now);
+            return;
+        };
+
+        if self.chance(conditions.loss) {
+            return;
+        }
+        if self.chance(conditions.duplication) {
+            let due = now + self.delay(&conditions);
+            self.queue(packet.clone(), due);
+        }
+        let due = now + self.delay(&conditions);
+        self.queue(packet, due);
+    }
+
+    /// The earliest packet that is due by `now`, in the order they became due
+    pub fn pop_due(&mut self, now: Instant) -> Option<P> {
+        if self.pending.peek()?.due > now {
+            return None;
+        }
+        self.pending.pop().map(|pending| pending.packet)
+    }
+
+    /// Packets still held back
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    fn queue(&mut self, packet: P, due: Instant) {
+        self.pending.push(Pending {
+            due,
+            sequence: self.sequence,
+            packet,
+        });
+        self.sequence += 1;
+    }
+
+    /// The latency, plus or minus up to the jitter
+    fn delay(&mut self, conditions: &LinkConditions) -> Duration {
+        let jitter = conditions.jitter.mul_f32(self.unit() * 2.0);
+        (conditions.latency + jitter).saturating_sub(conditions.jitter)
+    }
+
+    fn chance(&mut self, probability: f32) -> bool {
+        probability > 0.0 && self.unit() < probability
+    }
+
+    /// Uniform in [0, 1), from SplitMix64
+    fn unit(&mut self) -> f32 {
+        self.rng = self.rng.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        (z >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+struct Pending<P> {
+    due: Instant,
+    /// Keeps packets due at the same time in the order they were pushed
+    sequence: u64,
+    packet: P,
+}
+
+impl<P> PartialEq for Pending<P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<P> Eq for Pending<P> {}
+
+impl<P> PartialOrd for Pending<P> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Reversed, so that the max heap pops the earliest packet first
+impl<P> Ord for Pending<P> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.due, other.sequence).cmp(&(self.due, self.sequence))
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use avian3d::prelude::{Position, RigidBody, Rotation};
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use leafwing_input_manager::prelude::ActionState;
+use protocol::{
+    component::Player,
+    input::NetworkedInput,
+    input_log::{BodyState, InputLog},
+};
+
+use crate::{
+    Resimulated,
+    headless::HeadlessPlugin,
+    physics_plugins,
+    player::{CharacterMovement, character_collider, move_player},
+};
+
+/// Further apart than this counts as diverged, floats drift a little between machines
+const DIVERGENCE_TOLERANCE: f32 = 0.01;
+
+/// Where two positions of the player first disagreed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Divergence {
+    pub tick: u16,
+    /// The input applied on that tick
+    pub movement: Option<Vec2>,
+    pub expected: Vec3,
+    pub actual: Vec3,
+}
+
+/// How a resimulation of an input log went. The player's body is resimulated alone, without
+/// the other players and NPCs it may have collided with, so a divergence right where it met
+/// one of them is expected.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResimReport {
+    pub ticks: usize,
+    /// Ticks the server's state was known for
+    pub confirmed_ticks: usize,
+    /// Jumps in the client's ticks, after which the resimulation restarts from the prediction
+    pub gaps: usize,
+    /// Resimulated against the server, which is the desync to look into
+    pub first_divergence: Option<Divergence>,
+    /// Predicted against the server, where the client had to roll back
+    pub first_misprediction: Option<Divergence>,
+    /// Resimulated against predicted. Before the first misprediction, this means
+    /// the simulation itself is not deterministic.
+    pub first_prediction_mismatch: Option<Divergence>,
+}
+
+#[derive(Resource, Default)]
+struct ResimTicks(u32);
+
+fn count_tick(mut ticks: ResMut<ResimTicks>) {
+    ticks.0 += 1;
+}
+
+/// Run the app until it has simulated one more tick
+fn step(app: &mut App) {
+    let tick = app.world().resource::<ResimTicks>().0;
+    while app.world().resource::<ResimTicks>().0 == tick {
+        app.update();
+    }
+}
+
+/// lightyear's tick wraps around, so count it on from a tick whose count is known,
+/// assuming the two are less than half the range apart
+fn unwrap_tick(tick: u16, near: u16, near_count: i64) -> i64 {
+    near_count + i64::from(tick.wrapping_sub(near) as i16)
+}
+
+fn diverged(tick: u16, movement: Option<Vec2>, expected: Vec3, actual: Vec3) -> Option<Divergence> {
+    (expected.distance(actual) > DIVERGENCE_TOLERANCE).then_some(Divergence {
+        tick,
+        movement,
+        expected,
+        actual,
+    })
+}
+
+/// Feed a client's input log through `move_player` and avian in a headless app, from where
+/// the client's player first was, comparing every tick to what the client predicted and
+/// to what the server confirmed. Only the level and the player's own body are simulated.
+pub fn resimulate(log: &InputLog, asset_path: String) -> ResimReport {
+    let mut report = ResimReport::default();
+    let Some((first, frames)) = log.frames.split_first() else {
+        return report;
+    };
+
+    let tick_duration = log.header.tick_duration;
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        HeadlessPlugin {
+            asset_path,
+            hot_reload: false,
+        },
+        physics_plugins(),
+    ))
+    .insert_resource(Time::<Fixed>::from_duration(tick_duration))
+    .insert_resource(TimeUpdateStrategy::ManualDuration(tick_duration))
+    .init_resource::<ResimTicks>()
+    .add_systems(FixedFirst, count_tick)
+    .add_systems(FixedUpdate, move_player.in_set(CharacterMovement));
+    app.finish();
+    app.cleanup();
+    app.update();
+
+    let body = app
+        .world_mut()
+        .spawn((
+            Resimulated,
+            Player(log.header.client_id),
+            RigidBody::Kinematic,
+            character_collider(),
+            Position(first.predicted.position),
+            Rotation(first.predicted.rotation),
+            ActionState::<NetworkedInput>::default(),
+        ))
+        .id();
+
+    // Counted from the first frame, so that a long log doesn't mix up ticks once they wrap
+    let mut counts = Vec::with_capacity(log.frames.len());
+    let mut previous = (first.tick, 0);
+    for frame in &log.frames {
+        let count = unwrap_tick(frame.tick, previous.0, previous.1);
+        counts.push(count);
+        previous = (frame.tick, count);
+    }
+
+    let confirmed: HashMap<i64, BodyState> = log
+        .frames
+        .iter()
+        .zip(&counts)
+        .filter_map(|(frame, &count)| {
+            let confirmed = frame.confirmed?;
+            Some((
+                unwrap_tick(confirmed.tick, frame.tick, count),
+                confirmed.body,
+            ))
+        })
+        .collect();
+
+    let mut previous_count = counts[0];
+    for (frame, &count) in frames.iter().zip(&counts[1..]) {
+        let mut entity = app.world_mut().entity_mut(body);
+
+        if count != previous_count + 1 {
+            // The client skipped or repeated ticks, so pick up from its prediction
+            report.gaps += 1;
+            entity.insert((
+                Position(frame.predicted.position),
+                Rotation(frame.predicted.rotation),
+            ));
+            previous_count = count;
+            continue;
+        }
+        previous_count = count;
+
+        // No movement logged means the stick was idle, not that the last one is still held
+        entity
+            .get_mut::<ActionState<NetworkedInput>>()
+            .unwrap()
+            .set_axis_pair(&NetworkedInput::Move, frame.movement.unwrap_or(Vec2::ZERO));
+        step(&mut app);
+        report.ticks += 1;
+
+        let resimulated = app.world().get::<Position>(body).unwrap().0;
+        let predicted = frame.predicted.position;
+
+        if report.first_prediction_mismatch.is_none() {
+            report.first_prediction_mismatch =
+                diverged(frame.tick, frame.movement, predicted, resimulated);
+        }
+
+        if let Some(server) = confirmed.get(&count) {
+            report.confirmed_ticks += 1;
+            if report.first_divergence.is_none() {
+                report.first_divergence =
+                    diverged(frame.tick, frame.movement, server.position, resimulated);
+            }
+            if report.first_misprediction.is_none() {
+                report.first_misprediction =
+                    diverged(frame.tick, frame.movement, server.position, predicted);
+            }
+        }
+    }
+
+    report
+}
@@ -0,0 +1,124 @@
+//! Checks the packet conditioner holds back, drops and duplicates packets as told, and that a
+//! relay conditions a live UDP exchange both ways and takes new conditions without
+//! reconnecting.
+
+use std::{
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+    thread,
+    time::{Duration, Instant},
+};
+
+use common::{
+    conditioner::{LinkConditions, NetworkConditions},
+    relay::{ConditionedEnd, ConditionedRelay, PacketConditioner},
+};
+
+fn link(latency_ms: u64, loss: f32, duplication: f32) -> Option<LinkConditions> {
+    Some(LinkConditions {
+        latency: Duration::from_millis(latency_ms),
+        jitter: Duration::ZERO,
+        loss,
+        duplication,
+    })
+}
+
+fn drain(conditioner: &mut PacketConditioner<u32>, now: Instant) -> Vec<u32> {
+    std::iter::from_fn(|| conditioner.pop_due(now)).collect()
+}
+
+#[test]
+fn packets_are_held_back_for_the_latency() {
+    let start = Instant::now();
+    let mut conditioner = PacketConditioner::new(1);
+    conditioner.push(1, start, link(100, 0.0, 0.0));
+    conditioner.push(2, start, None);
+
+    assert_eq!(drain(&mut conditioner, start), [2]);
+    assert_eq!(
+        drain(&mut conditioner, start + Duration::from_millis(99)),
+        []
+    );
+    assert_eq!(
+        drain(&mut conditioner, start + Duration::from_millis(100)),
+        [1]
+    );
+    assert!(conditioner.is_empty());
+}
+
+#[test]
+fn loss_drops_and_duplication_doubles() {
+    let now = Instant::now();
+    let mut conditioner = PacketConditioner::new(2);
+
+    for packet in 0..10 {
+        conditioner.push(packet, now, link(0, 1.0, 0.0));
+    }
+    assert!(conditioner.is_empty(), "every packet should be dropped");
+
+    for packet in 0..10 {
+        conditioner.push(packet, now, link(0, 0.0, 1.0));
+    }
+    assert_eq!(conditioner.len(), 20, "every packet should be sent twice");
+    let mut delivered = drain(&mut conditioner, now);
+    delivered.sort();
+    let expected: Vec<u32> = (0..10).flat_map(|packet| [packet, packet]).collect();
+    assert_eq!(delivered, expected);
+}
+
+/// Echoes everything back to whoever sent it, until the test ends
+fn spawn_echo() -> SocketAddr {
+    let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let addr = socket.local_addr().unwrap();
+    thread::spawn(move || {
+        let mut buffer = [0; 64];
+        while let Ok((len, from)) = socket.recv_from(&mut buffer) {
+            let _ = socket.send_to(&buffer[..len], from);
+        }
+    });
+    addr
+}
+
+/// How long a packet takes to come back through the relay
+fn round_trip(socket: &UdpSocket) -> Duration {
+    let sent = Instant::now();
+    socket.send(b"ping").unwrap();
+    let mut buffer = [0; 64];
+    let len = socket.recv(&mut buffer).unwrap();
+    assert_eq!(&buffer[..len], b"ping");
+    sent.elapsed()
+}
+
+#[test]
+fn relay_conditions_both_ways_and_takes_changes_live() {
+    let echo_addr = spawn_echo();
+    let outgoing_only = NetworkConditions {
+        outgoing_latency: Duration::from_millis(150),
+        ..NetworkConditions::default()
+    };
+    let relay = ConditionedRelay::spawn(
+        SocketAddr::from((Ipv4Addr::LOCALHOST, 0)),
+        echo_addr,
+        SocketAddr::from((Ipv4Addr::LOCALHOST, 0)),
+        ConditionedEnd::Peers,
+        Some(outgoing_only),
+    )
+    .unwrap();
+
+    let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    socket.connect(relay.listen_addr()).unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    assert!(round_trip(&socket) >= Duration::from_millis(150));
+
+    relay.set_conditions(Some(NetworkConditions {
+        incoming_latency: Duration::from_millis(300),
+        ..NetworkConditions::default()
+    }));
+    assert!(round_trip(&socket) >= Duration::from_millis(300));
+
+    relay.set_conditions(None);
+    assert_eq!(relay.conditions(), None);
+    assert!(round_trip(&socket) < Duration::from_millis(150));
+}
@@ -0,0 +1,317 @@
+//! Runs an in-process server and headless clients connected over in-memory channels,
+//! stepped one tick at a time under a virtual clock, for end-to-end tests.
+
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    thread,
+    time::{Duration, Instant},
+};
+
+use assets::LevelState;
+use bevy::{ecs::query::QueryFilter, prelude::*, time::TimeUpdateStrategy};
+use client::{
+    app::{ClientMode, build_client_app},
+    game_state::GameState,
+    lobby::SetReadyExt,
+};
+use lightyear::{
+    client::config::{ClientConfig, NetcodeConfig as ClientNetcodeConfig},
+    connection::client::NetConfig as ClientNetConfig,
+    prelude::{
+        ClientId, SharedConfig,
+        client::{Authentication, ClientTransport, IoConfig as ClientIoConfig},
+        server::{IoConfig as ServerIoConfig, NetConfig as ServerNetConfig, ServerTransport},
+    },
+    server::config::{NetcodeConfig as ServerNetcodeConfig, ServerConfig},
+};
+use protocol::component::{LobbyRoster, Player};
+use server::{
+    app::{ServerMode, build_server_app},
+    lobby::LobbyConfig,
+};
+
+/// Relative to the crate running the tests
+pub const ASSET_PATH: &str = "../assets/assets";
+
+/// Steps `run_until` takes before giving up, a couple of minutes of virtual time
+const MAX_STEPS: usize = 10_000;
+
+/// Real time a step waits for level assets before giving up
+const ASSET_LOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often a step waiting for level assets checks on them
+const ASSET_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// A server and its clients, all stepped together. Nothing connects until asked to,
+/// so resources can still be inserted into the apps before the first step.
+pub struct TestHarness {
+    pub server: App,
+    clients: Vec<(u64, App)>,
+    tick_duration: Duration,
+}
+
+impl TestHarness {
+    /// A server and one headless client per id. The lobby has no countdown and waits
+    /// for every client, so a match starts as soon as all of them are ready.
+    pub fn new(client_ids: &[u64]) -> Self {
+        let tick_duration = SharedConfig::default().tick.tick_duration;
+        let mut server_channels = Vec::new();
+        let mut clients = Vec::new();
+
+        for &client_id in client_ids {
+            let client_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), client_id as u16);
+            let (to_server_send, to_server_recv) = crossbeam_channel::unbounded();
+            let (from_server_send, from_server_recv) = crossbeam_channel::unbounded();
+            server_channels.push((client_addr, to_server_recv, from_server_send));
+
+            let client_config = ClientConfig {
+                shared: SharedConfig::default(),
+                net: ClientNetConfig::Netcode {
+                    auth: Authentication::Manual {
+                        server_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
+                        client_id,
+                        private_key: [0; 32],
+                        protocol_id: 0,
+                    },
+                    config: ClientNetcodeConfig::default(),
+                    io: ClientIoConfig::from_transport(ClientTransport::LocalChannel {
+                        recv: from_server_recv,
+                        send: to_server_send,
+                    }),
+                },
+                ..default()
+            };
+
+            let mut client = build_client_app(
+                client_config,
+                ASSET_PATH.to_string(),
+                false,
+                ClientMode::Headless,
+            );
+            use_virtual_clock(&mut client, tick_duration);
+            clients.push((client_id, client));
+        }
+
+        let server_config = ServerConfig {
+            shared: SharedConfig::default(),
+            net: vec![ServerNetConfig::Netcode {
+                config: ServerNetcodeConfig::default(),
+                io: ServerIoConfig::from_transport(ServerTransport::Channels {
+                    channels: server_channels,
+                }),
+            }],
+            ..default()
+        };
+
+        let mut server = build_server_app(
+            server_config,
+            ASSET_PATH.to_string(),
+            ServerMode::Headless,
+            false,
+        );
+        server.insert_resource(LobbyConfig {
+            min_players: client_ids.len(),
+            countdown: Duration::ZERO,
+            ..default()
+        });
+        use_virtual_clock(&mut server, tick_duration);
+
+        Self {
+            server,
+            clients,
+            tick_duration,
+        }
+    }
+
+    /// Insert a resource into the server before its first step, for config read at startup
+    pub fn with_server_resource(mut self, resource: impl Resource) -> Self {
+        self.server.insert_resource(resource);
+        self
+    }
+
+    pub fn client(&self, client_id: u64) -> &App {
+        self.clients
+            .iter()
+            .find(|(id, _)| *id == client_id)
+            .map(|(_, client)| client)
+            .unwrap_or_else(|| panic!("no client {}", client_id))
+    }
+
+    pub fn client_mut(&mut self, client_id: u64) -> &mut App {
+        self.clients
+            .iter_mut()
+            .find(|(id, _)| *id == client_id)
+            .map(|(_, client)| client)
+            .unwrap_or_else(|| panic!("no client {}", client_id))
+    }
+
+    pub fn client_ids(&self) -> Vec<u64> {
+        self.clients.iter().map(|(id, _)| *id).collect()
+    }
+
+    /// Advance the server, then every client, by one tick. Waits for any level assets
+    /// being loaded first, so that loading takes no virtual time.
+    pub fn step(&mut self) {
+        self.wait_for_level_assets();
+        self.update_all();
+    }
+
+    fn update_all(&mut self) {
+        self.server.update();
+        for (_, client) in &mut self.clients {
+            client.update();
+        }
+    }
+
+    /// Level assets load on the IO task pool in real time. Until none of the apps is
+    /// loading any, keep updating them with the clock stopped, so that the load can finish
+    /// and connections can't time out while it does.
+    fn wait_for_level_assets(&mut self) {
+        let loading = |app: &App| {
+            app.world()
+                .get_resource::<State<LevelState>>()
+                .is_some_and(|state| *state.get() == LevelState::Loading)
+        };
+        if !loading(&self.server) && !self.clients.iter().any(|(_, client)| loading(client)) {
+            return;
+        }
+
+        self.set_clock(Duration::ZERO);
+        let started = Instant::now();
+        while loading(&self.server) || self.clients.iter().any(|(_, client)| loading(client)) {
+            assert!(
+                started.elapsed() < ASSET_LOAD_TIMEOUT,
+                "level assets took over {:?} to load",
+                ASSET_LOAD_TIMEOUT
+            );
+            thread::sleep(ASSET_POLL_INTERVAL);
+            self.update_all();
+        }
+        self.set_clock(self.tick_duration);
+    }
+
+    /// How far each update advances every app's clock
+    fn set_clock(&mut self, per_update: Duration) {
+        use_virtual_clock(&mut self.server, per_update);
+        for (_, client) in &mut self.clients {
+            use_virtual_clock(client, per_update);
+        }
+    }
+
+    pub fn steps(&mut self, count: usize) {
+        for _ in 0..count {
+            self.step();
+        }
+    }
+
+    /// Step until `done` holds, panicking with `what` if it never does
+    pub fn run_until(&mut self, what: &str, done: impl Fn(&Self) -> bool) {
+        for _ in 0..MAX_STEPS {
+            if done(self) {
+                return;
+            }
+            self.step();
+        }
+        panic!("gave up waiting for {} after {} steps", what, MAX_STEPS);
+    }
+
+    /// Step through `duration` of virtual time, calling `check` after every step
+    pub fn run_for(&mut self, duration: Duration, check: impl Fn(&Self)) {
+        let steps = duration.as_secs_f64() / self.tick_duration.as_secs_f64();
+        for _ in 0..steps.ceil() as usize {
+            self.step();
+            check(self);
+        }
+    }
+
+    pub fn game_state(&self, client_id: u64) -> GameState {
+        self.client(client_id)
+            .world()
+            .resource::<State<GameState>>()
+            .get()
+            .clone()
+    }
+
+    pub fn connect(&mut self, client_id: u64) {
+        self.client_mut(client_id)
+            .world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::ConnectingRemote);
+    }
+
+    pub fn connect_all(&mut self) {
+        for client_id in self.client_ids() {
+            self.connect(client_id);
+        }
+    }
+
+    pub fn set_ready(&mut self, client_id: u64, ready: bool) {
+        let world = self.client_mut(client_id).world_mut();
+        world.commands().set_ready(ready);
+        world.flush();
+    }
+
+    /// Whether the server took the client's `ClientLevelLoadComplete` and put it in the lobby
+    pub fn in_lobby(&self, client_id: u64) -> bool {
+        self.server
+            .world()
+            .iter_entities()
+            .filter_map(|entity| entity.get::<LobbyRoster>())
+            .any(|roster| roster.member(ClientId::Netcode(client_id)).is_some())
+    }
+
+    /// Whether the server spawned the client's player and the client received it
+    pub fn player_spawned(&self, client_id: u64) -> bool {
+        let has_player = |app: &App| {
+            app.world()
+                .iter_entities()
+                .filter_map(|entity| entity.get::<Player>())
+                .any(|player| player.0 == ClientId::Netcode(client_id))
+        };
+
+        has_player(&self.server) && has_player(self.client(client_id))
+    }
+
+    pub fn assert_player_spawned(&self, client_id: u64) {
+        assert!(
+            self.player_spawned(client_id),
+            "the player of client {} was not spawned",
+            client_id
+        );
+    }
+
+    /// Connect everyone, ready them up in the lobby and wait until they are all playing
+    pub fn start_match(&mut self) {
+        self.connect_all();
+
+        self.run_until("everyone in the lobby", |harness| {
+            harness.client_ids().into_iter().all(|client_id| {
+                harness.game_state(client_id) == GameState::Lobby && harness.in_lobby(client_id)
+            })
+        });
+
+        for client_id in self.client_ids() {
+            self.set_ready(client_id, true);
+        }
+
+        self.run_until("everyone playing", |harness| {
+            harness.client_ids().into_iter().all(|client_id| {
+                harness.game_state(client_id) == GameState::Playing
+                    && harness.player_spawned(client_id)
+            })
+        });
+    }
+}
+
+/// Entities in an app's world that match `F`
+pub fn count<F: QueryFilter>(app: &mut App) -> usize {
+    app.world_mut()
+        .query_filtered::<(), F>()
+        .iter(app.world())
+        .count()
+}
+
+/// Every update advances time by exactly one tick, whatever the wall clock says
+fn use_virtual_clock(app: &mut App, tick_duration: Duration) {
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(tick_duration));
+}
@@ -0,0 +1,137 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use bevy::prelude::*;
+use client::{
+    app::{ClientMode, build_client_app},
+    bot::{BotPlugin, BotStats, MovePattern},
+    conditioner::ClientConditions,
+    network::DisconnectWithReasonExt,
+};
+use common::conditioner::NetworkConditions;
+use lightyear::client::config::ClientConfig;
+
+/// Bots count up from here unless `--client_id` says otherwise, clear of hand picked ids
+pub(crate) const FIRST_BOT_CLIENT_ID: u64 = 1_000_000;
+
+/// Bots update at roughly this rate, like a client with vsync would
+const FRAME_TIME: Duration = Duration::from_millis(16);
+
+/// Frames to keep updating after disconnecting, so the server hears about it
+const DISCONNECT_FRAMES: usize = 10;
+
+/// Run a headless client per config on its own thread for `duration`, then print what each saw.
+/// Each bot's connection is conditioned with `conditions`.
+pub(crate) fn run(
+    client_configs: Vec<(u64, ClientConfig)>,
+    conditions: Option<NetworkConditions>,
+    asset_path: String,
+    duration: Duration,
+) {
+    let bots: Vec<_> = client_configs
+        .into_iter()
+        .enumerate()
+        .map(|(index, (client_id, client_config))| {
+            let asset_path = asset_path.clone();
+            let handle = thread::Builder::new()
+                .name(format!("bot {}", client_id))
+                .spawn(move || run_bot(index, client_config, conditions, asset_path, duration))
+                .expect("unable to spawn a bot thread");
+            (client_id, handle)
+        })
+        .collect();
+
+    let reports: Vec<_> = bots
+        .into_iter()
+        .map(|(client_id, handle)| (client_id, handle.join().ok()))
+        .collect();
+
+    print_reports(&reports);
+}
+
+fn run_bot(
+    index: usize,
+    client_config: ClientConfig,
+    conditions: Option<NetworkConditions>,
+    asset_path: String,
+    duration: Duration,
+) -> BotStats {
+    let mut app = build_client_app(client_config, asset_path, false, ClientMode::Headless);
+    app.insert_resource(ClientConditions::new(conditions));
+    app.add_plugins(BotPlugin {
+        pattern: MovePattern::ALL[index % MovePattern::ALL.len()],
+        phase: index as f32,
+    });
+
+    let deadline = Instant::now() + duration;
+    while Instant::now() < deadline {
+        let frame_start = Instant::now();
+        app.update();
+        thread::sleep(FRAME_TIME.saturating_sub(frame_start.elapsed()));
+    }
+
+    let stats = app.world().resource::<BotStats>().clone();
+
+    app.world_mut().commands().disconnect_by_request();
+    for _ in 0..DISCONNECT_FRAMES {
+        app.update();
+        thread::sleep(FRAME_TIME);
+    }
+
+    stats
+}
+
+fn print_reports(reports: &[(u64, Option<BotStats>)]) {
+    println!(
+        "{:>10} {:>7} {:>13} {:>12} {:>10} {:>12} {:>12}",
+        "bot", "played", "mean rtt ms", "max rtt ms", "rollbacks", "corrections", "disconnects"
+    );
+
+    for (client_id, stats) in reports {
+        let Some(stats) = stats else {
+            println!("{:>10} crashed", client_id);
+            continue;
+        };
+
+        let mean_rtt = stats
+            .mean_rtt_ms()
+            .map(|rtt| format!("{:.1}", rtt))
+            .unwrap_or_else(|| String::from("-"));
+
+        println!(
+            "{:>10} {:>7} {:>13} {:>12.1} {:>10} {:>12} {:>12}",
+            client_id,
+            stats.played,
+            mean_rtt,
+            stats.rtt_ms_max,
+            stats.rollbacks,
+            stats.corrections,
+            stats.disconnects
+        );
+    }
+
+    let finished: Vec<_> = reports
+        .iter()
+        .filter_map(|(_, stats)| stats.as_ref())
+        .collect();
+    let played = finished.iter().filter(|stats| stats.played).count();
+    let rtts: Vec<_> = finished
+        .iter()
+        .filter_map(|stats| stats.mean_rtt_ms())
+        .collect();
+
+    print!("{}/{} bots played", played, reports.len());
+    if !rtts.is_empty() {
+        print!(
+            ", mean rtt {:.1} ms",
+            rtts.iter().sum::<f32>() / rtts.len() as f32
+        );
+    }
+    println!(
+        ", {} rollbacks, {} corrections",
+        finished.iter().map(|stats| stats.rollbacks).sum::<u32>(),
+        finished.iter().map(|stats| stats.corrections).sum::<u32>()
+    );
+}
@@ -1,35 +1,88 @@
-use lightyear::prelude::{LinkConditionerConfig, TickConfig, server::ServerTransport};
+use common::{
+    conditioner::{ConditionerPreset, NetworkConditions},
+    game_mode::GameModeKind,
+};
+use lightyear::prelude::{TickConfig, server::ServerTransport};
+use protocol::{discovery::DISCOVERY_PORT, query::QUERY_PORT};
 use serde::{Deserialize, Serialize};
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
 use std::time::Duration;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SerializableLinkConditionerConfig {
     pub incoming_latency_ms: u64,
     pub incoming_jitter_ms: u64,
     pub incoming_loss: f32,
+    #[serde(default)]
+    pub outgoing_latency_ms: u64,
+    #[serde(default)]
+    pub outgoing_jitter_ms: u64,
+    #[serde(default)]
+    pub outgoing_loss: f32,
+    #[serde(default)]
+    pub duplication: f32,
 }
 
-impl From<LinkConditionerConfig> for SerializableLinkConditionerConfig {
-    fn from(config: LinkConditionerConfig) -> Self {
+impl From<NetworkConditions> for SerializableLinkConditionerConfig {
+    fn from(conditions: NetworkConditions) -> Self {
         Self {
-            incoming_latency_ms: config.incoming_latency.as_millis() as u64,
-            incoming_jitter_ms: config.incoming_jitter.as_millis() as u64,
-            incoming_loss: config.incoming_loss,
+            incoming_latency_ms: conditions.incoming_latency.as_millis() as u64,
+            incoming_jitter_ms: conditions.incoming_jitter.as_millis() as u64,
+            incoming_loss: conditions.incoming_loss,
+            outgoing_latency_ms: conditions.outgoing_latency.as_millis() as u64,
+            outgoing_jitter_ms: conditions.outgoing_jitter.as_millis() as u64,
+            outgoing_loss: conditions.outgoing_loss,
+            duplication: conditions.duplication,
         }
     }
 }
 
-impl From<SerializableLinkConditionerConfig> for LinkConditionerConfig {
+impl From<SerializableLinkConditionerConfig> for NetworkConditions {
     fn from(config: SerializableLinkConditionerConfig) -> Self {
         Self {
             incoming_latency: Duration::from_millis(config.incoming_latency_ms),
             incoming_jitter: Duration::from_millis(config.incoming_jitter_ms),
             incoming_loss: config.incoming_loss,
+            outgoing_latency: Duration::from_millis(config.outgoing_latency_ms),
+            outgoing_jitter: Duration::from_millis(config.outgoing_jitter_ms),
+            outgoing_loss: config.outgoing_loss,
+            duplication: config.duplication,
         }
     }
 }
 
+/// A preset, when given, takes the place of the explicit conditioner values
+fn conditions_from_options(
+    conditioner: SerializableLinkConditionerConfig,
+    preset: Option<ConditionerPreset>,
+) -> Option<NetworkConditions> {
+    match preset {
+        Some(preset) => preset.conditions(),
+        None => Some(NetworkConditions::from(conditioner)),
+    }
+}
+
+/// `Off` stands in for no conditioner, since the explicit values always condition
+fn conditions_to_options(
+    conditions: Option<NetworkConditions>,
+) -> (SerializableLinkConditionerConfig, Option<ConditionerPreset>) {
+    match conditions {
+        Some(conditions) => (SerializableLinkConditionerConfig::from(conditions), None),
+        None => (
+            SerializableLinkConditionerConfig::default(),
+            Some(ConditionerPreset::Off),
+        ),
+    }
+}
+
+fn default_conditions() -> Option<NetworkConditions> {
+    Some(NetworkConditions {
+        incoming_latency: Duration::from_millis(50),
+        ..NetworkConditions::default()
+    })
+}
+
 pub struct SharedLaunchOptions {
     pub protocol_id: u64,
     pub key: [u8; 32],
@@ -98,10 +151,16 @@ pub struct ClientLaunchOptions {
     pub listen_port: u16,
     pub server_addr: Ipv4Addr,
     pub server_port: u16,
-    pub conditioner: LinkConditionerConfig,
+    /// `None` leaves the connection unconditioned
+    pub conditioner: Option<NetworkConditions>,
     pub correction_ticks_factor: f32,
     pub min_delay: Duration,
     pub asset_path: String,
+    pub hot_reload: bool,
+    /// Log inputs and predictions to this file, for `launcher resim`
+    pub record_inputs: Option<PathBuf>,
+    /// Keep the player's name, as edited in the main menu, in this file
+    pub profile: Option<PathBuf>,
 }
 
 impl Default for ClientLaunchOptions {
@@ -111,14 +170,13 @@ impl Default for ClientLaunchOptions {
             listen_port: 0,
             server_addr: Ipv4Addr::LOCALHOST,
             server_port: 0,
-            conditioner: LinkConditionerConfig {
-                incoming_latency: Duration::from_millis(50),
-                incoming_jitter: Duration::ZERO,
-                incoming_loss: 0.0,
-            },
+            conditioner: None,
             correction_ticks_factor: 2.0,
             min_delay: Duration::from_millis(25),
             asset_path: String::from("../assets/assets"),
+            hot_reload: false,
+            record_inputs: None,
+            profile: None,
         }
     }
 }
@@ -130,22 +188,36 @@ pub struct SerializableClientLaunchOptions {
     pub server_addr: String,
     pub server_port: u16,
     pub conditioner: SerializableLinkConditionerConfig,
+    #[serde(default)]
+    pub conditioner_preset: Option<ConditionerPreset>,
     pub correction_ticks_factor: f32,
     pub min_delay_ms: u64,
     pub asset_path: String,
+    #[serde(default)]
+    pub hot_reload: bool,
+    #[serde(default)]
+    pub record_inputs: Option<PathBuf>,
+    #[serde(default)]
+    pub profile: Option<PathBuf>,
 }
 
 impl From<ClientLaunchOptions> for SerializableClientLaunchOptions {
     fn from(options: ClientLaunchOptions) -> Self {
+        let (conditioner, conditioner_preset) = conditions_to_options(options.conditioner);
+
         Self {
             listen_addr: options.listen_addr.to_string(),
             listen_port: options.listen_port,
             server_addr: options.server_addr.to_string(),
             server_port: options.server_port,
-            conditioner: SerializableLinkConditionerConfig::from(options.conditioner),
+            conditioner,
+            conditioner_preset,
             correction_ticks_factor: options.correction_ticks_factor,
             min_delay_ms: options.min_delay.as_millis() as u64,
             asset_path: options.asset_path,
+            hot_reload: options.hot_reload,
+            record_inputs: options.record_inputs,
+            profile: options.profile,
         }
     }
 }
@@ -163,55 +235,176 @@ impl From<SerializableClientLaunchOptions> for ClientLaunchOptions {
                 .parse()
                 .unwrap_or(Ipv4Addr::LOCALHOST),
             server_port: serializable.server_port,
-            conditioner: LinkConditionerConfig::from(serializable.conditioner),
+            conditioner: conditions_from_options(
+                serializable.conditioner,
+                serializable.conditioner_preset,
+            ),
             correction_ticks_factor: serializable.correction_ticks_factor,
             min_delay: Duration::from_millis(serializable.min_delay_ms),
             asset_path: serializable.asset_path,
+            hot_reload: serializable.hot_reload,
+            record_inputs: serializable.record_inputs,
+            profile: serializable.profile,
         }
     }
 }
 
 pub struct ServerLaunchOptions {
     pub headless: bool,
+    /// Shown to clients that find this server through LAN discovery
+    pub name: String,
+    pub discovery_port: u16,
+    /// Port status queries from `launcher query` and monitoring are answered on
+    pub query_port: u16,
+    /// Players that must be ready in the lobby before a match starts
+    pub min_players: usize,
+    pub countdown: Duration,
+    pub game_mode: GameModeKind,
+    /// Log every client's network metrics this often
+    pub metrics_log_interval: Option<Duration>,
+    /// Serve Prometheus metrics on `/metrics` at this address
+    pub metrics_endpoint: Option<SocketAddr>,
+    /// Server controlled characters spawned with each level
+    pub npc_count: usize,
+    /// Record a replay of the session to this file
+    pub record_replay: Option<PathBuf>,
+    /// `None` for no limit. Players past it wait in a queue.
+    pub max_players: Option<usize>,
+    pub max_spectators: Option<usize>,
+    /// Player slots kept free for `admin_client_ids`
+    pub reserved_slots: usize,
+    /// Not checked against anything, clients choose their own ids
+    pub admin_client_ids: Vec<u64>,
+    /// Players that can wait for a slot before the server turns them away
+    pub max_queue: usize,
     pub listen_addr: Ipv4Addr,
     pub udp_listen_port: u16,
-    pub conditioner: LinkConditionerConfig,
+    /// `None` leaves the connection unconditioned
+    pub conditioner: Option<NetworkConditions>,
     pub asset_path: String,
+    pub hot_reload: bool,
 }
 
 impl Default for ServerLaunchOptions {
     fn default() -> Self {
         Self {
             headless: false,
+            name: default_server_name(),
+            discovery_port: DISCOVERY_PORT,
+            query_port: QUERY_PORT,
+            min_players: default_min_players(),
+            countdown: Duration::from_secs(default_countdown_secs()),
+            game_mode: GameModeKind::default(),
+            metrics_log_interval: None,
+            metrics_endpoint: None,
+            npc_count: 0,
+            record_replay: None,
+            max_players: None,
+            max_spectators: None,
+            reserved_slots: 0,
+            admin_client_ids: Vec::new(),
+            max_queue: 0,
             listen_addr: Ipv4Addr::LOCALHOST,
             udp_listen_port: 12025,
-            conditioner: LinkConditionerConfig {
-                incoming_latency: Duration::from_millis(50),
-                incoming_jitter: Duration::ZERO,
-                incoming_loss: 0.0,
-            },
+            conditioner: default_conditions(),
             asset_path: String::from("../assets/assets"),
+            hot_reload: false,
         }
     }
 }
 
+fn default_server_name() -> String {
+    String::from("Server")
+}
+
+fn default_discovery_port() -> u16 {
+    DISCOVERY_PORT
+}
+
+fn default_query_port() -> u16 {
+    QUERY_PORT
+}
+
+fn default_min_players() -> usize {
+    1
+}
+
+fn default_countdown_secs() -> u64 {
+    5
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SerializableServerLaunchOptions {
     pub headless: bool,
+    #[serde(default = "default_server_name")]
+    pub name: String,
+    #[serde(default = "default_discovery_port")]
+    pub discovery_port: u16,
+    #[serde(default = "default_query_port")]
+    pub query_port: u16,
+    #[serde(default = "default_min_players")]
+    pub min_players: usize,
+    #[serde(default = "default_countdown_secs")]
+    pub countdown_secs: u64,
+    #[serde(default)]
+    pub game_mode: GameModeKind,
+    #[serde(default)]
+    pub metrics_log_secs: Option<u64>,
+    #[serde(default)]
+    pub metrics_endpoint: Option<String>,
+    #[serde(default)]
+    pub npc_count: usize,
+    #[serde(default)]
+    pub record_replay: Option<PathBuf>,
+    #[serde(default)]
+    pub max_players: Option<usize>,
+    #[serde(default)]
+    pub max_spectators: Option<usize>,
+    #[serde(default)]
+    pub reserved_slots: usize,
+    #[serde(default)]
+    pub admin_client_ids: Vec<u64>,
+    #[serde(default)]
+    pub max_queue: usize,
     pub listen_addr: String,
     pub udp_listen_port: u16,
     pub conditioner: SerializableLinkConditionerConfig,
+    #[serde(default)]
+    pub conditioner_preset: Option<ConditionerPreset>,
     pub asset_path: String,
+    #[serde(default)]
+    pub hot_reload: bool,
 }
 
 impl From<ServerLaunchOptions> for SerializableServerLaunchOptions {
     fn from(options: ServerLaunchOptions) -> Self {
+        let (conditioner, conditioner_preset) = conditions_to_options(options.conditioner);
+
         Self {
             headless: options.headless,
+            name: options.name,
+            discovery_port: options.discovery_port,
+            query_port: options.query_port,
+            min_players: options.min_players,
+            countdown_secs: options.countdown.as_secs(),
+            game_mode: options.game_mode,
+            metrics_log_secs: options
+                .metrics_log_interval
+                .map(|interval| interval.as_secs()),
+            metrics_endpoint: options.metrics_endpoint.map(|addr| addr.to_string()),
+            npc_count: options.npc_count,
+            record_replay: options.record_replay,
+            max_players: options.max_players,
+            max_spectators: options.max_spectators,
+            reserved_slots: options.reserved_slots,
+            admin_client_ids: options.admin_client_ids,
+            max_queue: options.max_queue,
             listen_addr: options.listen_addr.to_string(),
             udp_listen_port: options.udp_listen_port,
-            conditioner: SerializableLinkConditionerConfig::from(options.conditioner),
+            conditioner,
+            conditioner_preset,
             asset_path: options.asset_path,
+            hot_reload: options.hot_reload,
         }
     }
 }
@@ -220,15 +413,34 @@ impl From<SerializableServerLaunchOptions> for ServerLaunchOptions {
     fn from(serializable: SerializableServerLaunchOptions) -> Self {
         Self {
             headless: serializable.headless,
+            name: serializable.name,
+            discovery_port: serializable.discovery_port,
+            query_port: serializable.query_port,
+            min_players: serializable.min_players,
+            countdown: Duration::from_secs(serializable.countdown_secs),
+            game_mode: serializable.game_mode,
+            metrics_log_interval: serializable.metrics_log_secs.map(Duration::from_secs),
+            metrics_endpoint: serializable
+                .metrics_endpoint
+                .and_then(|addr| addr.parse().ok()),
+            npc_count: serializable.npc_count,
+            record_replay: serializable.record_replay,
+            max_players: serializable.max_players,
+            max_spectators: serializable.max_spectators,
+            reserved_slots: serializable.reserved_slots,
+            admin_client_ids: serializable.admin_client_ids,
+            max_queue: serializable.max_queue,
             listen_addr: serializable
                 .listen_addr
                 .parse()
                 .unwrap_or(Ipv4Addr::LOCALHOST),
             udp_listen_port: serializable.udp_listen_port,
-
-            conditioner: LinkConditionerConfig::from(serializable.conditioner),
-
+            conditioner: conditions_from_options(
+                serializable.conditioner,
+                serializable.conditioner_preset,
+            ),
             asset_path: serializable.asset_path,
+            hot_reload: serializable.hot_reload,
         }
     }
 }
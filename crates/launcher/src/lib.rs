@@ -0,0 +1,7 @@
+mod bots;
+mod launch_options;
+
+pub mod native;
+pub mod options_watcher;
+mod query;
+mod resim;
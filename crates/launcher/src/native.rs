@@ -1,18 +1,26 @@
 use crate::{
+    bots,
     launch_options::{ClientLaunchOptions, ServerLaunchOptions, SharedLaunchOptions},
     launch_options::{
         SerializableClientLaunchOptions, SerializableServerLaunchOptions,
         SerializableSharedLaunchOptions,
     },
+    options_watcher::OptionsWatcherPlugin,
+    query, resim,
 };
 use bevy::prelude::*;
 use clap::{Parser, ValueEnum};
-use client::app::build_client_app;
+use client::{
+    app::{ClientMode, build_client_app, build_replay_app},
+    conditioner::ClientConditions,
+    input_log::InputLogConfig,
+    profile::ProfileConfig,
+};
 use lightyear::{
     client::config::{ClientConfig, NetcodeConfig as ClientNetcodeConfig},
     connection::client::NetConfig as ClientNetConfig,
     prelude::{
-        LinkConditionerConfig, SharedConfig, TickConfig,
+        ClientId, SharedConfig, TickConfig,
         client::{
             Authentication, ClientTransport, InterpolationConfig, IoConfig as ClientIoConfig,
             PredictionConfig,
@@ -21,12 +29,27 @@ use lightyear::{
     },
     server::config::{NetcodeConfig as ServerNetcodeConfig, ServerConfig},
 };
+use protocol::replay::read_replay;
 use ron::de::from_str;
-use server::app::{ServerMode, build_server_app};
+use server::{
+    app::{ServerMode, build_server_app},
+    capacity::CapacityConfig,
+    conditioner::ServerConditions,
+    console::StdinConsolePlugin,
+    discovery::DiscoveryConfig,
+    game_mode::ActiveGameMode,
+    lobby::LobbyConfig,
+    metrics::MetricsConfig,
+    metrics_endpoint::MetricsEndpointConfig,
+    npc::NpcConfig,
+    query::QueryConfig,
+    recorder::RecorderConfig,
+};
 use std::{
     error::Error,
     fs,
-    net::{IpAddr, Ipv4Addr, SocketAddr},
+    io::BufReader,
+    net::{IpAddr, SocketAddr},
     path::{Path, PathBuf},
     time::Duration,
 };
@@ -43,12 +66,35 @@ struct Cli {
     #[arg(value_enum)]
     mode: Mode,
 
+    /// Server to ask for its status, as `host` or `host:port`. Only used by `query`.
+    addr: Option<String>,
+
     #[arg(long, default_value_t = false)]
     headless: bool,
 
+    /// Watch the asset folder and option files, applying changes while running
+    #[arg(long, default_value_t = false)]
+    hot_reload: bool,
+
     #[arg(short, long, default_value_t = 0)]
     client_id: u64,
 
+    /// Number of bots to run. Only used by `bots`.
+    #[arg(long, default_value_t = 10)]
+    count: u64,
+
+    /// Server the bots connect to, instead of the one in the client options. Only used by `bots`.
+    #[arg(long)]
+    server: Option<SocketAddr>,
+
+    /// How long the bots play before reporting. Only used by `bots`.
+    #[arg(long, default_value_t = 60)]
+    duration_secs: u64,
+
+    /// Replay to play back, or input log to resimulate. Only used by `replay` and `resim`.
+    #[arg(long, value_name = "FILE")]
+    file: Option<PathBuf>,
+
     #[arg(long, value_name = "FILE")]
     shared_options: Option<PathBuf>,
 
@@ -63,6 +109,29 @@ struct Cli {
 enum Mode {
     Client,
     Server,
+    /// Print the status of a running server
+    Query,
+    /// Load test a server with headless clients that play on their own
+    Bots,
+    /// Play back a replay recorded by a server
+    Replay,
+    /// Resimulate a client's input log and report where it first diverged from the server
+    Resim,
+}
+
+/// Read and parse the file at `config_path`, without falling back to anything
+fn read_config<T, S>(config_path: &Path) -> Result<T, String>
+where
+    T: From<S>,
+    S: serde::de::DeserializeOwned,
+{
+    let config_str = fs::read_to_string(config_path)
+        .map_err(|e| format!("Failed to read config from {:?}: {}", config_path, e))?;
+
+    let serializable_config: S = from_str(&config_str)
+        .map_err(|e| format!("Failed to parse config from {:?}: {}", config_path, e))?;
+
+    Ok(T::from(serializable_config))
 }
 
 fn load_config<T, S>(path: Option<PathBuf>, default_path: &str) -> Option<T>
@@ -76,29 +145,26 @@ where
         return None;
     }
 
-    let config_str = match fs::read_to_string(&config_path) {
-        Ok(str) => str,
-        Err(_) => {
-            println!("Warning: Failed to read config from {:?}", config_path);
-            return None;
-        }
-    };
-
-    let serializable_config: S = match from_str(&config_str) {
-        Ok(config) => config,
+    match read_config::<T, S>(&config_path) {
+        Ok(config) => Some(config),
         Err(e) => {
-            println!(
-                "Warning: Failed to parse config from {:?}: {}",
-                config_path, e
-            );
-            return None;
+            println!("Warning: {}", e);
+            None
         }
-    };
+    }
+}
 
-    Some(T::from(serializable_config))
+/// For reloading, where a file that can't be read should leave the current options alone
+/// rather than put the defaults in their place
+pub(crate) fn read_shared_options(path: &Path) -> Result<SharedLaunchOptions, String> {
+    read_config::<SharedLaunchOptions, SerializableSharedLaunchOptions>(path)
 }
 
-fn load_shared_options(path: Option<PathBuf>) -> SharedLaunchOptions {
+pub(crate) fn read_server_options(path: &Path) -> Result<ServerLaunchOptions, String> {
+    read_config::<ServerLaunchOptions, SerializableServerLaunchOptions>(path)
+}
+
+pub(crate) fn load_shared_options(path: Option<PathBuf>) -> SharedLaunchOptions {
     load_config::<SharedLaunchOptions, SerializableSharedLaunchOptions>(
         path,
         DEFAULT_SHARED_CONFIG_PATH,
@@ -114,7 +180,7 @@ fn load_client_options(path: Option<PathBuf>) -> ClientLaunchOptions {
     .unwrap_or_default()
 }
 
-fn load_server_options(path: Option<PathBuf>) -> ServerLaunchOptions {
+pub(crate) fn load_server_options(path: Option<PathBuf>) -> ServerLaunchOptions {
     load_config::<ServerLaunchOptions, SerializableServerLaunchOptions>(
         path,
         DEFAULT_SERVER_CONFIG_PATH,
@@ -122,20 +188,147 @@ fn load_server_options(path: Option<PathBuf>) -> ServerLaunchOptions {
     .unwrap_or_default()
 }
 
-pub fn run() {
-    let cli = Cli::parse();
-
-    let shared_launch_options = load_shared_options(cli.shared_options);
-
-    let shared_config = SharedConfig {
+fn shared_config(shared_launch_options: &SharedLaunchOptions) -> SharedConfig {
+    SharedConfig {
         server_replication_send_interval: shared_launch_options.server_replication_send_interval,
         client_replication_send_interval: shared_launch_options.client_replication_send_interval,
         tick: TickConfig {
             tick_duration: shared_launch_options.simulation_update_frequency,
         },
+    }
+}
+
+/// Connects to `server_addr` over UDP as `client_id`
+fn remote_client_config(
+    client_launch_options: &ClientLaunchOptions,
+    shared_launch_options: &SharedLaunchOptions,
+    client_id: u64,
+    server_addr: SocketAddr,
+) -> ClientConfig {
+    let remote_transport_config = ClientIoConfig::from_transport(ClientTransport::UdpSocket(
+        (
+            client_launch_options.listen_addr,
+            client_launch_options.listen_port,
+        )
+            .into(),
+    ));
+
+    let remote_auth = Authentication::Manual {
+        server_addr,
+        client_id,
+        private_key: shared_launch_options.key,
+        protocol_id: shared_launch_options.protocol_id,
+    };
+
+    let remote_netcode = ClientNetConfig::Netcode {
+        auth: remote_auth,
+        config: ClientNetcodeConfig {
+            token_expire_secs: -1,
+            client_timeout_secs: 5,
+            ..default()
+        },
+        io: remote_transport_config,
     };
 
+    ClientConfig {
+        shared: shared_config(shared_launch_options),
+        net: remote_netcode,
+        prediction: PredictionConfig::default()
+            .with_correction_ticks_factor(client_launch_options.correction_ticks_factor),
+        interpolation: InterpolationConfig {
+            min_delay: client_launch_options.min_delay,
+            send_interval_ratio: 0.,
+        },
+        ..default()
+    }
+}
+
+pub fn run() {
+    let cli = Cli::parse();
+
+    let shared_options_path = cli
+        .shared_options
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_SHARED_CONFIG_PATH));
+    let shared_launch_options = load_shared_options(Some(shared_options_path.clone()));
+
     match cli.mode {
+        Mode::Query => {
+            let Some(addr) = cli.addr else {
+                eprintln!("Usage: launcher query <addr>");
+                std::process::exit(2);
+            };
+
+            if let Err(e) = query::run(&addr) {
+                eprintln!("Unable to query {}: {}", addr, e);
+                std::process::exit(1);
+            }
+        }
+        Mode::Replay => {
+            let Some(path) = cli.file else {
+                eprintln!("Usage: launcher replay --file <FILE>");
+                std::process::exit(2);
+            };
+
+            let replay =
+                match fs::File::open(&path).and_then(|file| read_replay(BufReader::new(file))) {
+                    Ok(replay) => replay,
+                    Err(e) => {
+                        eprintln!("Unable to read replay {:?}: {}", path, e);
+                        std::process::exit(1);
+                    }
+                };
+
+            let client_launch_options = load_client_options(cli.client_options);
+            build_replay_app(replay, client_launch_options.asset_path).run();
+        }
+        Mode::Resim => {
+            let Some(path) = cli.file else {
+                eprintln!("Usage: launcher resim --file <FILE>");
+                std::process::exit(2);
+            };
+
+            let client_launch_options = load_client_options(cli.client_options);
+            if let Err(e) = resim::run(&path, client_launch_options.asset_path) {
+                eprintln!("Unable to resimulate {:?}: {}", path, e);
+                std::process::exit(1);
+            }
+        }
+        Mode::Bots => {
+            let client_launch_options = load_client_options(cli.client_options);
+
+            let server_addr = cli.server.unwrap_or(SocketAddr::new(
+                IpAddr::V4(client_launch_options.server_addr),
+                client_launch_options.server_port,
+            ));
+            let first_client_id = if cli.client_id == 0 {
+                bots::FIRST_BOT_CLIENT_ID
+            } else {
+                cli.client_id
+            };
+
+            let client_configs = (first_client_id..first_client_id + cli.count)
+                .map(|client_id| {
+                    let client_config = remote_client_config(
+                        &client_launch_options,
+                        &shared_launch_options,
+                        client_id,
+                        server_addr,
+                    );
+                    (client_id, client_config)
+                })
+                .collect();
+
+            println!(
+                "Running {} bots against {} for {} seconds",
+                cli.count, server_addr, cli.duration_secs
+            );
+            bots::run(
+                client_configs,
+                client_launch_options.conditioner,
+                client_launch_options.asset_path,
+                Duration::from_secs(cli.duration_secs),
+            );
+        }
         Mode::Client => {
             if cli.client_id == 0 {
                 panic!(
@@ -145,50 +338,40 @@ pub fn run() {
 
             let client_launch_options = load_client_options(cli.client_options);
 
-            let (from_server_send, from_server_recv) = crossbeam_channel::unbounded();
-            let (to_server_send, to_server_recv) = crossbeam_channel::unbounded();
-
-            let local_transport_config =
-                ClientIoConfig::from_transport(ClientTransport::LocalChannel {
-                    recv: from_server_recv.clone(),
-                    send: to_server_send.clone(),
-                });
-
-            let local_auth = Authentication::Manual {
-                server_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
-                client_id: cli.client_id,
-                private_key: shared_launch_options.key,
-                protocol_id: shared_launch_options.protocol_id,
-            };
-
-            let local_netcode = ClientNetConfig::Netcode {
-                auth: local_auth,
-                config: ClientNetcodeConfig {
-                    token_expire_secs: -1,
-                    client_timeout_secs: 5,
-                    ..default()
-                },
-                io: local_transport_config,
-            };
-
-            let client_config = ClientConfig {
-                shared: shared_config,
-                net: local_netcode,
-                prediction: PredictionConfig::default()
-                    .with_correction_ticks_factor(client_launch_options.correction_ticks_factor),
-                interpolation: InterpolationConfig {
-                    min_delay: client_launch_options.min_delay,
-                    send_interval_ratio: 0.,
-                },
-                ..default()
-            };
+            let server_addr = SocketAddr::new(
+                IpAddr::V4(client_launch_options.server_addr),
+                client_launch_options.server_port,
+            );
+            let client_config = remote_client_config(
+                &client_launch_options,
+                &shared_launch_options,
+                cli.client_id,
+                server_addr,
+            );
 
-            build_client_app(client_config, client_launch_options.asset_path).run();
+            build_client_app(
+                client_config,
+                client_launch_options.asset_path,
+                cli.hot_reload || client_launch_options.hot_reload,
+                ClientMode::Windowed,
+            )
+            .insert_resource(InputLogConfig {
+                path: client_launch_options.record_inputs,
+            })
+            .insert_resource(ProfileConfig {
+                path: client_launch_options.profile,
+            })
+            .insert_resource(ClientConditions::new(client_launch_options.conditioner))
+            .run();
         }
         Mode::Server => {
-            let server_launch_options = load_server_options(cli.server_options);
+            let server_options_path = cli
+                .server_options
+                .unwrap_or_else(|| PathBuf::from(DEFAULT_SERVER_CONFIG_PATH));
+            let server_launch_options = load_server_options(Some(server_options_path.clone()));
 
             let headless = cli.headless || server_launch_options.headless;
+            let hot_reload = cli.hot_reload || server_launch_options.hot_reload;
 
             let server_netcode_config = ServerNetcodeConfig::default()
                 .with_protocol_id(shared_launch_options.protocol_id)
@@ -203,12 +386,11 @@ pub fn run() {
                         server_launch_options.udp_listen_port,
                     )
                         .into(),
-                ))
-                .with_conditioner(server_launch_options.conditioner.clone()),
+                )),
             }];
 
             let server_config = ServerConfig {
-                shared: shared_config,
+                shared: shared_config(&shared_launch_options),
                 net: net_configs,
                 ..default()
             };
@@ -219,7 +401,61 @@ pub fn run() {
                 ServerMode::Windowed
             };
 
-            build_server_app(server_config, server_launch_options.asset_path, mode).run();
+            let mut app = build_server_app(
+                server_config,
+                server_launch_options.asset_path,
+                mode,
+                hot_reload,
+            );
+
+            app.insert_resource(DiscoveryConfig {
+                name: server_launch_options.name,
+                port: server_launch_options.discovery_port,
+            })
+            .insert_resource(QueryConfig {
+                port: server_launch_options.query_port,
+                ..default()
+            })
+            .insert_resource(LobbyConfig {
+                min_players: server_launch_options.min_players,
+                countdown: server_launch_options.countdown,
+                ..default()
+            })
+            .insert_resource(MetricsConfig {
+                log_interval: server_launch_options.metrics_log_interval,
+            })
+            .insert_resource(MetricsEndpointConfig {
+                addr: server_launch_options.metrics_endpoint,
+            })
+            .insert_resource(NpcConfig {
+                count: server_launch_options.npc_count,
+            })
+            .insert_resource(RecorderConfig {
+                path: server_launch_options.record_replay,
+            })
+            .insert_resource(CapacityConfig {
+                max_players: server_launch_options.max_players,
+                max_spectators: server_launch_options.max_spectators,
+                reserved_slots: server_launch_options.reserved_slots,
+                admins: server_launch_options
+                    .admin_client_ids
+                    .into_iter()
+                    .map(ClientId::Netcode)
+                    .collect(),
+                max_queue: server_launch_options.max_queue,
+            })
+            .insert_resource(ActiveGameMode(server_launch_options.game_mode.create()))
+            .insert_resource(ServerConditions(server_launch_options.conditioner))
+            .add_plugins(StdinConsolePlugin);
+
+            if hot_reload {
+                app.add_plugins(OptionsWatcherPlugin {
+                    shared_options_path,
+                    server_options_path,
+                });
+            }
+
+            app.run();
         }
     }
 }
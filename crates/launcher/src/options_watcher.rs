@@ -0,0 +1,86 @@
+use std::{fs, path::PathBuf, time::SystemTime};
+
+use bevy::prelude::*;
+use server::hot_reload::ServerOptionsReloaded;
+
+use crate::native::{read_server_options, read_shared_options};
+
+const POLL_INTERVAL_SECS: f32 = 1.0;
+
+/// Polls the option files a server was launched with, and forwards the values that can be
+/// changed at runtime to the server app whenever either file is saved. A save that can't be
+/// parsed is logged and leaves the server as it was, until the file is saved again.
+pub struct OptionsWatcherPlugin {
+    pub shared_options_path: PathBuf,
+    pub server_options_path: PathBuf,
+}
+
+impl Plugin for OptionsWatcherPlugin {
+    fn build(&self, app: &mut App) {
+        let watched = [&self.shared_options_path, &self.server_options_path]
+            .into_iter()
+            .map(|path| (path.clone(), modified_time(path)))
+            .collect();
+
+        app.insert_resource(WatchedOptions {
+            files: watched,
+            shared_options_path: self.shared_options_path.clone(),
+            server_options_path: self.server_options_path.clone(),
+            timer: Timer::from_seconds(POLL_INTERVAL_SECS, TimerMode::Repeating),
+        })
+        .add_systems(Update, poll_options_files);
+    }
+}
+
+#[derive(Resource)]
+struct WatchedOptions {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    shared_options_path: PathBuf,
+    server_options_path: PathBuf,
+    timer: Timer,
+}
+
+fn modified_time(path: &PathBuf) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+fn poll_options_files(
+    time: Res<Time>,
+    mut watched: ResMut<WatchedOptions>,
+    mut reloaded_events: EventWriter<ServerOptionsReloaded>,
+) {
+    if !watched.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let mut changed = false;
+    for (path, last_modified) in watched.files.iter_mut() {
+        let modified = modified_time(path);
+        if modified != *last_modified {
+            *last_modified = modified;
+            changed = true;
+        }
+    }
+
+    if !changed {
+        return;
+    }
+
+    info!("launch options changed on disk, reloading");
+
+    let options = read_shared_options(&watched.shared_options_path).and_then(|shared| {
+        read_server_options(&watched.server_options_path).map(|server| (shared, server))
+    });
+    let (shared_options, server_options) = match options {
+        Ok(options) => options,
+        Err(e) => {
+            error!("{}, keeping the current options", e);
+            return;
+        }
+    };
+
+    reloaded_events.send(ServerOptionsReloaded {
+        tick_duration: shared_options.simulation_update_frequency,
+        conditioner: server_options.conditioner,
+    });
+}
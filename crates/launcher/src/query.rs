@@ -0,0 +1,20 @@
+use std::error::Error;
+
+use protocol::query::{QUERY_VERSION, query_status, resolve_query_addr};
+
+/// Ask the server at `addr` (`host` or `host:port`) for its status and print it
+pub(crate) fn run(addr: &str) -> Result<(), Box<dyn Error>> {
+    let server_addr = resolve_query_addr(addr)?;
+    let status = query_status(server_addr)?;
+
+    if status.version != QUERY_VERSION {
+        eprintln!(
+            "Warning: server answered with query version {}, expected {}",
+            status.version, QUERY_VERSION
+        );
+    }
+
+    println!("{}", serde_json::to_string_pretty(&status)?);
+
+    Ok(())
+}
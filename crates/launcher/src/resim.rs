@@ -0,0 +1,42 @@
+use std::{fs::File, io::BufReader, path::Path};
+
+use common::resim::{Divergence, resimulate};
+use protocol::input_log::read_input_log;
+
+/// Resimulate the input log at `path` and print where it first diverged
+pub(crate) fn run(path: &Path, asset_path: String) -> std::io::Result<()> {
+    let log = read_input_log(BufReader::new(File::open(path)?))?;
+    let report = resimulate(&log, asset_path);
+
+    println!(
+        "Resimulated {} ticks of client {}, {} of them confirmed by the server, {} gaps",
+        report.ticks, log.header.client_id, report.confirmed_ticks, report.gaps
+    );
+    print_divergence("Resimulation against the server", report.first_divergence);
+    print_divergence("Prediction against the server", report.first_misprediction);
+    print_divergence(
+        "Resimulation against the prediction",
+        report.first_prediction_mismatch,
+    );
+
+    Ok(())
+}
+
+fn print_divergence(what: &str, divergence: Option<Divergence>) {
+    match divergence {
+        Some(divergence) => println!(
+            "{}: first diverged on tick {}, at {:.3} instead of {:.3}, moving {}",
+            what,
+            divergence.tick,
+            divergence.actual,
+            divergence.expected,
+            divergence
+                .movement
+                .map_or(String::from("unknown"), |movement| format!(
+                    "{:.3}",
+                    movement
+                )),
+        ),
+        None => println!("{}: never diverged", what),
+    }
+}
@@ -0,0 +1,92 @@
+//! Saves the option files a server watches, and checks that a save that can't be parsed is
+//! ignored instead of putting the defaults in place of the current options.
+
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use launcher::options_watcher::OptionsWatcherPlugin;
+use server::hot_reload::ServerOptionsReloaded;
+
+const OPTIONS_PATH: &str = "options";
+
+/// Each save gets its own modified time, however quickly the test writes them
+fn save(path: &Path, contents: &str, saves: &mut u64) {
+    fs::write(path, contents).unwrap();
+    *saves += 1;
+    File::options()
+        .write(true)
+        .open(path)
+        .and_then(|file| {
+            file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000 + *saves))
+        })
+        .unwrap();
+}
+
+/// Step past the next poll, and return what was reloaded
+fn poll(app: &mut App) -> Vec<ServerOptionsReloaded> {
+    app.update();
+    app.world_mut()
+        .resource_mut::<Events<ServerOptionsReloaded>>()
+        .drain()
+        .collect()
+}
+
+#[test]
+fn malformed_options_are_not_applied() {
+    let dir = std::env::temp_dir().join(format!("options_watcher_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let shared_options_path = dir.join("shared_options.ron");
+    let server_options_path = dir.join("server_options.ron");
+    let server_options =
+        fs::read_to_string(PathBuf::from(OPTIONS_PATH).join("server_options.ron")).unwrap();
+
+    let mut saves = 0;
+    save(
+        &shared_options_path,
+        &fs::read_to_string(PathBuf::from(OPTIONS_PATH).join("shared_options.ron")).unwrap(),
+        &mut saves,
+    );
+    save(&server_options_path, &server_options, &mut saves);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs(2)))
+        .add_event::<ServerOptionsReloaded>()
+        .add_plugins(OptionsWatcherPlugin {
+            shared_options_path,
+            server_options_path: server_options_path.clone(),
+        });
+    app.update();
+    assert!(poll(&mut app).is_empty(), "nothing was saved yet");
+
+    let slower = server_options.replace("incoming_latency_ms: 50", "incoming_latency_ms: 200");
+    assert_ne!(slower, server_options);
+    save(&server_options_path, &slower, &mut saves);
+    let reloaded = poll(&mut app);
+    assert_eq!(reloaded.len(), 1);
+    assert_eq!(
+        reloaded[0].conditioner.unwrap().incoming_latency,
+        Duration::from_millis(200)
+    );
+
+    save(&server_options_path, "(headless: fals", &mut saves);
+    assert!(
+        poll(&mut app).is_empty(),
+        "malformed options should leave the server alone"
+    );
+    assert!(poll(&mut app).is_empty());
+
+    save(&server_options_path, &server_options, &mut saves);
+    let reloaded = poll(&mut app);
+    assert_eq!(reloaded.len(), 1);
+    assert_eq!(
+        reloaded[0].conditioner.unwrap().incoming_latency,
+        Duration::from_millis(50)
+    );
+
+    let _ = fs::remove_dir_all(dir);
+}
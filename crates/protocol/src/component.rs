@@ -8,25 +8,141 @@ use lightyear::{
     utils::bevy::TransformLinearInterpolation,
 };
 
+use crate::fingerprint::AppProtocolExt;
+
 #[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Player(pub ClientId);
 
+/// Longest display name, in characters, the server will accept
+pub const MAX_PLAYER_NAME_LENGTH: usize = 16;
+
+/// Display name of a player, validated and made unique by the server.
+/// Replicated alongside `Player`.
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PlayerName(pub String);
+
+/// The display name of every connected client that sent its profile, players and
+/// spectators alike, for anything that shows a client id. Replicated on the same
+/// entity as `MatchStatus`.
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct ClientNames(pub Vec<(ClientId, String)>);
+
+impl ClientNames {
+    pub fn get(&self, client_id: ClientId) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(named, _)| *named == client_id)
+            .map(|(_, name)| name.as_str())
+    }
+
+    /// The client's name, or a stand-in until the server has sent it
+    pub fn label(&self, client_id: ClientId) -> String {
+        self.get(client_id)
+            .map_or_else(|| format!("Player {}", client_id), str::to_string)
+    }
+}
+
+/// A character the server moves on its own, with the same movement code as players
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Npc;
+
+/// Which side a player is on. Assigned by the server when a client connects.
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Team(pub u8);
+
+/// A point only the members of `team` know about, e.g. where their team gathers.
+/// Entities carrying this are only ever replicated to that team.
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TeamWaypoint {
+    pub team: Team,
+    pub position: Vec3,
+}
+
+/// Phases of a match. A state on the server, followed by clients through `MatchStatus`.
+#[derive(States, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum MatchState {
+    /// Waiting for enough players to join and ready up
+    #[default]
+    Lobby,
+    /// Everyone is ready, the match starts when the countdown ends
+    Countdown,
+    InProgress,
+    /// The match is over, back to the lobby when the timer ends
+    PostMatch,
+}
+
+/// Replicated on a single entity, so clients can follow the server's `MatchState`
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MatchStatus {
+    pub state: MatchState,
+    /// Whole seconds left in the countdown, the round or the post match phase.
+    /// Zero when the current phase has no time limit.
+    pub remaining_secs: u32,
+    /// Name of the game mode the server is running
+    pub game_mode: String,
+    /// Winner of the round that just ended, `None` during a round or after a draw
+    pub winner: Option<ClientId>,
+}
+
+/// Points a player has scored this round, kept by the server's game mode
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Score(pub i32);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LobbyMember {
+    pub client_id: ClientId,
+    pub team: Team,
+    pub ready: bool,
+}
+
+/// Every client that has finished loading the level, in the order they joined.
+/// Replicated on the same entity as `MatchStatus`.
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct LobbyRoster(pub Vec<LobbyMember>);
+
+impl LobbyRoster {
+    pub fn member(&self, client_id: ClientId) -> Option<&LobbyMember> {
+        self.0.iter().find(|member| member.client_id == client_id)
+    }
+}
+
 pub fn register_components(app: &mut App) {
-    app.register_component::<Player>(ChannelDirection::ServerToClient)
+    app.register_protocol_component::<Player>(ChannelDirection::ServerToClient)
         .add_prediction(ComponentSyncMode::Once)
         .add_interpolation(ComponentSyncMode::Once);
 
-    app.register_component::<Position>(ChannelDirection::ServerToClient)
+    app.register_protocol_component::<PlayerName>(ChannelDirection::ServerToClient)
+        .add_prediction(ComponentSyncMode::Simple)
+        .add_interpolation(ComponentSyncMode::Simple);
+
+    app.register_protocol_component::<Npc>(ChannelDirection::ServerToClient)
+        .add_interpolation(ComponentSyncMode::Once);
+
+    app.register_protocol_component::<Team>(ChannelDirection::ServerToClient)
+        .add_prediction(ComponentSyncMode::Once)
+        .add_interpolation(ComponentSyncMode::Once);
+
+    app.register_protocol_component::<TeamWaypoint>(ChannelDirection::ServerToClient);
+
+    app.register_protocol_component::<Position>(ChannelDirection::ServerToClient)
         .add_prediction(ComponentSyncMode::Full)
         .add_interpolation(ComponentSyncMode::Full)
         .add_interpolation_fn(|start, end, t| Position(start.lerp(**end, t)))
         .add_correction_fn(|start, end, t| Position(start.lerp(**end, t)));
 
-    app.register_component::<Rotation>(ChannelDirection::ServerToClient)
+    app.register_protocol_component::<Rotation>(ChannelDirection::ServerToClient)
         .add_prediction(ComponentSyncMode::Full)
         .add_interpolation(ComponentSyncMode::Full)
         .add_interpolation_fn(|start, end, t| Rotation(*start.slerp(*end, t)))
         .add_correction_fn(|start, end, t| Rotation(*start.slerp(*end, t)));
 
+    app.register_protocol_component::<MatchStatus>(ChannelDirection::ServerToClient);
+
+    app.register_protocol_component::<LobbyRoster>(ChannelDirection::ServerToClient);
+
+    app.register_protocol_component::<ClientNames>(ChannelDirection::ServerToClient);
+
+    app.register_protocol_component::<Score>(ChannelDirection::ServerToClient);
+
     app.add_interpolation_fn::<Transform>(TransformLinearInterpolation::lerp);
 }
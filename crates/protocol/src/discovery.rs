@@ -0,0 +1,40 @@
+use std::net::IpAddr;
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::message::Level;
+
+/// Well known port servers listen on for discovery queries
+pub const DISCOVERY_PORT: u16 = 12028;
+
+/// Prefixes every discovery packet, so that stray traffic on the port is ignored
+const DISCOVERY_MAGIC: &[u8; 4] = b"LTDQ";
+
+/// Sent by clients, usually broadcast, to find servers on the local network.
+/// Discovery happens outside of lightyear, before any connection exists.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DiscoveryQuery;
+
+/// A server's answer to a `DiscoveryQuery`, sent back to the address the query came from
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DiscoveryResponse {
+    pub name: String,
+    pub current_level: Level,
+    pub player_count: u32,
+    /// The server's `ProtocolFingerprint`, so incompatible servers can be shown as such
+    pub protocol_fingerprint: u64,
+    /// Where the game itself is served. `None` for the ip the response came from.
+    pub game_ip: Option<IpAddr>,
+    pub game_port: u16,
+}
+
+pub fn encode_discovery_packet<T: Serialize>(packet: &T) -> Option<Vec<u8>> {
+    let mut bytes = DISCOVERY_MAGIC.to_vec();
+    bincode::serialize_into(&mut bytes, packet).ok()?;
+    Some(bytes)
+}
+
+pub fn decode_discovery_packet<T: DeserializeOwned>(bytes: &[u8]) -> Option<T> {
+    let payload = bytes.strip_prefix(DISCOVERY_MAGIC.as_slice())?;
+    bincode::deserialize(payload).ok()
+}
@@ -0,0 +1,135 @@
+use std::{any::type_name, hash::Hasher};
+
+use bevy::prelude::*;
+use lightyear::{prelude::*, protocol::component::ComponentRegistration};
+use serde::de::DeserializeOwned;
+
+use crate::schema;
+
+/// Everything registered with lightyear through `AppProtocolExt`, in registration order,
+/// with the shape of each type. Folded into the `ProtocolFingerprint` once registration is done.
+#[derive(Resource, Default, Debug)]
+pub struct ProtocolSchema(Vec<String>);
+
+impl ProtocolSchema {
+    /// Describe a type lightyear registers on its own, such as the inputs of its input plugin.
+    /// Panics if the type can't be described, since it would add nothing to the fingerprint.
+    pub fn add<T: DeserializeOwned>(&mut self, kind: &str) {
+        let description = schema::describe::<T>().unwrap_or_else(|e| {
+            panic!(
+                "unable to describe {} {} for the protocol fingerprint: {}",
+                kind,
+                type_name::<T>(),
+                e
+            )
+        });
+        self.0
+            .push(format!("{} {}\n{}", kind, type_name::<T>(), description));
+    }
+}
+
+/// Registers components, messages and channels with lightyear and adds them to the
+/// `ProtocolSchema` in the same call, so neither can miss something the other has.
+pub trait AppProtocolExt {
+    fn register_protocol_component<C>(
+        &mut self,
+        direction: ChannelDirection,
+    ) -> ComponentRegistration<'_, C>
+    where
+        C: Component + Message + Serialize + DeserializeOwned + PartialEq;
+
+    fn register_protocol_message<M>(&mut self, direction: ChannelDirection) -> &mut Self
+    where
+        M: Message + Serialize + DeserializeOwned;
+
+    fn add_protocol_channel<C: Channel>(&mut self, settings: ChannelSettings) -> &mut Self;
+}
+
+impl AppProtocolExt for App {
+    fn register_protocol_component<C>(
+        &mut self,
+        direction: ChannelDirection,
+    ) -> ComponentRegistration<'_, C>
+    where
+        C: Component + Message + Serialize + DeserializeOwned + PartialEq,
+    {
+        self.world_mut()
+            .resource_mut::<ProtocolSchema>()
+            .add::<C>(&format!("component {:?}", direction));
+        self.register_component::<C>(direction)
+    }
+
+    fn register_protocol_message<M>(&mut self, direction: ChannelDirection) -> &mut Self
+    where
+        M: Message + Serialize + DeserializeOwned,
+    {
+        self.world_mut()
+            .resource_mut::<ProtocolSchema>()
+            .add::<M>(&format!("message {:?}", direction));
+        self.register_message::<M>(direction);
+        self
+    }
+
+    fn add_protocol_channel<C: Channel>(&mut self, settings: ChannelSettings) -> &mut Self {
+        self.world_mut()
+            .resource_mut::<ProtocolSchema>()
+            .0
+            .push(format!("channel {} {:?}", type_name::<C>(), settings.mode));
+        self.add_channel::<C>(settings);
+        self
+    }
+}
+
+/// Identifies a build of the protocol. Sent to every client in `ServerHello`, and clients
+/// whose own fingerprint differs disconnect with `DisconnectReason::ProtocolMismatch`.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq, Deref)]
+pub struct ProtocolFingerprint(pub u64);
+
+impl ProtocolFingerprint {
+    pub fn from_schema(schema: &ProtocolSchema) -> Self {
+        let mut hasher = ContentHasher::default();
+
+        hasher.write(env!("CARGO_PKG_VERSION").as_bytes());
+        for entry in &schema.0 {
+            hasher.write(entry.as_bytes());
+            // Separator so that ["ab", "c"] and ["a", "bc"] don't collide
+            hasher.write_u8(0);
+        }
+
+        Self(hasher.finish())
+    }
+}
+
+/// 64 bit FNV-1a. Unlike `DefaultHasher`, the output is stable across
+/// platforms, builds and Rust versions, so it can be compared over the network.
+pub struct ContentHasher(u64);
+
+impl ContentHasher {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    pub fn hash_bytes(bytes: &[u8]) -> u64 {
+        let mut hasher = Self::default();
+        hasher.write(bytes);
+        hasher.finish()
+    }
+}
+
+impl Default for ContentHasher {
+    fn default() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+}
+
+impl Hasher for ContentHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+}
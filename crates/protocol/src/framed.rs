@@ -0,0 +1,93 @@
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    marker::PhantomData,
+    time::Duration,
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+/// How often recordings are flushed, so that a crash loses at most this much of one
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The header of a file made of a magic, the header, then bincode frames until the end
+pub trait FramedHeader: Serialize + DeserializeOwned {
+    type Frame: Serialize + DeserializeOwned;
+
+    /// Starts every file, so that anything else is rejected up front
+    const MAGIC: &'static [u8; 4];
+    /// The only version that can be read back
+    const VERSION: u32;
+    /// What the file is called in errors
+    const NAME: &'static str;
+
+    fn version(&self) -> u32;
+}
+
+pub(crate) fn invalid_data(e: bincode::Error) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, e)
+}
+
+/// Streams frames to a file as they are recorded
+pub struct FramedWriter<H: FramedHeader, W: Write> {
+    writer: W,
+    header: PhantomData<H>,
+}
+
+impl<H: FramedHeader, W: Write> FramedWriter<H, W> {
+    pub fn new(mut writer: W, header: &H) -> io::Result<Self> {
+        writer.write_all(H::MAGIC)?;
+        bincode::serialize_into(&mut writer, header).map_err(invalid_data)?;
+        Ok(Self {
+            writer,
+            header: PhantomData,
+        })
+    }
+
+    pub fn write_frame(&mut self, frame: &H::Frame) -> io::Result<()> {
+        bincode::serialize_into(&mut self.writer, frame).map_err(invalid_data)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Read a whole file, tolerating a last frame cut short by the writer stopping mid write
+pub fn read_framed<H: FramedHeader>(mut reader: impl Read) -> io::Result<(H, Vec<H::Frame>)> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != H::MAGIC {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("missing the {} magic", H::NAME),
+        ));
+    }
+
+    let header: H = bincode::deserialize_from(&mut reader).map_err(invalid_data)?;
+    if header.version() != H::VERSION {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "{} version {} is not supported, expected {}",
+                H::NAME,
+                header.version(),
+                H::VERSION
+            ),
+        ));
+    }
+
+    let mut frames = Vec::new();
+    loop {
+        match bincode::deserialize_from::<_, H::Frame>(&mut reader) {
+            Ok(frame) => frames.push(frame),
+            Err(e) => match *e {
+                bincode::ErrorKind::Io(ref io_error)
+                    if io_error.kind() == ErrorKind::UnexpectedEof =>
+                {
+                    return Ok((header, frames));
+                }
+                _ => return Err(invalid_data(e)),
+            },
+        }
+    }
+}
@@ -2,6 +2,8 @@ use bevy::prelude::*;
 use leafwing_input_manager::prelude::*;
 use lightyear::prelude::*;
 
+use crate::fingerprint::ProtocolSchema;
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash, Reflect, Actionlike)]
 pub enum NetworkedInput {
     #[actionlike(DualAxis)]
@@ -15,4 +17,9 @@ pub fn register_input(app: &mut App) {
             ..default()
         },
     });
+
+    // The input plugin registers its messages itself, so describe the actions here
+    app.world_mut()
+        .resource_mut::<ProtocolSchema>()
+        .add::<NetworkedInput>("input");
 }
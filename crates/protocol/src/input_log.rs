@@ -0,0 +1,77 @@
+use std::{
+    io::{self, Read},
+    time::Duration,
+};
+
+use bevy::math::{Quat, Vec2, Vec3};
+use lightyear::prelude::ClientId;
+use serde::{Deserialize, Serialize};
+
+use crate::framed::{FramedHeader, FramedWriter, read_framed};
+
+/// Bumped whenever the layout of the types below changes
+pub const INPUT_LOG_VERSION: u32 = 1;
+
+/// Written once, right after the magic
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct InputLogHeader {
+    pub version: u32,
+    /// Whose player the log follows
+    pub client_id: ClientId,
+    /// Time between two ticks
+    pub tick_duration: Duration,
+}
+
+impl FramedHeader for InputLogHeader {
+    type Frame = InputLogFrame;
+
+    const MAGIC: &'static [u8; 4] = b"LTIL";
+    const VERSION: u32 = INPUT_LOG_VERSION;
+    const NAME: &'static str = "input log";
+
+    fn version(&self) -> u32 {
+        self.version
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct BodyState {
+    pub position: Vec3,
+    pub rotation: Quat,
+}
+
+/// Where the server had the player at a tick, as the client received it
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ConfirmedState {
+    pub tick: u16,
+    pub body: BodyState,
+}
+
+/// What the client simulated for its own player in one tick
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct InputLogFrame {
+    /// lightyear's tick, which wraps around
+    pub tick: u16,
+    /// `NetworkedInput::Move`, when the action state had any
+    pub movement: Option<Vec2>,
+    /// After the tick was simulated, before any later rollback corrected it
+    pub predicted: BodyState,
+    /// Set on the ticks where a newer server state arrived. It is usually for an earlier tick.
+    pub confirmed: Option<ConfirmedState>,
+}
+
+/// A whole input log, read into memory
+#[derive(Clone, Debug)]
+pub struct InputLog {
+    pub header: InputLogHeader,
+    pub frames: Vec<InputLogFrame>,
+}
+
+/// Streams frames to an input log as they are recorded
+pub type InputLogWriter<W> = FramedWriter<InputLogHeader, W>;
+
+/// Read an input log, tolerating a last frame cut short by the client stopping mid write
+pub fn read_input_log(reader: impl Read) -> io::Result<InputLog> {
+    let (header, frames) = read_framed(reader)?;
+    Ok(InputLog { header, frames })
+}
@@ -1,15 +1,31 @@
 use bevy::prelude::*;
+use fingerprint::{ProtocolFingerprint, ProtocolSchema};
 
 pub mod component;
+pub mod discovery;
+pub mod fingerprint;
+pub mod framed;
 pub mod input;
+pub mod input_log;
 pub mod message;
+pub mod metrics;
+pub mod query;
+pub mod replay;
+pub mod schema;
 
 pub struct ProtocolPlugin;
 
 impl Plugin for ProtocolPlugin {
     fn build(&self, app: &mut App) {
+        app.init_resource::<ProtocolSchema>();
+
         component::register_components(app);
         message::register_messages(app);
         input::register_input(app);
+        metrics::register_metrics(app);
+
+        let fingerprint =
+            ProtocolFingerprint::from_schema(app.world().resource::<ProtocolSchema>());
+        app.insert_resource(fingerprint);
     }
 }
@@ -1,6 +1,8 @@
 use bevy::prelude::*;
 use lightyear::prelude::*;
 
+use crate::fingerprint::AppProtocolExt;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash, Copy, Default)]
 pub enum Level {
     #[default]
@@ -8,14 +10,144 @@ pub enum Level {
     Example,
 }
 
+/// Content hash of one of the files that make up a level, relative to the asset root
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AssetHash {
+    pub path: String,
+    pub hash: u64,
+}
+
+/// Sent to every client as soon as it connects, before anything else. It is the first message
+/// registered, on the first channel, and must never change, so that a client built from any other
+/// protocol can still read it and tell the user why it can't play on this server.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ServerHello {
+    /// The server's `ProtocolFingerprint`
+    pub protocol_fingerprint: u64,
+}
+
+/// Sent once the server has a slot or a place in the queue for the client
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct ServerWelcome {
     pub current_level: Level,
+    /// Hashes of the server's copy of the assets for `current_level`
+    pub level_asset_hashes: Vec<AssetHash>,
+}
+
+/// Sent in reply to a `ServerHello` with a matching fingerprint, with what the client wants to
+/// be known as and whether it is joining to play or to watch. The server answers with `ServerWelcome`, or turns
+/// the client away if there is no room for it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ClientProfile {
+    /// Checked and made unique by the server, which replicates the result as `PlayerName`
+    pub name: String,
+    /// Spectators watch without joining the lobby or ever getting a player
+    pub spectator: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct ClientLevelLoadComplete;
 
+/// Toggle whether this client is ready for the match to start
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ClientSetReady(pub bool);
+
+/// Sent to a client waiting for a player slot whenever its place in the queue changes
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ServerQueuePosition {
+    /// 1 for the next client to get a slot
+    pub position: u32,
+    pub queue_length: u32,
+}
+
+/// Longest chat message, in characters, the server will pass on
+pub const MAX_CHAT_LENGTH: usize = 200;
+
+/// Who a chat message is for
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChatChannel {
+    All,
+    /// The sender's team
+    Team,
+    /// Only the sender and this client
+    Whisper(ClientId),
+    /// From the server itself. Clients can't send on it.
+    System,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ClientChat {
+    pub channel: ChatChannel,
+    pub text: String,
+}
+
+/// A chat message passed on by the server, after moderation
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ServerChat {
+    pub channel: ChatChannel,
+    /// `None` for system messages
+    pub from: Option<ClientId>,
+    pub text: String,
+}
+
+/// The server's copy of the current level changed on disk and has been rebuilt.
+/// Clients should reload their own copy.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ServerLevelReload {
+    pub level_asset_hashes: Vec<AssetHash>,
+}
+
+/// Why a client's session ended
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum DisconnectReason {
+    /// The connection dropped without either side asking for it
+    Timeout,
+    Kicked(String),
+    /// The client and server were built from different protocols, so nothing past the
+    /// `ServerHello` can be read
+    ProtocolMismatch {
+        server: u64,
+        client: u64,
+    },
+    /// The client's assets differ from the server's
+    VersionMismatch(String),
+    ServerShutdown,
+    /// The client couldn't load or spawn into the level it was sent
+    LoadFailure(String),
+    /// No slot was free and the queue, if any, was full too
+    ServerFull(String),
+}
+
+impl std::fmt::Display for DisconnectReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DisconnectReason::Timeout => write!(f, "Lost connection to the server"),
+            DisconnectReason::Kicked(reason) => write!(f, "Kicked from the server: {}", reason),
+            DisconnectReason::ProtocolMismatch { server, client } => write!(
+                f,
+                "The server runs a different version of the game (protocol {:016x}, ours {:016x})",
+                server, client
+            ),
+            DisconnectReason::VersionMismatch(details) => {
+                write!(f, "Version mismatch: {}", details)
+            }
+            DisconnectReason::ServerShutdown => write!(f, "The server shut down"),
+            DisconnectReason::LoadFailure(details) => write!(f, "Failed to load: {}", details),
+            DisconnectReason::ServerFull(details) => write!(f, "The server is full: {}", details),
+        }
+    }
+}
+
+/// Sent to a client right before the server disconnects it, so it can tell the user why
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ServerDisconnectNotice {
+    pub reason: DisconnectReason,
+}
+
+/// Only carries `ServerHello`
+#[derive(Channel)]
+pub struct Handshake;
+
 #[derive(Channel)]
 pub struct UnorderedReliable;
 
@@ -23,16 +155,38 @@ pub struct UnorderedReliable;
 pub struct Reliable;
 
 pub fn register_messages(app: &mut App) {
-    app.register_message::<ServerWelcome>(ChannelDirection::ServerToClient);
+    // Must stay first, see `ServerHello`
+    app.register_protocol_message::<ServerHello>(ChannelDirection::ServerToClient);
+
+    app.add_protocol_channel::<Handshake>(ChannelSettings {
+        mode: ChannelMode::UnorderedReliable(ReliableSettings::default()),
+        ..default()
+    });
+
+    app.register_protocol_message::<ServerWelcome>(ChannelDirection::ServerToClient);
+
+    app.register_protocol_message::<ClientProfile>(ChannelDirection::ClientToServer);
+
+    app.register_protocol_message::<ClientLevelLoadComplete>(ChannelDirection::ClientToServer);
+
+    app.register_protocol_message::<ClientSetReady>(ChannelDirection::ClientToServer);
+
+    app.register_protocol_message::<ServerQueuePosition>(ChannelDirection::ServerToClient);
+
+    app.register_protocol_message::<ClientChat>(ChannelDirection::ClientToServer);
+
+    app.register_protocol_message::<ServerChat>(ChannelDirection::ServerToClient);
+
+    app.register_protocol_message::<ServerLevelReload>(ChannelDirection::ServerToClient);
 
-    app.register_message::<ClientLevelLoadComplete>(ChannelDirection::ClientToServer);
+    app.register_protocol_message::<ServerDisconnectNotice>(ChannelDirection::ServerToClient);
 
-    app.add_channel::<UnorderedReliable>(ChannelSettings {
+    app.add_protocol_channel::<UnorderedReliable>(ChannelSettings {
         mode: ChannelMode::UnorderedReliable(ReliableSettings::default()),
         ..default()
     });
 
-    app.add_channel::<Reliable>(ChannelSettings {
+    app.add_protocol_channel::<Reliable>(ChannelSettings {
         mode: ChannelMode::UnorderedReliable(ReliableSettings::default()),
         ..default()
     });
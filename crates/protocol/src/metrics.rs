@@ -0,0 +1,289 @@
+use std::{
+    any::type_name,
+    collections::{BTreeMap, HashMap},
+    fmt,
+    time::Duration,
+};
+
+use bevy::prelude::*;
+use lightyear::prelude::*;
+
+use crate::{
+    fingerprint::AppProtocolExt,
+    message::{
+        ClientChat, ClientLevelLoadComplete, ClientProfile, ClientSetReady, Handshake, Reliable,
+        ServerChat, ServerDisconnectNotice, ServerHello, ServerLevelReload, ServerQueuePosition,
+        ServerWelcome, UnorderedReliable,
+    },
+};
+
+/// How often each side samples its connection and reports it to the other
+pub const METRICS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Reports go unreliably, so the receiver can count the ones that never arrive
+#[derive(Channel)]
+pub struct MetricsChannel;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct MessageCount {
+    pub channel: String,
+    pub message: String,
+    pub received: u64,
+}
+
+/// One side's view of a connection, averaged over the last `METRICS_INTERVAL`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ConnectionMetrics {
+    pub bytes_in_per_sec: f32,
+    pub bytes_out_per_sec: f32,
+    pub packets_in_per_sec: f32,
+    pub packets_out_per_sec: f32,
+    pub rtt_ms: f32,
+    pub jitter_ms: f32,
+    /// Fraction of the other side's reports that never arrived
+    pub packet_loss: f32,
+    /// Messages of each type received over the connection, by channel. What one side
+    /// received is what the other sent and got through, so sends aren't counted separately.
+    pub messages: Vec<MessageCount>,
+}
+
+impl ConnectionMetrics {
+    /// Messages received on each channel, summed over the message types
+    pub fn channel_totals(&self) -> Vec<(&str, u64)> {
+        let mut totals = BTreeMap::new();
+        for count in &self.messages {
+            *totals.entry(count.channel.as_str()).or_default() += count.received;
+        }
+        totals.into_iter().collect()
+    }
+}
+
+impl fmt::Display for ConnectionMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "in {:.0} B/s ({:.0} pkt/s), out {:.0} B/s ({:.0} pkt/s), rtt {:.0} ms, jitter {:.0} ms, loss {:.1}%",
+            self.bytes_in_per_sec,
+            self.packets_in_per_sec,
+            self.bytes_out_per_sec,
+            self.packets_out_per_sec,
+            self.rtt_ms,
+            self.jitter_ms,
+            self.packet_loss * 100.0
+        )?;
+
+        for (channel, received) in self.channel_totals() {
+            write!(f, ", {} {} received", channel, received)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Sent every `METRICS_INTERVAL` on `MetricsChannel`, in both directions
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MetricsReport {
+    pub sequence: u32,
+    pub metrics: ConnectionMetrics,
+}
+
+/// Number of reports the loss estimate is taken over
+const LOSS_WINDOW: u32 = 20;
+
+/// Estimates packet loss from the gaps in received `MetricsReport` sequences
+#[derive(Default, Debug, Clone)]
+pub struct LossEstimator {
+    window_start: Option<u32>,
+    highest: u32,
+    received: u32,
+    loss: f32,
+}
+
+impl LossEstimator {
+    pub fn receive(&mut self, sequence: u32) {
+        let window_start = *self.window_start.get_or_insert(sequence);
+        if sequence < window_start {
+            return;
+        }
+
+        self.highest = self.highest.max(sequence);
+        self.received += 1;
+
+        let expected = self.highest - window_start + 1;
+        if expected >= LOSS_WINDOW {
+            self.loss = 1.0 - (self.received.min(expected) as f32 / expected as f32);
+            self.window_start = Some(self.highest + 1);
+            self.received = 0;
+        }
+    }
+
+    /// Over the last complete window
+    pub fn loss(&self) -> f32 {
+        self.loss
+    }
+}
+
+/// Cumulative byte and packet counts of a transport
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TrafficTotals {
+    pub bytes_in: usize,
+    pub bytes_out: usize,
+    pub packets_in: usize,
+    pub packets_out: usize,
+}
+
+/// Turns `TrafficTotals` sampled over time into per-second rates
+#[derive(Default, Debug, Clone)]
+pub struct TrafficRates {
+    last: Option<TrafficTotals>,
+}
+
+impl TrafficRates {
+    /// Fills in the rate fields of `metrics` from the totals since the previous sample
+    pub fn sample(
+        &mut self,
+        totals: TrafficTotals,
+        elapsed: Duration,
+        metrics: &mut ConnectionMetrics,
+    ) {
+        let last = self.last.replace(totals).unwrap_or(totals);
+        let secs = elapsed.as_secs_f32().max(f32::EPSILON);
+        let rate = |now: usize, before: usize| now.saturating_sub(before) as f32 / secs;
+
+        metrics.bytes_in_per_sec = rate(totals.bytes_in, last.bytes_in);
+        metrics.bytes_out_per_sec = rate(totals.bytes_out, last.bytes_out);
+        metrics.packets_in_per_sec = rate(totals.packets_in, last.packets_in);
+        metrics.packets_out_per_sec = rate(totals.packets_out, last.packets_out);
+    }
+}
+
+/// Messages a client received from the server since connecting, by channel and type.
+/// Counted for every message in `register_message_counts`.
+#[derive(Resource, Default, Debug, Clone)]
+pub struct MessageCounts(BTreeMap<(&'static str, &'static str), u64>);
+
+impl MessageCounts {
+    pub fn record<C: Channel, M: Message>(&mut self, count: usize) {
+        *self
+            .0
+            .entry((short_name::<C>(), short_name::<M>()))
+            .or_default() += count as u64;
+    }
+
+    pub fn to_vec(&self) -> Vec<MessageCount> {
+        self.0
+            .iter()
+            .map(|((channel, message), received)| MessageCount {
+                channel: channel.to_string(),
+                message: message.to_string(),
+                received: *received,
+            })
+            .collect()
+    }
+}
+
+/// `MessageCounts` of what the server received from each client
+#[derive(Resource, Default, Debug)]
+pub struct ClientMessageCounts(HashMap<ClientId, MessageCounts>);
+
+impl ClientMessageCounts {
+    pub fn get(&self, client_id: ClientId) -> Option<&MessageCounts> {
+        self.0.get(&client_id)
+    }
+
+    pub fn remove(&mut self, client_id: ClientId) {
+        self.0.remove(&client_id);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ClientId, &MessageCounts)> {
+        self.0
+            .iter()
+            .map(|(client_id, counts)| (*client_id, counts))
+    }
+}
+
+fn short_name<T>() -> &'static str {
+    let name = type_name::<T>();
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+fn count_received_by_client<C: Channel, M: Message>(
+    mut events: EventReader<ClientReceiveMessage<M>>,
+    mut counts: ResMut<MessageCounts>,
+) {
+    let received = events.read().count();
+    if received > 0 {
+        counts.record::<C, M>(received);
+    }
+}
+
+fn count_received_by_server<C: Channel, M: Message>(
+    mut events: EventReader<FromClients<M>>,
+    mut counts: ResMut<ClientMessageCounts>,
+) {
+    for ev in events.read() {
+        counts.0.entry(ev.from).or_default().record::<C, M>(1);
+    }
+}
+
+/// Count `M` under `C` as it arrives. Lightyear doesn't say which channel a message came
+/// in on, so this relies on `M` only ever being sent on `C`.
+fn count_received<C: Channel, M: Message>(app: &mut App, direction: ChannelDirection) {
+    if matches!(
+        direction,
+        ChannelDirection::ServerToClient | ChannelDirection::Bidirectional
+    ) {
+        app.add_systems(
+            PreUpdate,
+            count_received_by_client::<C, M>
+                .after(MainSet::Receive)
+                .run_if(resource_exists::<Events<ClientReceiveMessage<M>>>),
+        );
+    }
+    if matches!(
+        direction,
+        ChannelDirection::ClientToServer | ChannelDirection::Bidirectional
+    ) {
+        app.add_systems(
+            PreUpdate,
+            count_received_by_server::<C, M>
+                .after(MainSet::Receive)
+                .run_if(resource_exists::<Events<FromClients<M>>>),
+        );
+    }
+}
+
+/// Every message and the channel it is sent on. A message missing here isn't counted.
+fn register_message_counts(app: &mut App) {
+    app.init_resource::<MessageCounts>()
+        .init_resource::<ClientMessageCounts>();
+
+    count_received::<Handshake, ServerHello>(app, ChannelDirection::ServerToClient);
+    count_received::<UnorderedReliable, ServerWelcome>(app, ChannelDirection::ServerToClient);
+    count_received::<UnorderedReliable, ClientProfile>(app, ChannelDirection::ClientToServer);
+    count_received::<UnorderedReliable, ClientLevelLoadComplete>(
+        app,
+        ChannelDirection::ClientToServer,
+    );
+    count_received::<UnorderedReliable, ClientSetReady>(app, ChannelDirection::ClientToServer);
+    count_received::<UnorderedReliable, ServerQueuePosition>(app, ChannelDirection::ServerToClient);
+    count_received::<Reliable, ClientChat>(app, ChannelDirection::ClientToServer);
+    count_received::<Reliable, ServerChat>(app, ChannelDirection::ServerToClient);
+    count_received::<UnorderedReliable, ServerLevelReload>(app, ChannelDirection::ServerToClient);
+    count_received::<UnorderedReliable, ServerDisconnectNotice>(
+        app,
+        ChannelDirection::ServerToClient,
+    );
+    count_received::<MetricsChannel, MetricsReport>(app, ChannelDirection::Bidirectional);
+}
+
+pub fn register_metrics(app: &mut App) {
+    app.register_protocol_message::<MetricsReport>(ChannelDirection::Bidirectional);
+
+    app.add_protocol_channel::<MetricsChannel>(ChannelSettings {
+        mode: ChannelMode::SequencedUnreliable,
+        ..default()
+    });
+
+    register_message_counts(app);
+}
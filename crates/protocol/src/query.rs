@@ -0,0 +1,90 @@
+use std::{
+    error::Error,
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket},
+    time::Duration,
+};
+
+use lightyear::prelude::ClientId;
+use serde::{Deserialize, Serialize};
+
+use crate::message::Level;
+
+/// Default port servers answer status queries on
+pub const QUERY_PORT: u16 = 12027;
+
+/// Bumped whenever `ServerStatus` changes in a way outside tools would notice
+pub const QUERY_VERSION: u32 = 3;
+
+/// The whole of a status query. Anything else sent to the query port is ignored,
+/// so `echo -n status | nc -u -w1 <host> 12027` works as a client.
+pub const STATUS_QUERY: &[u8] = b"status";
+
+/// How long `query_status` waits for an answer
+const QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Sent back as JSON in a single datagram in answer to `STATUS_QUERY`. Names are capped at
+/// `MAX_PLAYER_NAME_LENGTH`, so even a full server's answer fits.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ServerStatus {
+    pub version: u32,
+    pub name: String,
+    pub current_level: Level,
+    pub tick_rate_hz: f64,
+    /// Clients playing or waiting to, by client id
+    pub players: Vec<StatusPlayer>,
+    pub spectator_count: u32,
+    pub uptime_secs: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StatusPlayer {
+    pub client_id: ClientId,
+    pub name: String,
+}
+
+/// Resolve `host`, `host:port`, an ip address or `[ipv6]:port`, defaulting to `QUERY_PORT`
+pub fn resolve_query_addr(addr: &str) -> io::Result<SocketAddr> {
+    let addr = addr.trim();
+
+    if let Ok(addr) = addr.parse::<SocketAddr>() {
+        return Ok(addr);
+    }
+
+    // A bare ipv6 address is full of colons, none of which start a port
+    let bare_ip = addr.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(ip) = bare_ip.parse::<IpAddr>() {
+        return Ok(SocketAddr::new(ip, QUERY_PORT));
+    }
+
+    let resolved = match addr.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().map_err(io::Error::other)?).to_socket_addrs(),
+        None => (addr, QUERY_PORT).to_socket_addrs(),
+    };
+
+    resolved?.next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("unable to resolve {}", addr),
+        )
+    })
+}
+
+/// Ask the server at `server_addr` for its status, blocking until it answers or times out
+pub fn query_status(server_addr: SocketAddr) -> Result<ServerStatus, Box<dyn Error>> {
+    let unspecified = match server_addr {
+        SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    };
+    let socket = UdpSocket::bind((unspecified, 0))?;
+    socket.set_read_timeout(Some(QUERY_TIMEOUT))?;
+    socket.send_to(STATUS_QUERY, server_addr)?;
+
+    let mut buffer = [0; 65_507];
+    loop {
+        let (len, from) = socket.recv_from(&mut buffer)?;
+        if from == server_addr {
+            return Ok(serde_json::from_slice(&buffer[..len])?);
+        }
+    }
+}
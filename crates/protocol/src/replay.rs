@@ -0,0 +1,84 @@
+use std::{
+    io::{self, Read},
+    time::Duration,
+};
+
+use bevy::math::{Quat, Vec2, Vec3};
+use lightyear::prelude::ClientId;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    framed::{FramedHeader, FramedWriter, read_framed},
+    message::Level,
+};
+
+/// Bumped whenever the layout of the types below changes
+pub const REPLAY_VERSION: u32 = 1;
+
+/// Written once, right after the magic
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReplayHeader {
+    pub version: u32,
+    pub level: Level,
+    /// Time between two frames
+    pub tick_duration: Duration,
+}
+
+impl FramedHeader for ReplayHeader {
+    type Frame = ReplayFrame;
+
+    const MAGIC: &'static [u8; 4] = b"LTRP";
+    const VERSION: u32 = REPLAY_VERSION;
+    const NAME: &'static str = "replay";
+
+    fn version(&self) -> u32 {
+        self.version
+    }
+}
+
+/// What the server simulated in one tick
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ReplayFrame {
+    /// Ticks since the recording started
+    pub tick: u32,
+    pub entities: Vec<EntitySnapshot>,
+    pub inputs: Vec<InputSnapshot>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapshotKind {
+    Player(ClientId),
+    Npc,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct EntitySnapshot {
+    /// The server entity's bits, stable for as long as the entity lives
+    pub id: u64,
+    pub kind: SnapshotKind,
+    pub position: Vec3,
+    pub rotation: Quat,
+}
+
+/// `NetworkedInput::Move` as the server applied it for a client
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct InputSnapshot {
+    pub client_id: ClientId,
+    pub movement: Vec2,
+}
+
+/// A whole replay file, read into memory
+#[derive(Clone, Debug)]
+pub struct Replay {
+    pub header: ReplayHeader,
+    pub frames: Vec<ReplayFrame>,
+}
+
+/// Streams frames to a replay file as they are recorded
+pub type ReplayWriter<W> = FramedWriter<ReplayHeader, W>;
+
+/// Read a replay file, tolerating a last frame cut short by the server stopping mid write
+pub fn read_replay(reader: impl Read) -> io::Result<Replay> {
+    let (header, frames) = read_framed(reader)?;
+    Ok(Replay { header, frames })
+}
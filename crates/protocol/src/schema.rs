@@ -0,0 +1,351 @@
+//! Describes the shape of a type by driving its `Deserialize` impl with a deserializer
+//! that records what it is asked for. Renaming, adding, removing or reordering a field
+//! or a variant changes the description, where the type name alone would not.
+//! Types that only know their shape once they see the data, through `deserialize_any`,
+//! such as untagged enums, can't be described and are refused.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use serde::{
+    Deserializer,
+    de::{
+        self, DeserializeOwned, DeserializeSeed, EnumAccess, Error as _, IntoDeserializer,
+        MapAccess, SeqAccess, VariantAccess, Visitor, value::Error,
+    },
+};
+
+/// Past this, the type is taken to be recursive and the description stops
+const MAX_DEPTH: usize = 32;
+
+/// Gives up on types whose enums keep revealing new variants
+const MAX_PASSES: usize = 256;
+
+/// A description of `T` as the wire format sees it, covering every variant of every enum,
+/// or why it can't be described
+pub fn describe<T: DeserializeOwned>() -> Result<String, String> {
+    let mut tracer = Tracer::default();
+    let mut description = String::new();
+
+    // Each pass takes the first variant of every enum that wasn't taken yet,
+    // until a pass finds nothing new
+    for _ in 0..MAX_PASSES {
+        tracer.found_variant = false;
+        // A type that refuses the made up values still describes itself up to that point
+        let _ = T::deserialize(&mut tracer);
+        description.push_str(&tracer.out);
+        description.push('\n');
+        tracer.out.clear();
+
+        if let Some(method) = tracer.unsupported {
+            return Err(format!(
+                "it calls {}, so its shape depends on the data",
+                method
+            ));
+        }
+        if !tracer.found_variant {
+            break;
+        }
+    }
+
+    Ok(description)
+}
+
+#[derive(Default)]
+struct Tracer {
+    out: String,
+    depth: usize,
+    /// Variants taken so far, by enum name
+    taken: BTreeMap<&'static str, BTreeSet<usize>>,
+    found_variant: bool,
+    /// Set by a method that has no fixed shape to describe
+    unsupported: Option<&'static str>,
+}
+
+impl Tracer {
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        if self.depth >= MAX_DEPTH {
+            self.out.push_str("...");
+            return Err(Error::custom("type is too deeply nested"));
+        }
+
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn refuse<T>(&mut self, method: &'static str) -> Result<T, Error> {
+        self.unsupported = Some(method);
+        Err(Error::custom(format!("{} can't be described", method)))
+    }
+
+    fn pick_variant(&mut self, name: &'static str, variants: &[&'static str]) -> usize {
+        let taken = self.taken.entry(name).or_default();
+        let variant = (0..variants.len())
+            .find(|variant| !taken.contains(variant))
+            .unwrap_or(0);
+        if taken.insert(variant) {
+            self.found_variant = true;
+        }
+        variant
+    }
+}
+
+macro_rules! trace_primitive {
+    ($($method:ident => $visit:ident($value:expr)),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                self.out.push_str(stringify!($method).trim_start_matches("deserialize_"));
+                self.out.push(' ');
+                visitor.$visit($value)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for &mut Tracer {
+    type Error = Error;
+
+    trace_primitive! {
+        deserialize_bool => visit_bool(false),
+        deserialize_i8 => visit_i8(0),
+        deserialize_i16 => visit_i16(0),
+        deserialize_i32 => visit_i32(0),
+        deserialize_i64 => visit_i64(0),
+        deserialize_i128 => visit_i128(0),
+        deserialize_u8 => visit_u8(0),
+        deserialize_u16 => visit_u16(0),
+        deserialize_u32 => visit_u32(0),
+        deserialize_u64 => visit_u64(0),
+        deserialize_u128 => visit_u128(0),
+        deserialize_f32 => visit_f32(0.0),
+        deserialize_f64 => visit_f64(0.0),
+        deserialize_char => visit_char('\0'),
+        deserialize_str => visit_str(""),
+        deserialize_string => visit_string(String::new()),
+        deserialize_bytes => visit_bytes(&[]),
+        deserialize_byte_buf => visit_byte_buf(Vec::new()),
+        deserialize_unit => visit_unit(),
+        deserialize_identifier => visit_u32(0),
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Error> {
+        self.refuse("deserialize_any")
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Error> {
+        self.refuse("deserialize_ignored_any")
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.out.push_str("option ");
+        self.nested(|tracer| visitor.visit_some(tracer))
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let _ = write!(self.out, "{} ", name);
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let _ = write!(self.out, "{}( ", name);
+        let value = self.nested(|tracer| visitor.visit_newtype_struct(&mut *tracer))?;
+        self.out.push_str(") ");
+        Ok(value)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.out.push_str("seq[ ");
+        // One element is enough to describe them all
+        let value = visitor.visit_seq(Elements::new(&mut *self, 1))?;
+        self.out.push_str("] ");
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+        let _ = write!(self.out, "tuple{}( ", len);
+        let value = visitor.visit_seq(Elements::new(&mut *self, len))?;
+        self.out.push_str(") ");
+        Ok(value)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let _ = write!(self.out, "{}( ", name);
+        let value = visitor.visit_seq(Elements::new(&mut *self, len))?;
+        self.out.push_str(") ");
+        Ok(value)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.out.push_str("map{ ");
+        let value = visitor.visit_map(Entry {
+            tracer: &mut *self,
+            remaining: 1,
+        })?;
+        self.out.push_str("} ");
+        Ok(value)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let _ = write!(self.out, "{}{{{}}}( ", name, fields.join(","));
+        // Fields in declaration order, the way compact formats send them
+        let value = visitor.visit_seq(Elements::new(&mut *self, fields.len()))?;
+        self.out.push_str(") ");
+        Ok(value)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let variant = self.pick_variant(name, variants);
+        let _ = write!(
+            self.out,
+            "{}[{}]::{} ",
+            name,
+            variants.join(","),
+            variants.get(variant).unwrap_or(&"")
+        );
+        visitor.visit_enum(Variant {
+            tracer: self,
+            variant,
+        })
+    }
+
+    fn is_human_readable(&self) -> bool {
+        // Match the wire format, which is bincode
+        false
+    }
+}
+
+struct Elements<'a> {
+    tracer: &'a mut Tracer,
+    remaining: usize,
+}
+
+impl<'a> Elements<'a> {
+    fn new(tracer: &'a mut Tracer, len: usize) -> Self {
+        Self {
+            tracer,
+            remaining: len,
+        }
+    }
+}
+
+impl<'de> SeqAccess<'de> for Elements<'_> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+
+        self.tracer
+            .nested(|tracer| seed.deserialize(tracer))
+            .map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+struct Entry<'a> {
+    tracer: &'a mut Tracer,
+    remaining: usize,
+}
+
+impl<'de> MapAccess<'de> for Entry<'_> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+
+        self.tracer
+            .nested(|tracer| seed.deserialize(tracer))
+            .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        self.tracer.out.push_str("=> ");
+        self.tracer.nested(|tracer| seed.deserialize(tracer))
+    }
+}
+
+struct Variant<'a> {
+    tracer: &'a mut Tracer,
+    variant: usize,
+}
+
+impl<'de> EnumAccess<'de> for Variant<'_> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), Error> {
+        let index: de::value::U32Deserializer<Error> = (self.variant as u32).into_deserializer();
+        Ok((seed.deserialize(index)?, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Variant<'_> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        self.tracer.out.push_str("( ");
+        let value = self.tracer.nested(|tracer| seed.deserialize(tracer))?;
+        self.tracer.out.push_str(") ");
+        Ok(value)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.tracer.deserialize_tuple(len, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let _ = write!(self.tracer.out, "{{{}}}( ", fields.join(","));
+        let value = visitor.visit_seq(Elements::new(self.tracer, fields.len()))?;
+        self.tracer.out.push_str(") ");
+        Ok(value)
+    }
+}
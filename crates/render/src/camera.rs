@@ -7,7 +7,12 @@ impl Plugin for CameraPlugin {
         app.add_systems(Startup, |mut commands: Commands| {
             commands
                 .spawn(Camera3d::default())
-                .insert(Transform::from_xyz(-50.0, 50.0, 50.0).looking_at(Vec3::ZERO, Vec3::Y));
+                .insert(default_camera_transform());
         });
     }
 }
+
+/// Where the camera starts, and goes back to when something else is done moving it
+pub fn default_camera_transform() -> Transform {
+    Transform::from_xyz(-50.0, 50.0, 50.0).looking_at(Vec3::ZERO, Vec3::Y)
+}
@@ -1,11 +1,18 @@
 use avian3d::prelude::PhysicsDebugPlugin;
-use bevy::{color::palettes::css::WHITE, prelude::*};
+use bevy::{
+    color::palettes::css::WHITE, input::common_conditions::input_toggle_active, prelude::*,
+};
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 
 pub struct RenderPlugin;
 
 mod camera;
 
+pub use camera::default_camera_transform;
+
+/// Toggles the world inspector
+pub const WORLD_INSPECTOR_KEY: KeyCode = KeyCode::F2;
+
 // If the headless server can't run it or doesn't need it
 // It goes in this plugin
 impl Plugin for RenderPlugin {
@@ -13,7 +20,7 @@ impl Plugin for RenderPlugin {
         app.add_plugins((
             camera::CameraPlugin,
             //PhysicsDebugPlugin::default(),
-            WorldInspectorPlugin::default(),
+            WorldInspectorPlugin::default().run_if(input_toggle_active(false, WORLD_INSPECTOR_KEY)),
         ));
     }
 }
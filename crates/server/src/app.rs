@@ -1,32 +1,22 @@
 use std::time::Duration;
 
-use bevy::{
-    app::{PanicHandlerPlugin, ScheduleRunnerPlugin},
-    asset::AssetPlugin,
-    diagnostic::DiagnosticsPlugin,
-    gltf::GltfPlugin,
-    input::InputPlugin,
-    log::LogPlugin,
-    pbr::PbrPlugin,
-    prelude::*,
-    render::{
-        RenderPlugin as BevyRenderPlugin,
-        camera::CameraPlugin,
-        mesh::skinning::SkinnedMeshInverseBindposes,
-        settings::{RenderCreation, WgpuSettings},
-    },
-    scene::ScenePlugin,
-    state::app::StatesPlugin,
-    window::ExitCondition,
-};
-use common::CommonPlugin;
+use assets::AssetPath;
+use bevy::{app::ScheduleRunnerPlugin, asset::AssetPlugin, log::LogPlugin, prelude::*};
+use common::{CommonPlugin, headless::HeadlessPlugin};
 use lightyear::{
     prelude::*,
     server::{config::ServerConfig, plugin::ServerPlugins},
 };
 use render::RenderPlugin;
 
-use crate::{network::NetworkPlugin, replication::ReplicationPlugin};
+use crate::{
+    capacity::CapacityPlugin, chat::ChatPlugin, conditioner::ConditionerPlugin,
+    console::ConsolePlugin, discovery::DiscoveryPlugin, game_mode::GameModePlugin,
+    hot_reload::HotReloadPlugin, interest::InterestPlugin, lobby::LobbyPlugin,
+    metrics::MetricsPlugin, metrics_endpoint::MetricsEndpointPlugin, names::NamesPlugin,
+    network::NetworkPlugin, npc::NpcPlugin, query::QueryPlugin, recorder::RecorderPlugin,
+    replication::ReplicationPlugin, spectator::SpectatorPlugin, teams::TeamsPlugin,
+};
 
 #[derive(Resource, PartialEq, Eq)]
 pub enum ServerMode {
@@ -34,56 +24,70 @@ pub enum ServerMode {
     Headless,
 }
 
-pub fn build_server_app(server_config: ServerConfig, asset_path: String, mode: ServerMode) -> App {
+/// `hot_reload` turns on bevy's file watcher for the asset folder, so that level scenes
+/// edited while the server runs are rebuilt and pushed to clients
+pub fn build_server_app(
+    server_config: ServerConfig,
+    asset_path: String,
+    mode: ServerMode,
+    hot_reload: bool,
+) -> App {
     let mut app = App::new();
 
-    let asset_plugin = AssetPlugin {
-        file_path: asset_path.clone(),
-        ..default()
-    };
-
     match mode {
         ServerMode::Windowed => {
-            app.add_plugins((DefaultPlugins.build().set(asset_plugin), RenderPlugin));
+            app.add_plugins((
+                DefaultPlugins.build().set(AssetPlugin {
+                    file_path: asset_path.clone(),
+                    watch_for_changes_override: Some(hot_reload),
+                    ..default()
+                }),
+                RenderPlugin,
+            ));
         }
         _ => {
             app.add_plugins((
                 MinimalPlugins.build().set(ScheduleRunnerPlugin::run_loop(
                     Duration::from_secs_f64(1.0 / 100.0),
                 )),
-                asset_plugin,
-                WindowPlugin {
-                    primary_window: None,
-                    exit_condition: ExitCondition::DontExit,
-                    ..default()
+                HeadlessPlugin {
+                    asset_path: asset_path.clone(),
+                    hot_reload,
                 },
-                BevyRenderPlugin {
-                    render_creation: RenderCreation::Automatic(WgpuSettings {
-                        backends: None,
-                        ..default()
-                    }),
-                    ..default()
-                },
-                PanicHandlerPlugin,
-                TransformPlugin,
-                HierarchyPlugin,
-                DiagnosticsPlugin,
-                StatesPlugin,
-                ScenePlugin,
-                GltfPlugin::default(),
-                PbrPlugin::default(),
             ));
 
             app.add_plugins(LogPlugin::default());
-
-            app.init_asset::<Image>(); // or add ImagePlugin
         }
     };
 
     app.add_plugins(ServerPlugins {
         config: server_config,
     })
-    .add_plugins((CommonPlugin, NetworkPlugin, ReplicationPlugin))
+    .add_plugins((
+        CommonPlugin,
+        NetworkPlugin,
+        ReplicationPlugin,
+        ConsolePlugin,
+        HotReloadPlugin,
+        ConditionerPlugin,
+        DiscoveryPlugin,
+        QueryPlugin,
+        MetricsPlugin,
+        MetricsEndpointPlugin,
+        RecorderPlugin,
+    ))
+    .add_plugins((
+        LobbyPlugin,
+        SpectatorPlugin,
+        CapacityPlugin,
+        ChatPlugin,
+        NamesPlugin,
+        GameModePlugin,
+        TeamsPlugin,
+        InterestPlugin,
+        NpcPlugin,
+    ))
+    .insert_resource(AssetPath(asset_path))
     .insert_resource(mode);
 
     app
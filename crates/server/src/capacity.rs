@@ -0,0 +1,249 @@
+use std::collections::{HashSet, VecDeque};
+
+use bevy::prelude::*;
+use lightyear::prelude::{
+    ClientId, FromClients, NetworkTarget, ServerConnectionManager, ServerDisconnectEvent,
+};
+use protocol::message::{ClientProfile, DisconnectReason, ServerQueuePosition, UnorderedReliable};
+
+use crate::network::DisconnectWithReasonExt;
+
+/// Limits how many players and spectators can be in at once. Clients are given a slot,
+/// a place in the queue or turned away on the profile they send as they connect, before
+/// the server welcomes them and they load anything. Queued players are told where they
+/// are in the queue as it moves.
+pub struct CapacityPlugin;
+
+impl Plugin for CapacityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CapacityConfig>()
+            .init_resource::<ConnectionQueue>()
+            .init_resource::<Slots>()
+            .add_observer(leave_on_disconnect)
+            .add_systems(
+                Update,
+                (
+                    admit_connecting_clients,
+                    promote_queued_players,
+                    send_queue_positions.run_if(resource_changed::<ConnectionQueue>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Insert before startup to limit how many clients can join. Unlimited by default.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct CapacityConfig {
+    pub max_players: Option<usize>,
+    pub max_spectators: Option<usize>,
+    /// Player slots, out of `max_players`, that only admins can take
+    pub reserved_slots: usize,
+    /// Recognised by client id alone. With manual authentication every client picks its
+    /// own id, so these only keep slots from honest clients, not from anyone who knows an
+    /// admin's id. Hand out connect tokens from a backend to make them mean anything.
+    pub admins: Vec<ClientId>,
+    /// Players that can wait for a slot. Any more are turned away.
+    pub max_queue: usize,
+}
+
+impl CapacityConfig {
+    /// Whether `client_id` is in `admins`, see there for why that proves nothing
+    pub fn is_admin(&self, client_id: ClientId) -> bool {
+        self.admins.contains(&client_id)
+    }
+
+    /// Whether `client_id` can join with `players` already in
+    pub fn has_player_slot(&self, client_id: ClientId, players: usize) -> bool {
+        let Some(max_players) = self.max_players else {
+            return true;
+        };
+
+        let slots = if self.is_admin(client_id) {
+            max_players
+        } else {
+            max_players.saturating_sub(self.reserved_slots)
+        };
+        players < slots
+    }
+
+    pub fn has_spectator_slot(&self, spectators: usize) -> bool {
+        self.max_spectators.is_none_or(|max| spectators < max)
+    }
+}
+
+/// Clients holding a player or spectator slot, from the moment they are admitted
+/// until they disconnect, whether or not they have loaded the level yet
+#[derive(Resource, Default, Debug)]
+pub struct Slots {
+    players: HashSet<ClientId>,
+    spectators: HashSet<ClientId>,
+}
+
+impl Slots {
+    pub fn holds_player_slot(&self, client_id: ClientId) -> bool {
+        self.players.contains(&client_id)
+    }
+
+    pub fn holds_spectator_slot(&self, client_id: ClientId) -> bool {
+        self.spectators.contains(&client_id)
+    }
+
+    fn contains(&self, client_id: ClientId) -> bool {
+        self.holds_player_slot(client_id) || self.holds_spectator_slot(client_id)
+    }
+}
+
+/// Sent to a client once it has a slot or a place in the queue, so it can be welcomed
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ClientAdmitted {
+    pub client_id: ClientId,
+}
+
+/// Players that found no free slot when they connected, first in line at the front
+#[derive(Resource, Default, Debug)]
+pub struct ConnectionQueue(VecDeque<ClientId>);
+
+impl ConnectionQueue {
+    /// Where `client_id` is in the queue, 1 being next
+    pub fn position(&self, client_id: ClientId) -> Option<usize> {
+        self.0
+            .iter()
+            .position(|queued| *queued == client_id)
+            .map(|index| index + 1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn front(&self) -> Option<ClientId> {
+        self.0.front().copied()
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<ClientId> {
+        self.0.pop_front()
+    }
+
+    /// Admins skip ahead of everyone that isn't one
+    pub(crate) fn enqueue(&mut self, client_id: ClientId, config: &CapacityConfig) {
+        if self.0.contains(&client_id) {
+            return;
+        }
+
+        if config.is_admin(client_id) {
+            let index = self
+                .0
+                .iter()
+                .position(|queued| !config.is_admin(*queued))
+                .unwrap_or(self.0.len());
+            self.0.insert(index, client_id);
+        } else {
+            self.0.push_back(client_id);
+        }
+    }
+}
+
+fn leave_on_disconnect(
+    trigger: Trigger<ServerDisconnectEvent>,
+    mut queue: ResMut<ConnectionQueue>,
+    mut slots: ResMut<Slots>,
+) {
+    let client_id = trigger.event().client_id;
+    if queue.0.contains(&client_id) {
+        queue.0.retain(|queued| *queued != client_id);
+    }
+    slots.players.remove(&client_id);
+    slots.spectators.remove(&client_id);
+}
+
+/// Give each newly connected client a slot or a place in the queue, or turn it away
+fn admit_connecting_clients(
+    mut commands: Commands,
+    mut ev_client_profile: EventReader<FromClients<ClientProfile>>,
+    capacity: Res<CapacityConfig>,
+    mut queue: ResMut<ConnectionQueue>,
+    mut slots: ResMut<Slots>,
+) {
+    for ev in ev_client_profile.read() {
+        let client_id = ev.from;
+        if slots.contains(client_id) || queue.position(client_id).is_some() {
+            continue;
+        }
+
+        if ev.message.spectator {
+            if !capacity.has_spectator_slot(slots.spectators.len()) {
+                commands.disconnect_with_reason(
+                    client_id,
+                    DisconnectReason::ServerFull(String::from("no spectator slots are free")),
+                );
+                continue;
+            }
+            slots.spectators.insert(client_id);
+        } else {
+            let jumps_queue = queue.is_empty() || capacity.is_admin(client_id);
+            if capacity.has_player_slot(client_id, slots.players.len()) && jumps_queue {
+                slots.players.insert(client_id);
+            } else if queue.len() >= capacity.max_queue {
+                commands.disconnect_with_reason(
+                    client_id,
+                    DisconnectReason::ServerFull(format!(
+                        "all {} player slots are taken",
+                        capacity.max_players.unwrap_or_default()
+                    )),
+                );
+                continue;
+            } else {
+                queue.enqueue(client_id, &capacity);
+                info!(
+                    "server is full, client {} is {:?} in the queue",
+                    client_id,
+                    queue.position(client_id)
+                );
+            }
+        }
+
+        commands.trigger(ClientAdmitted { client_id });
+    }
+}
+
+/// Hand out freed player slots to the queue, in order
+fn promote_queued_players(
+    capacity: Res<CapacityConfig>,
+    mut queue: ResMut<ConnectionQueue>,
+    mut slots: ResMut<Slots>,
+) {
+    while let Some(client_id) = queue.front() {
+        if !capacity.has_player_slot(client_id, slots.players.len()) {
+            return;
+        }
+
+        queue.pop();
+        info!("a slot freed up for queued client {}", client_id);
+        slots.players.insert(client_id);
+    }
+}
+
+/// Everyone still waiting hears their new place whenever the queue moves
+fn send_queue_positions(queue: Res<ConnectionQueue>, mut server: ResMut<ServerConnectionManager>) {
+    let queue_length = queue.len() as u32;
+
+    for (index, client_id) in queue.0.iter().enumerate() {
+        if let Err(e) = server.send_message_to_target::<UnorderedReliable, ServerQueuePosition>(
+            &ServerQueuePosition {
+                position: index as u32 + 1,
+                queue_length,
+            },
+            NetworkTarget::Single(*client_id),
+        ) {
+            error!(
+                "unable to send queue position to client id {}, had error {}",
+                client_id, e
+            );
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::Duration,
+};
+
+use bevy::prelude::*;
+use lightyear::prelude::{
+    ClientId, FromClients, NetworkTarget, ServerConnectEvent, ServerConnectionManager,
+    ServerDisconnectEvent,
+};
+use protocol::message::{ChatChannel, ClientChat, MAX_CHAT_LENGTH, Reliable, ServerChat};
+
+use crate::{
+    console::{AddAdminCommandExt, AdminCommand},
+    teams::{TeamAssignments, team_target},
+};
+
+/// Passes chat between clients, after checking it against the rate and length limits,
+/// the mute list and the blocked words managed from the admin console
+pub struct ChatPlugin;
+
+impl Plugin for ChatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChatConfig>()
+            .init_resource::<ChatModeration>()
+            .init_resource::<RecentMessages>()
+            .add_admin_command("say", "say <message>: send a system message to everyone")
+            .add_admin_command(
+                "mute",
+                "mute [client id]: list muted clients, or stop one from chatting",
+            )
+            .add_admin_command(
+                "unmute",
+                "unmute <client id>: let a muted client chat again",
+            )
+            .add_admin_command(
+                "blockword",
+                "blockword [word]: list blocked words, or star out a word in chat",
+            )
+            .add_admin_command(
+                "unblockword",
+                "unblockword <word>: stop starring out a word",
+            )
+            .add_observer(track_connected_sender)
+            .add_observer(forget_disconnected_sender)
+            .add_systems(Update, (handle_chat_commands, relay_chat).chain());
+    }
+}
+
+/// Insert before startup to change the chat limits
+#[derive(Resource, Clone, Debug)]
+pub struct ChatConfig {
+    /// Longer messages are refused. Capped at `MAX_CHAT_LENGTH`.
+    pub max_length: usize,
+    /// Messages a client can send within `rate_window`
+    pub max_messages: usize,
+    pub rate_window: Duration,
+}
+
+impl Default for ChatConfig {
+    fn default() -> Self {
+        Self {
+            max_length: MAX_CHAT_LENGTH,
+            max_messages: 5,
+            rate_window: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Clients that can't chat, and words starred out of everything that is sent
+#[derive(Resource, Default, Debug)]
+pub struct ChatModeration {
+    pub muted: HashSet<ClientId>,
+    /// Lowercase
+    pub blocked_words: HashSet<String>,
+}
+
+impl ChatModeration {
+    /// Star out every word that is blocked, ignoring case. Words are runs of alphanumeric
+    /// characters, so punctuation and any whitespace separate them, and everything between
+    /// words is kept as it was.
+    pub fn filter(&self, text: &str) -> String {
+        let mut filtered = String::with_capacity(text.len());
+        let mut rest = text;
+        while !rest.is_empty() {
+            let start = rest
+                .find(|c: char| c.is_alphanumeric())
+                .unwrap_or(rest.len());
+            filtered.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let end = rest
+                .find(|c: char| !c.is_alphanumeric())
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            if self.blocked_words.contains(&word.to_lowercase()) {
+                filtered.extend(word.chars().map(|_| '*'));
+            } else {
+                filtered.push_str(word);
+            }
+            rest = &rest[end..];
+        }
+        filtered
+    }
+}
+
+/// When each connected client last sent a message, within the rate window
+#[derive(Resource, Default)]
+struct RecentMessages(HashMap<ClientId, VecDeque<Duration>>);
+
+fn track_connected_sender(
+    trigger: Trigger<ServerConnectEvent>,
+    mut recent: ResMut<RecentMessages>,
+) {
+    recent.0.insert(trigger.event().client_id, VecDeque::new());
+}
+
+fn forget_disconnected_sender(
+    trigger: Trigger<ServerDisconnectEvent>,
+    mut recent: ResMut<RecentMessages>,
+) {
+    recent.0.remove(&trigger.event().client_id);
+}
+
+fn send_chat(server: &mut ServerConnectionManager, message: &ServerChat, target: NetworkTarget) {
+    if let Err(e) = server.send_message_to_target::<Reliable, ServerChat>(message, target) {
+        error!("unable to send chat message, had error {}", e);
+    }
+}
+
+/// Tell one client, and only that client, something from the server
+fn send_system_reply(
+    server: &mut ServerConnectionManager,
+    client_id: ClientId,
+    text: impl Into<String>,
+) {
+    send_chat(
+        server,
+        &ServerChat {
+            channel: ChatChannel::System,
+            from: None,
+            text: text.into(),
+        },
+        NetworkTarget::Single(client_id),
+    );
+}
+
+fn parse_client_id(args: &[String]) -> Option<ClientId> {
+    let [client_id] = args else {
+        return None;
+    };
+    client_id.parse().ok().map(ClientId::Netcode)
+}
+
+fn handle_chat_commands(
+    mut commands: EventReader<AdminCommand>,
+    mut moderation: ResMut<ChatModeration>,
+    mut server: ResMut<ServerConnectionManager>,
+) {
+    for command in commands.read() {
+        match command.name.as_str() {
+            "say" if !command.args.is_empty() => send_chat(
+                &mut server,
+                &ServerChat {
+                    channel: ChatChannel::System,
+                    from: None,
+                    text: command.args.join(" "),
+                },
+                NetworkTarget::All,
+            ),
+            "say" => warn!("expected a message"),
+            "mute" if command.args.is_empty() => {
+                info!("muted clients: {:?}", moderation.muted);
+            }
+            "mute" => match parse_client_id(&command.args) {
+                Some(client_id) => {
+                    info!("muted client {}", client_id);
+                    moderation.muted.insert(client_id);
+                    send_system_reply(&mut server, client_id, "You have been muted");
+                }
+                None => warn!("expected a client id"),
+            },
+            "unmute" => match parse_client_id(&command.args) {
+                Some(client_id) if moderation.muted.remove(&client_id) => {
+                    info!("unmuted client {}", client_id);
+                    send_system_reply(&mut server, client_id, "You can chat again");
+                }
+                Some(client_id) => warn!("client {} isn't muted", client_id),
+                None => warn!("expected a client id"),
+            },
+            "blockword" if command.args.is_empty() => {
+                info!("blocked words: {:?}", moderation.blocked_words);
+            }
+            "blockword" | "unblockword" => {
+                let [word] = command.args.as_slice() else {
+                    warn!("expected a single word");
+                    continue;
+                };
+                let word = word.to_lowercase();
+
+                if command.name == "blockword" {
+                    // The filter splits words on anything else, so these would never match
+                    if !word.chars().all(char::is_alphanumeric) {
+                        warn!("only letters and digits can be blocked");
+                        continue;
+                    }
+                    moderation.blocked_words.insert(word);
+                } else if !moderation.blocked_words.remove(&word) {
+                    warn!("{} isn't blocked", word);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn relay_chat(
+    time: Res<Time>,
+    mut ev_client_chat: ResMut<Events<FromClients<ClientChat>>>,
+    config: Res<ChatConfig>,
+    moderation: Res<ChatModeration>,
+    assignments: Res<TeamAssignments>,
+    mut recent: ResMut<RecentMessages>,
+    mut server: ResMut<ServerConnectionManager>,
+) {
+    let now = time.elapsed();
+
+    for ev in ev_client_chat.drain() {
+        let from = ev.from;
+        let text = ev.message.text.trim();
+
+        if text.is_empty() {
+            continue;
+        }
+
+        if moderation.muted.contains(&from) {
+            send_system_reply(&mut server, from, "You are muted");
+            continue;
+        }
+
+        let max_length = config.max_length.min(MAX_CHAT_LENGTH);
+        if text.chars().count() > max_length {
+            send_system_reply(
+                &mut server,
+                from,
+                format!("Messages can be at most {} characters", max_length),
+            );
+            continue;
+        }
+
+        let Some(sent) = recent.0.get_mut(&from) else {
+            continue;
+        };
+        while sent
+            .front()
+            .is_some_and(|sent_at| now.saturating_sub(*sent_at) >= config.rate_window)
+        {
+            sent.pop_front();
+        }
+        if sent.len() >= config.max_messages {
+            send_system_reply(&mut server, from, "You are sending messages too quickly");
+            continue;
+        }
+
+        let target = match ev.message.channel {
+            ChatChannel::All => NetworkTarget::All,
+            ChatChannel::Team => match assignments.team_of(from) {
+                Some(team) => team_target(&assignments, team),
+                None => {
+                    send_system_reply(&mut server, from, "You are not on a team");
+                    continue;
+                }
+            },
+            ChatChannel::Whisper(to) if to == from => {
+                send_system_reply(&mut server, from, "You can't whisper to yourself");
+                continue;
+            }
+            ChatChannel::Whisper(to) if recent.0.contains_key(&to) => {
+                NetworkTarget::Only(vec![from, to])
+            }
+            ChatChannel::Whisper(to) => {
+                send_system_reply(&mut server, from, format!("Player {} isn't connected", to));
+                continue;
+            }
+            ChatChannel::System => {
+                warn!("client {} tried to send a system message", from);
+                continue;
+            }
+        };
+
+        recent.0.entry(from).or_default().push_back(now);
+        send_chat(
+            &mut server,
+            &ServerChat {
+                channel: ev.message.channel,
+                from: Some(from),
+                text: moderation.filter(text),
+            },
+            target,
+        );
+    }
+}
@@ -0,0 +1,258 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+    time::Duration,
+};
+
+use bevy::prelude::*;
+use common::{
+    conditioner::{ConditionerPreset, NetworkConditions},
+    relay::{ConditionedEnd, ConditionedRelay},
+};
+use lightyear::{
+    prelude::server::{NetConfig, NetworkingState, ServerCommandsExt, ServerTransport},
+    server::config::ServerConfig,
+};
+
+use crate::console::{AddAdminCommandExt, AdminCommand};
+
+/// Conditions the server's connections through a `ConditionedRelay` bound where the UDP
+/// transport was, with the transport moved to a loopback port behind it. Changes from the
+/// admin console or option reloads apply to the relay straight away. The relay is only
+/// put in when the server starts with a conditioner, since every client then seems to come
+/// from loopback. A server started without one needs `conditioner restart` to put it in,
+/// which drops every connected client.
+pub struct ConditionerPlugin;
+
+impl Plugin for ConditionerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SetLinkConditioner>()
+            .init_resource::<ServerConditions>()
+            .init_resource::<PendingRestart>()
+            .add_admin_command(
+                "conditioner",
+                "conditioner [off|lan|broadband|mobile|awful|<latency ms> <jitter ms> <loss> [duplication]|restart]: show or change the link conditioner, restart puts it in on a server started without one and drops every client",
+            )
+            .add_systems(PreStartup, put_in_relay)
+            .add_systems(
+                Update,
+                (handle_conditioner_command, apply_link_conditioner).chain(),
+            )
+            .add_systems(OnEnter(NetworkingState::Stopped), restart_server);
+    }
+}
+
+/// Condition the server's connections with these, or turn conditioning off with `None`
+#[derive(Event, Debug, Clone)]
+pub struct SetLinkConditioner(pub Option<NetworkConditions>);
+
+/// Insert before startup to condition the server's connections from the start
+#[derive(Resource, Clone, Debug, Default)]
+pub struct ServerConditions(pub Option<NetworkConditions>);
+
+/// In front of the UDP transport, when the server is conditioned
+#[derive(Resource, Deref)]
+pub struct ServerRelay(ConditionedRelay);
+
+/// Set when the server was stopped only so it can be started again behind a relay
+#[derive(Resource, Default)]
+struct PendingRestart(bool);
+
+fn parse_conditions(args: &[String]) -> Result<Option<NetworkConditions>, String> {
+    let millis = |value: &String| {
+        value
+            .parse::<u64>()
+            .map(Duration::from_millis)
+            .map_err(|e| format!("{}: {}", value, e))
+    };
+    let fraction = |value: &String| {
+        value
+            .parse::<f32>()
+            .map_err(|e| format!("{}: {}", value, e))
+    };
+
+    match args {
+        [preset] => preset
+            .parse::<ConditionerPreset>()
+            .map(ConditionerPreset::conditions),
+        [latency_ms, jitter_ms, loss] => Ok(Some(NetworkConditions::symmetric(
+            millis(latency_ms)?,
+            millis(jitter_ms)?,
+            fraction(loss)?,
+            0.0,
+        ))),
+        [latency_ms, jitter_ms, loss, duplication] => Ok(Some(NetworkConditions::symmetric(
+            millis(latency_ms)?,
+            millis(jitter_ms)?,
+            fraction(loss)?,
+            fraction(duplication)?,
+        ))),
+        _ => Err(String::from(
+            "expected a preset, or latency, jitter, loss and optionally duplication",
+        )),
+    }
+}
+
+fn describe(conditions: Option<NetworkConditions>) -> String {
+    match conditions {
+        Some(conditions) => conditions.to_string(),
+        None => String::from("off"),
+    }
+}
+
+/// A loopback port nothing is bound to, for the transport to move to
+fn free_loopback_addr() -> std::io::Result<SocketAddr> {
+    UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).and_then(|socket| socket.local_addr())
+}
+
+/// Bind a relay where the first UDP transport is, and move the transport behind it
+fn relay_transport(
+    server_config: &mut ServerConfig,
+    conditions: Option<NetworkConditions>,
+) -> Option<ServerRelay> {
+    for net_config in server_config.net.iter_mut() {
+        #[allow(irrefutable_let_patterns)]
+        let NetConfig::Netcode { io, .. } = net_config else {
+            continue;
+        };
+        let ServerTransport::UdpSocket(public_addr) = io.transport else {
+            continue;
+        };
+
+        let relay = free_loopback_addr().and_then(|internal_addr| {
+            ConditionedRelay::spawn(
+                public_addr,
+                internal_addr,
+                SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
+                ConditionedEnd::Target,
+                conditions,
+            )
+            .map(|relay| (relay, internal_addr))
+        });
+
+        return match relay {
+            Ok((relay, internal_addr)) => {
+                info!(
+                    "conditioning connections on {} through {}",
+                    relay.listen_addr(),
+                    internal_addr
+                );
+                io.transport = ServerTransport::UdpSocket(internal_addr);
+                io.conditioner = None;
+                Some(ServerRelay(relay))
+            }
+            Err(e) => {
+                warn!(
+                    "unable to put a link conditioner on {}, running without one: {}",
+                    public_addr, e
+                );
+                None
+            }
+        };
+    }
+
+    None
+}
+
+fn put_in_relay(
+    mut commands: Commands,
+    mut server_config: ResMut<ServerConfig>,
+    conditions: Res<ServerConditions>,
+) {
+    if conditions.0.is_none() {
+        return;
+    }
+
+    if let Some(relay) = relay_transport(&mut server_config, conditions.0) {
+        commands.insert_resource(relay);
+    }
+}
+
+fn handle_conditioner_command(
+    mut commands: Commands,
+    mut admin_commands: EventReader<AdminCommand>,
+    mut set_conditioner: EventWriter<SetLinkConditioner>,
+    conditions: Res<ServerConditions>,
+    relay: Option<Res<ServerRelay>>,
+    mut pending_restart: ResMut<PendingRestart>,
+) {
+    for command in admin_commands
+        .read()
+        .filter(|command| command.name == "conditioner")
+    {
+        if command.args.is_empty() {
+            match &relay {
+                Some(relay) => info!("link conditioner: {}", describe(relay.conditions())),
+                None if conditions.0.is_some() => info!(
+                    "link conditioner: off, set to {} once `conditioner restart` puts it in",
+                    describe(conditions.0)
+                ),
+                None => info!("link conditioner: off"),
+            }
+            continue;
+        }
+
+        if command.args == ["restart"] {
+            if relay.is_some() {
+                info!("the link conditioner is already in, and changes apply as they are made");
+            } else if conditions.0.is_none() {
+                info!("set the link conditioner before putting it in");
+            } else {
+                info!("restarting networking to put the link conditioner in");
+                pending_restart.0 = true;
+                commands.stop_server();
+            }
+            continue;
+        }
+
+        match parse_conditions(&command.args) {
+            Ok(conditions) => {
+                set_conditioner.send(SetLinkConditioner(conditions));
+            }
+            Err(e) => warn!("{}", e),
+        }
+    }
+}
+
+/// Applies to the relay at once, if there is one
+fn apply_link_conditioner(
+    mut set_conditioner: EventReader<SetLinkConditioner>,
+    mut conditions: ResMut<ServerConditions>,
+    relay: Option<Res<ServerRelay>>,
+) {
+    let Some(SetLinkConditioner(new_conditions)) = set_conditioner.read().last() else {
+        return;
+    };
+    if conditions.0 == *new_conditions {
+        return;
+    }
+    conditions.0 = *new_conditions;
+
+    match &relay {
+        Some(relay) => {
+            relay.set_conditions(*new_conditions);
+            info!("link conditioner set to {}", describe(*new_conditions));
+        }
+        None if new_conditions.is_some() => info!(
+            "link conditioner set to {}. This server started without one, `conditioner restart` puts it in, dropping every connected client",
+            describe(*new_conditions)
+        ),
+        None => {}
+    }
+}
+
+fn restart_server(
+    mut commands: Commands,
+    mut pending_restart: ResMut<PendingRestart>,
+    mut server_config: ResMut<ServerConfig>,
+    conditions: Res<ServerConditions>,
+) {
+    if !pending_restart.0 {
+        return;
+    }
+    pending_restart.0 = false;
+
+    if let Some(relay) = relay_transport(&mut server_config, conditions.0) {
+        commands.insert_resource(relay);
+    }
+    commands.start_server();
+}
@@ -0,0 +1,119 @@
+use std::{
+    io::BufRead,
+    sync::{
+        Mutex,
+        mpsc::{self, Receiver, TryRecvError},
+    },
+    thread,
+};
+
+use bevy::prelude::*;
+
+/// Admin commands, typed one per line like `conditioner mobile`. Modules register the
+/// commands they handle with `add_admin_command` and read `AdminCommand` events.
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<AdminCommand>()
+            .init_resource::<AdminCommands>()
+            .add_admin_command("help", "help: list the available commands")
+            .add_systems(Update, handle_help_and_unknown_commands);
+    }
+}
+
+/// Reads admin commands from the server's stdin
+pub struct StdinConsolePlugin;
+
+impl Plugin for StdinConsolePlugin {
+    fn build(&self, app: &mut App) {
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            for line in std::io::stdin().lock().lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        app.insert_resource(StdinLines(Mutex::new(receiver)))
+            .add_systems(PreUpdate, read_stdin_commands);
+    }
+}
+
+#[derive(Event, Clone, Debug, PartialEq)]
+pub struct AdminCommand {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+impl AdminCommand {
+    /// Splits a line on whitespace, `None` if it is blank
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut words = line.split_whitespace();
+        let name = words.next()?.to_lowercase();
+
+        Some(Self {
+            name,
+            args: words.map(String::from).collect(),
+        })
+    }
+}
+
+/// Name and usage of every registered command
+#[derive(Resource, Default)]
+pub struct AdminCommands(Vec<(&'static str, &'static str)>);
+
+pub trait AddAdminCommandExt {
+    /// Register a command so it shows up in `help` and isn't reported as unknown
+    fn add_admin_command(&mut self, name: &'static str, usage: &'static str) -> &mut Self;
+}
+
+impl AddAdminCommandExt for App {
+    fn add_admin_command(&mut self, name: &'static str, usage: &'static str) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<AdminCommands>()
+            .0
+            .push((name, usage));
+        self
+    }
+}
+
+#[derive(Resource)]
+struct StdinLines(Mutex<Receiver<String>>);
+
+fn read_stdin_commands(lines: Res<StdinLines>, mut commands: EventWriter<AdminCommand>) {
+    let Ok(receiver) = lines.0.lock() else {
+        return;
+    };
+
+    loop {
+        match receiver.try_recv() {
+            Ok(line) => {
+                if let Some(command) = AdminCommand::parse(&line) {
+                    commands.send(command);
+                }
+            }
+            Err(TryRecvError::Empty | TryRecvError::Disconnected) => return,
+        }
+    }
+}
+
+fn handle_help_and_unknown_commands(
+    mut commands: EventReader<AdminCommand>,
+    registered: Res<AdminCommands>,
+) {
+    for command in commands.read() {
+        if command.name == "help" {
+            for (_, usage) in &registered.0 {
+                info!("{}", usage);
+            }
+        } else if !registered.0.iter().any(|(name, _)| *name == command.name) {
+            warn!("unknown command {}, try help", command.name);
+        }
+    }
+}
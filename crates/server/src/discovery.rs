@@ -0,0 +1,193 @@
+use std::{
+    io::ErrorKind,
+    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+};
+
+use assets::CurrentLevel;
+use bevy::prelude::*;
+use lightyear::{
+    prelude::server::{NetConfig, ServerTransport},
+    server::config::ServerConfig,
+};
+use protocol::{
+    component::Player,
+    discovery::{
+        DISCOVERY_PORT, DiscoveryQuery, DiscoveryResponse, decode_discovery_packet,
+        encode_discovery_packet,
+    },
+    fingerprint::ProtocolFingerprint,
+};
+use socket2::{Domain, Protocol, Socket, Type};
+
+use crate::conditioner::ServerRelay;
+
+/// Answers LAN discovery queries with what the server browser needs to list this server
+pub struct DiscoveryPlugin;
+
+impl Plugin for DiscoveryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DiscoveryConfig>()
+            .add_systems(Startup, bind_discovery_socket)
+            .add_systems(
+                Update,
+                answer_discovery_queries.run_if(resource_exists::<DiscoverySocket>),
+            );
+    }
+}
+
+/// Insert before startup to change how the server advertises itself
+#[derive(Resource, Clone, Debug)]
+pub struct DiscoveryConfig {
+    pub name: String,
+    /// Port 0 binds any free port, which is only useful when clients are told about it directly
+    pub port: u16,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            name: String::from("Server"),
+            port: DISCOVERY_PORT,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct DiscoverySocket(UdpSocket);
+
+impl DiscoverySocket {
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.0.local_addr().ok()
+    }
+}
+
+/// Several servers on one machine share the well known port, which is fine since
+/// queries are broadcast and every one of them should answer.
+fn open_discovery_socket(port: u16) -> std::io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    #[cfg(unix)]
+    socket.set_reuse_port(true)?;
+    socket.set_broadcast(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)).into())?;
+
+    Ok(socket.into())
+}
+
+fn bind_discovery_socket(
+    mut commands: Commands,
+    config: Res<DiscoveryConfig>,
+    server_config: Res<ServerConfig>,
+    relay: Option<Res<ServerRelay>>,
+) {
+    if game_addr(&server_config, relay.as_deref()).is_some_and(|addr| addr.ip().is_loopback()) {
+        warn!(
+            "the game port is bound to loopback, so only clients on this machine are answered. \
+             Set listen_addr to 0.0.0.0 or a LAN address to be discoverable on the LAN."
+        );
+    }
+
+    match open_discovery_socket(config.port) {
+        Ok(socket) => {
+            info!("answering discovery queries on {:?}", socket.local_addr());
+            commands.insert_resource(DiscoverySocket(socket));
+        }
+        Err(e) => warn!(
+            "unable to bind discovery port {}, server won't be discoverable: {}",
+            config.port, e
+        ),
+    }
+}
+
+/// Where the first UDP transport is bound, which is the one LAN clients can reach, or the
+/// link conditioner's relay in front of it
+fn game_addr(server_config: &ServerConfig, relay: Option<&ServerRelay>) -> Option<SocketAddr> {
+    if let Some(relay) = relay {
+        return Some(relay.listen_addr());
+    }
+
+    server_config.net.iter().find_map(|net_config| {
+        #[allow(irrefutable_let_patterns)]
+        if let NetConfig::Netcode { io, .. } = net_config {
+            if let ServerTransport::UdpSocket(addr) = io.transport {
+                return Some(addr);
+            }
+        }
+
+        None
+    })
+}
+
+/// Whether `ip` is one of this machine's addresses, which are the only ones that can be bound
+fn is_local(ip: IpAddr) -> bool {
+    ip.is_loopback() || UdpSocket::bind((ip, 0)).is_ok()
+}
+
+/// Where a client that queried from `from` should connect to reach a game socket bound to
+/// `game_ip`. `Some(None)` for the ip the response comes from, `None` if it can't reach it.
+/// Clients on this machine are sent to loopback, since their broadcasts arrive from one of
+/// our LAN addresses and a game socket bound to loopback doesn't listen there.
+fn reachable_game_ip(game_ip: IpAddr, from: IpAddr) -> Option<Option<IpAddr>> {
+    match (is_local(from), game_ip.is_unspecified()) {
+        (true, true) => Some(Some(IpAddr::V4(Ipv4Addr::LOCALHOST))),
+        (true, false) => Some(Some(game_ip)),
+        (false, true) => Some(None),
+        (false, false) if game_ip.is_loopback() => None,
+        (false, false) => Some(Some(game_ip)),
+    }
+}
+
+fn answer_discovery_queries(
+    socket: Res<DiscoverySocket>,
+    config: Res<DiscoveryConfig>,
+    server_config: Res<ServerConfig>,
+    relay: Option<Res<ServerRelay>>,
+    current_level: Res<CurrentLevel>,
+    protocol_fingerprint: Res<ProtocolFingerprint>,
+    q_players: Query<(), With<Player>>,
+) {
+    let mut buffer = [0; 512];
+
+    loop {
+        let (len, from) = match socket.0.recv_from(&mut buffer) {
+            Ok(received) => received,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => return,
+            Err(e) => {
+                warn!("discovery socket error: {}", e);
+                return;
+            }
+        };
+
+        if decode_discovery_packet::<DiscoveryQuery>(&buffer[..len]).is_none() {
+            continue;
+        }
+
+        let Some(game_addr) = game_addr(&server_config, relay.as_deref()) else {
+            // Nothing a LAN client could connect to
+            continue;
+        };
+
+        let Some(game_ip) = reachable_game_ip(game_addr.ip(), from.ip()) else {
+            continue;
+        };
+
+        let response = DiscoveryResponse {
+            name: config.name.clone(),
+            current_level: current_level.0,
+            player_count: q_players.iter().count() as u32,
+            protocol_fingerprint: **protocol_fingerprint,
+            game_ip,
+            game_port: game_addr.port(),
+        };
+
+        let Some(packet) = encode_discovery_packet(&response) else {
+            error!("unable to encode discovery response");
+            return;
+        };
+
+        if let Err(e) = socket.0.send_to(&packet, from) {
+            warn!("unable to answer discovery query from {}: {}", from, e);
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use avian3d::prelude::Position;
+use bevy::{ecs::event::EventCursor, prelude::*};
+use common::game_mode::{GameMode, GameModeKind, PlayerDied, PlayerJoined, RoundResult};
+use protocol::component::{MatchState, MatchStatus, Player};
+
+use crate::replication::PLAYER_START_POSITION;
+
+/// Runs the active `GameMode`'s hooks while a round is in progress,
+/// and ends the round when the mode or the round timer says so
+pub struct GameModePlugin;
+
+impl Plugin for GameModePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlayerJoined>()
+            .add_event::<PlayerDied>()
+            .init_resource::<ActiveGameMode>()
+            .init_resource::<RoundTimer>()
+            .add_systems(OnEnter(MatchState::InProgress), start_round)
+            .add_systems(
+                Update,
+                (run_game_mode, respawn_dead_players).run_if(in_state(MatchState::InProgress)),
+            )
+            .add_systems(OnExit(MatchState::PostMatch), clear_winner);
+    }
+}
+
+/// Insert before startup to pick the game mode
+#[derive(Resource)]
+pub struct ActiveGameMode(pub Box<dyn GameMode>);
+
+impl Default for ActiveGameMode {
+    fn default() -> Self {
+        Self(GameModeKind::default().create())
+    }
+}
+
+/// Running while a round with a time limit is in progress
+#[derive(Resource, Default)]
+pub(crate) struct RoundTimer(pub Option<Timer>);
+
+fn start_round(world: &mut World) {
+    world.resource_scope(|world, mut game_mode: Mut<ActiveGameMode>| {
+        info!("starting a round of {}", game_mode.0.name());
+
+        world.resource_mut::<RoundTimer>().0 = game_mode
+            .0
+            .round_duration()
+            .map(|duration| Timer::new(duration, TimerMode::Once));
+
+        game_mode.0.on_round_start(world);
+    });
+}
+
+fn run_game_mode(
+    world: &mut World,
+    mut joined_cursor: Local<EventCursor<PlayerJoined>>,
+    mut died_cursor: Local<EventCursor<PlayerDied>>,
+) {
+    let joined: Vec<PlayerJoined> = joined_cursor
+        .read(world.resource::<Events<PlayerJoined>>())
+        .cloned()
+        .collect();
+    let died: Vec<PlayerDied> = died_cursor
+        .read(world.resource::<Events<PlayerDied>>())
+        .cloned()
+        .collect();
+
+    let delta = world.resource::<Time>().delta();
+    let time_up = world
+        .resource_mut::<RoundTimer>()
+        .0
+        .as_mut()
+        .is_some_and(|timer| timer.tick(delta).just_finished());
+
+    let result = world.resource_scope(|world, mut game_mode: Mut<ActiveGameMode>| {
+        for joined in &joined {
+            game_mode.0.on_player_join(world, joined);
+        }
+
+        for death in &died {
+            game_mode.0.on_death(world, death);
+        }
+
+        match game_mode.0.check_win(world) {
+            Some(result) => Some(result),
+            None if time_up => Some(game_mode.0.on_time_up(world)),
+            None => None,
+        }
+    });
+
+    if let Some(result) = result {
+        end_round(world, result);
+    }
+}
+
+fn end_round(world: &mut World, result: RoundResult) {
+    let winner = result
+        .winner
+        .and_then(|winner| world.get::<Player>(winner))
+        .map(|player| player.0);
+
+    match winner {
+        Some(client_id) => info!("round over, client {} wins", client_id),
+        None => info!("round over, it's a draw"),
+    }
+
+    for mut status in world.query::<&mut MatchStatus>().iter_mut(world) {
+        status.winner = winner;
+    }
+
+    world.resource_mut::<RoundTimer>().0 = None;
+    world
+        .resource_mut::<NextState<MatchState>>()
+        .set(MatchState::PostMatch);
+}
+
+fn respawn_dead_players(
+    mut died_events: EventReader<PlayerDied>,
+    mut q_players: Query<&mut Position, With<Player>>,
+) {
+    for death in died_events.read() {
+        if let Ok(mut position) = q_players.get_mut(death.victim) {
+            *position = PLAYER_START_POSITION;
+        }
+    }
+}
+
+fn clear_winner(mut q_match_status: Query<&mut MatchStatus>) {
+    for mut status in &mut q_match_status {
+        status.winner = None;
+    }
+}
@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use assets::{LevelAssetHashes, LevelAssetsReloaded};
+use bevy::prelude::*;
+use common::conditioner::NetworkConditions;
+use lightyear::{
+    prelude::{NetworkTarget, ServerConnectionManager},
+    server::config::ServerConfig,
+};
+use protocol::message::{ServerLevelReload, UnorderedReliable};
+
+use crate::conditioner::SetLinkConditioner;
+
+pub struct HotReloadPlugin;
+
+impl Plugin for HotReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ServerOptionsReloaded>()
+            .add_systems(Update, (broadcast_level_reload, apply_reloaded_options));
+    }
+}
+
+/// Sent by the launcher when the option files the server was started with change on disk
+#[derive(Event, Debug, Clone)]
+pub struct ServerOptionsReloaded {
+    pub tick_duration: Duration,
+    pub conditioner: Option<NetworkConditions>,
+}
+
+/// Tell every client to pick up the level that was just rebuilt
+fn broadcast_level_reload(
+    mut reloaded_events: EventReader<LevelAssetsReloaded>,
+    mut server: ResMut<ServerConnectionManager>,
+    level_asset_hashes: Res<LevelAssetHashes>,
+) {
+    for _ in reloaded_events.read() {
+        if let Err(e) = server.send_message_to_target::<UnorderedReliable, ServerLevelReload>(
+            &ServerLevelReload {
+                level_asset_hashes: level_asset_hashes.0.clone(),
+            },
+            NetworkTarget::All,
+        ) {
+            error!("unable to broadcast level reload, had error {}", e);
+        }
+    }
+}
+
+/// The tick rate can change in place, the conditioner is handed to the `ConditionerPlugin`
+fn apply_reloaded_options(
+    mut reloaded_events: EventReader<ServerOptionsReloaded>,
+    mut server_config: ResMut<ServerConfig>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+    mut set_conditioner: EventWriter<SetLinkConditioner>,
+) {
+    for options in reloaded_events.read() {
+        if server_config.shared.tick.tick_duration != options.tick_duration {
+            info!(
+                "tick duration changed to {:?}, clients must be restarted with the same value",
+                options.tick_duration
+            );
+            server_config.shared.tick.tick_duration = options.tick_duration;
+            fixed_time.set_timestep(options.tick_duration);
+        }
+
+        set_conditioner.send(SetLinkConditioner(options.conditioner));
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::{HashMap, HashSet};
+
+use avian3d::prelude::Position;
+use bevy::prelude::*;
+use lightyear::prelude::{
+    ClientId, ServerDisconnectEvent,
+    server::{RoomId, RoomManager},
+};
+use protocol::component::Player;
+
+use crate::spectator::Spectators;
+
+/// Splits the level into a grid of square cells, each one a lightyear room.
+/// Spatially replicated entities are in the room of the cell they stand in,
+/// and each client is in the rooms around its player, so it is only sent what is nearby.
+/// Spectators are in every room that has something in it.
+pub struct InterestPlugin;
+
+impl Plugin for InterestPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InterestConfig>()
+            .init_resource::<ClientCells>()
+            .add_observer(forget_disconnected_client)
+            .add_systems(Update, (update_entity_cells, update_client_cells).chain());
+    }
+}
+
+/// Insert before startup to change how far clients can see
+#[derive(Resource, Clone, Debug)]
+pub struct InterestConfig {
+    /// Width of a grid cell along x and z
+    pub cell_size: f32,
+    /// How many cells around its player's cell a client can see into
+    pub view_distance_cells: i32,
+}
+
+impl Default for InterestConfig {
+    fn default() -> Self {
+        Self {
+            cell_size: 50.0,
+            view_distance_cells: 1,
+        }
+    }
+}
+
+/// Only replicated to clients whose player is nearby. Needs a `Position`, and a
+/// `ServerReplicate` with `NetworkRelevanceMode::InterestManagement`.
+#[derive(Component, Default)]
+pub struct SpatiallyReplicated;
+
+/// The grid cell a `SpatiallyReplicated` entity was last put in
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GridCell(pub IVec2);
+
+/// The cells each client can currently see, i.e. the rooms it is in
+#[derive(Resource, Default)]
+struct ClientCells(HashMap<ClientId, HashSet<IVec2>>);
+
+fn cell_of(position: Vec3, config: &InterestConfig) -> IVec2 {
+    (Vec2::new(position.x, position.z) / config.cell_size)
+        .floor()
+        .as_ivec2()
+}
+
+/// One room per cell, packing both coordinates into the id
+fn room_of(cell: IVec2) -> RoomId {
+    RoomId(((cell.x as u32 as u64) << 32) | cell.y as u32 as u64)
+}
+
+fn update_entity_cells(
+    mut commands: Commands,
+    config: Res<InterestConfig>,
+    mut room_manager: ResMut<RoomManager>,
+    mut q_spatial: Query<(Entity, &Position, Option<&mut GridCell>), With<SpatiallyReplicated>>,
+) {
+    for (entity, position, grid_cell) in &mut q_spatial {
+        let cell = cell_of(position.0, &config);
+
+        match grid_cell {
+            Some(mut grid_cell) if grid_cell.0 != cell => {
+                room_manager.remove_entity(entity, room_of(grid_cell.0));
+                room_manager.add_entity(entity, room_of(cell));
+                grid_cell.0 = cell;
+            }
+            Some(_) => {}
+            None => {
+                room_manager.add_entity(entity, room_of(cell));
+                commands.entity(entity).insert(GridCell(cell));
+            }
+        }
+    }
+}
+
+/// Clients see the cells around their player, and nothing while they have no player.
+/// Spectators see every occupied cell.
+fn update_client_cells(
+    config: Res<InterestConfig>,
+    spectators: Res<Spectators>,
+    mut room_manager: ResMut<RoomManager>,
+    mut client_cells: ResMut<ClientCells>,
+    q_players: Query<(&Player, &GridCell)>,
+    q_occupied: Query<&GridCell>,
+) {
+    let mut visible: HashMap<ClientId, HashSet<IVec2>> = client_cells
+        .0
+        .keys()
+        .map(|client_id| (*client_id, HashSet::new()))
+        .collect();
+
+    let distance = config.view_distance_cells;
+    for (player, grid_cell) in &q_players {
+        let cells = visible.entry(player.0).or_default();
+        for x in -distance..=distance {
+            for y in -distance..=distance {
+                cells.insert(grid_cell.0 + IVec2::new(x, y));
+            }
+        }
+    }
+
+    if !spectators.is_empty() {
+        let occupied: HashSet<IVec2> = q_occupied.iter().map(|grid_cell| grid_cell.0).collect();
+        for client_id in spectators.iter() {
+            visible.insert(client_id, occupied.clone());
+        }
+    }
+
+    for (client_id, cells) in visible {
+        let previous = client_cells.0.entry(client_id).or_default();
+        if *previous == cells {
+            continue;
+        }
+
+        for left in previous.difference(&cells) {
+            room_manager.remove_client(client_id, room_of(*left));
+        }
+        for entered in cells.difference(previous) {
+            room_manager.add_client(client_id, room_of(*entered));
+        }
+
+        if cells.is_empty() {
+            client_cells.0.remove(&client_id);
+        } else {
+            *previous = cells;
+        }
+    }
+}
+
+fn forget_disconnected_client(
+    trigger: Trigger<ServerDisconnectEvent>,
+    mut client_cells: ResMut<ClientCells>,
+) {
+    client_cells.0.remove(&trigger.event().client_id);
+}
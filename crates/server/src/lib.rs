@@ -1,3 +1,20 @@
 pub mod app;
+pub mod capacity;
+pub mod chat;
+pub mod conditioner;
+pub mod console;
+pub mod discovery;
+pub mod game_mode;
+pub mod hot_reload;
+pub mod interest;
+pub mod lobby;
+pub mod metrics;
+pub mod metrics_endpoint;
+pub mod names;
 mod network;
+pub mod npc;
+pub mod query;
+pub mod recorder;
 mod replication;
+pub mod spectator;
+pub mod teams;
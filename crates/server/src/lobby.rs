@@ -0,0 +1,322 @@
+use std::time::Duration;
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use lightyear::prelude::{ClientId, FromClients, ServerDisconnectEvent, ServerReplicate};
+use protocol::{
+    component::{ClientNames, LobbyMember, LobbyRoster, MatchState, MatchStatus, Player},
+    message::{ClientLevelLoadComplete, ClientSetReady},
+};
+
+use crate::{
+    capacity::Slots,
+    game_mode::{ActiveGameMode, RoundTimer},
+    replication::spawn_player,
+    spectator::Spectators,
+    teams::TeamAssignments,
+};
+
+/// Holds clients in a lobby once they have loaded the level, and only spawns
+/// their players when enough of them are ready and the countdown has run out
+pub struct LobbyPlugin;
+
+impl Plugin for LobbyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<MatchState>()
+            .init_resource::<LobbyConfig>()
+            .init_resource::<MatchTimer>()
+            .init_resource::<AwaitingSlot>()
+            .add_observer(on_client_disconnect)
+            .add_systems(Startup, spawn_match)
+            .add_systems(
+                Update,
+                (
+                    (on_client_load_complete, on_client_set_ready),
+                    admit_loaded_players,
+                    start_countdown_when_ready.run_if(in_state(MatchState::Lobby)),
+                    tick_countdown.run_if(in_state(MatchState::Countdown)),
+                    return_to_lobby_when_empty.run_if(in_state(MatchState::InProgress)),
+                    tick_post_match.run_if(in_state(MatchState::PostMatch)),
+                    sync_match_status,
+                )
+                    .chain(),
+            )
+            .add_systems(OnEnter(MatchState::Countdown), start_countdown_timer)
+            .add_systems(OnEnter(MatchState::InProgress), spawn_lobby_players)
+            .add_systems(OnEnter(MatchState::PostMatch), start_post_match_timer)
+            .add_systems(
+                OnExit(MatchState::PostMatch),
+                (despawn_players, clear_ready),
+            );
+    }
+}
+
+/// Insert before startup to change how matches start and end
+#[derive(Resource, Clone, Debug)]
+pub struct LobbyConfig {
+    /// Players that must be in the lobby, all of them ready, before the countdown starts
+    pub min_players: usize,
+    pub countdown: Duration,
+    /// How long results are shown before going back to the lobby
+    pub post_match: Duration,
+}
+
+impl Default for LobbyConfig {
+    fn default() -> Self {
+        Self {
+            min_players: 1,
+            countdown: Duration::from_secs(5),
+            post_match: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Times the `Countdown` and `PostMatch` phases
+#[derive(Resource, Default)]
+struct MatchTimer(Timer);
+
+/// Players that loaded the level, waiting to be let into the lobby. Queued players
+/// wait here until someone leaves and the queue hands them a slot.
+#[derive(Resource, Default)]
+struct AwaitingSlot(HashSet<ClientId>);
+
+fn spawn_match(mut commands: Commands, game_mode: Res<ActiveGameMode>) {
+    commands.spawn((
+        MatchStatus {
+            state: MatchState::Lobby,
+            remaining_secs: 0,
+            game_mode: game_mode.0.name().to_string(),
+            winner: None,
+        },
+        LobbyRoster::default(),
+        ClientNames::default(),
+        ServerReplicate::default(),
+        Name::new("Match"),
+    ));
+}
+
+/// Capacity was settled when the client connected, so all that is left is whether
+/// it came to watch or to play
+fn on_client_load_complete(
+    mut ev_client_load_complete: ResMut<Events<FromClients<ClientLevelLoadComplete>>>,
+    slots: Res<Slots>,
+    mut assignments: ResMut<TeamAssignments>,
+    mut spectators: ResMut<Spectators>,
+    mut awaiting: ResMut<AwaitingSlot>,
+) {
+    for ev in ev_client_load_complete.drain() {
+        if slots.holds_spectator_slot(ev.from) {
+            info!("client {} is spectating", ev.from);
+            spectators.insert(ev.from);
+            assignments.unassign(ev.from);
+        } else {
+            awaiting.0.insert(ev.from);
+        }
+    }
+}
+
+/// Let players that loaded the level into the lobby once they hold a player slot
+fn admit_loaded_players(
+    mut commands: Commands,
+    mut q_roster: Query<&mut LobbyRoster>,
+    match_state: Res<State<MatchState>>,
+    q_players: Query<&Player>,
+    assignments: Res<TeamAssignments>,
+    slots: Res<Slots>,
+    mut awaiting: ResMut<AwaitingSlot>,
+) {
+    awaiting.0.retain(|client_id| {
+        if !slots.holds_player_slot(*client_id) {
+            return true;
+        }
+
+        admit_player(
+            &mut commands,
+            *client_id,
+            &mut q_roster,
+            &match_state,
+            &q_players,
+            &assignments,
+        );
+        false
+    });
+}
+
+fn admit_player(
+    commands: &mut Commands,
+    client_id: ClientId,
+    q_roster: &mut Query<&mut LobbyRoster>,
+    match_state: &State<MatchState>,
+    q_players: &Query<&Player>,
+    assignments: &TeamAssignments,
+) {
+    for mut roster in q_roster.iter_mut() {
+        if roster.member(client_id).is_some() {
+            warn!(
+                "Client {} reported load complete, but is already in the lobby. Ignoring.",
+                client_id
+            );
+            continue;
+        }
+
+        roster.0.push(LobbyMember {
+            client_id,
+            team: assignments.team_of(client_id).unwrap_or_default(),
+            ready: false,
+        });
+    }
+
+    // Late joiners go straight into the running match
+    let player_exists = q_players.iter().any(|player| player.0 == client_id);
+    if *match_state.get() == MatchState::InProgress && !player_exists {
+        spawn_player(commands, client_id);
+    }
+}
+
+fn on_client_set_ready(
+    mut ev_client_set_ready: ResMut<Events<FromClients<ClientSetReady>>>,
+    mut q_roster: Query<&mut LobbyRoster>,
+) {
+    for ev in ev_client_set_ready.drain() {
+        for mut roster in &mut q_roster {
+            if let Some(member) = roster
+                .0
+                .iter_mut()
+                .find(|member| member.client_id == ev.from)
+            {
+                member.ready = ev.message.0;
+            }
+        }
+    }
+}
+
+fn on_client_disconnect(
+    trigger: Trigger<ServerDisconnectEvent>,
+    mut q_roster: Query<&mut LobbyRoster>,
+    mut awaiting: ResMut<AwaitingSlot>,
+) {
+    let client_id = trigger.event().client_id;
+    awaiting.0.remove(&client_id);
+
+    for mut roster in &mut q_roster {
+        roster.0.retain(|member| member.client_id != client_id);
+    }
+}
+
+fn everyone_ready(roster: &LobbyRoster, config: &LobbyConfig) -> bool {
+    roster.0.len() >= config.min_players.max(1) && roster.0.iter().all(|member| member.ready)
+}
+
+fn start_countdown_when_ready(
+    q_roster: Query<&LobbyRoster>,
+    config: Res<LobbyConfig>,
+    mut next_match_state: ResMut<NextState<MatchState>>,
+) {
+    if q_roster
+        .iter()
+        .any(|roster| everyone_ready(roster, &config))
+    {
+        next_match_state.set(MatchState::Countdown);
+    }
+}
+
+fn start_countdown_timer(config: Res<LobbyConfig>, mut timer: ResMut<MatchTimer>) {
+    info!("everyone is ready, starting in {:?}", config.countdown);
+    timer.0 = Timer::new(config.countdown, TimerMode::Once);
+}
+
+/// The countdown is called off if anyone unreadies or leaves before it ends
+fn tick_countdown(
+    time: Res<Time>,
+    q_roster: Query<&LobbyRoster>,
+    config: Res<LobbyConfig>,
+    mut timer: ResMut<MatchTimer>,
+    mut next_match_state: ResMut<NextState<MatchState>>,
+) {
+    if !q_roster
+        .iter()
+        .any(|roster| everyone_ready(roster, &config))
+    {
+        info!("countdown cancelled");
+        next_match_state.set(MatchState::Lobby);
+        return;
+    }
+
+    if timer.0.tick(time.delta()).finished() {
+        next_match_state.set(MatchState::InProgress);
+    }
+}
+
+fn spawn_lobby_players(mut commands: Commands, q_roster: Query<&LobbyRoster>) {
+    for roster in &q_roster {
+        info!("match starting with {} players", roster.0.len());
+
+        for member in &roster.0 {
+            spawn_player(&mut commands, member.client_id);
+        }
+    }
+}
+
+fn return_to_lobby_when_empty(
+    q_roster: Query<&LobbyRoster>,
+    mut next_match_state: ResMut<NextState<MatchState>>,
+) {
+    if q_roster.iter().all(|roster| roster.0.is_empty()) {
+        info!("everyone left, returning to the lobby");
+        next_match_state.set(MatchState::Lobby);
+    }
+}
+
+fn start_post_match_timer(config: Res<LobbyConfig>, mut timer: ResMut<MatchTimer>) {
+    timer.0 = Timer::new(config.post_match, TimerMode::Once);
+}
+
+fn tick_post_match(
+    time: Res<Time>,
+    mut timer: ResMut<MatchTimer>,
+    mut next_match_state: ResMut<NextState<MatchState>>,
+) {
+    if timer.0.tick(time.delta()).finished() {
+        next_match_state.set(MatchState::Lobby);
+    }
+}
+
+fn despawn_players(mut commands: Commands, q_players: Query<Entity, With<Player>>) {
+    for entity in &q_players {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Everyone has to ready up again for the next match
+fn clear_ready(mut q_roster: Query<&mut LobbyRoster>) {
+    for mut roster in &mut q_roster {
+        for member in roster.0.iter_mut() {
+            member.ready = false;
+        }
+    }
+}
+
+fn sync_match_status(
+    match_state: Res<State<MatchState>>,
+    timer: Res<MatchTimer>,
+    round_timer: Res<RoundTimer>,
+    mut q_match_status: Query<&mut MatchStatus>,
+) {
+    let state = *match_state.get();
+    let remaining_secs = match state {
+        MatchState::Countdown | MatchState::PostMatch => timer.0.remaining_secs().ceil() as u32,
+        MatchState::InProgress => round_timer
+            .0
+            .as_ref()
+            .map_or(0, |timer| timer.remaining_secs().ceil() as u32),
+        MatchState::Lobby => 0,
+    };
+
+    for mut status in &mut q_match_status {
+        if status.state != state || status.remaining_secs != remaining_secs {
+            status.state = state;
+            status.remaining_secs = remaining_secs;
+        }
+    }
+}
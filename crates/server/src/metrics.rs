@@ -0,0 +1,216 @@
+use std::{collections::HashMap, time::Duration};
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use lightyear::{
+    connection::server::{NetServer, ServerConnections},
+    prelude::{
+        ClientId, FromClients, NetworkTarget, ServerConnectEvent, ServerConnectionManager,
+        ServerDisconnectEvent,
+    },
+};
+use protocol::metrics::{
+    ClientMessageCounts, ConnectionMetrics, LossEstimator, METRICS_INTERVAL, MessageCounts,
+    MetricsChannel, MetricsReport, TrafficRates, TrafficTotals,
+};
+
+/// Collects the metrics each client reports about its connection, and reports back
+/// how the connection looks from the server. `ClientMetrics::report` renders them as text.
+/// Per client byte and packet rates are only ever what the client reported.
+pub struct MetricsPlugin;
+
+impl Plugin for MetricsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MetricsConfig>()
+            .init_resource::<ClientMetrics>()
+            .init_resource::<ServerTraffic>()
+            .add_observer(track_connected_client)
+            .add_observer(forget_disconnected_client)
+            .add_systems(
+                Update,
+                (
+                    receive_client_reports,
+                    (
+                        sample_server_traffic.run_if(resource_exists::<ServerConnections>),
+                        send_server_reports,
+                    )
+                        .chain()
+                        .run_if(on_timer(METRICS_INTERVAL)),
+                    log_client_metrics,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Insert before startup to log every client's metrics periodically
+#[derive(Resource, Clone, Debug, Default)]
+pub struct MetricsConfig {
+    pub log_interval: Option<Duration>,
+}
+
+#[derive(Default, Debug)]
+pub struct ClientMetricsEntry {
+    /// What the client last said about its connection
+    pub reported: ConnectionMetrics,
+    /// How the connection looks from the server, as last sent to the client. Netcode only
+    /// counts traffic per socket, so the byte and packet rates are left to the client
+    /// and the server's total is in `ServerTraffic`.
+    pub local: ConnectionMetrics,
+    /// Fraction of the client's reports that never reached us
+    loss: LossEstimator,
+    sequence: u32,
+}
+
+impl ClientMetricsEntry {
+    pub fn upstream_loss(&self) -> f32 {
+        self.loss.loss()
+    }
+}
+
+/// Latest metrics of every connected client
+#[derive(Resource, Default, Debug)]
+pub struct ClientMetrics(HashMap<ClientId, ClientMetricsEntry>);
+
+impl ClientMetrics {
+    pub fn get(&self, client_id: ClientId) -> Option<&ClientMetricsEntry> {
+        self.0.get(&client_id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&ClientId, &ClientMetricsEntry)> {
+        self.0.iter()
+    }
+
+    /// One line per client
+    pub fn report(&self) -> String {
+        self.0
+            .iter()
+            .map(|(client_id, entry)| {
+                format!(
+                    "client {}: client-reported {}, server side rtt {:.0} ms, jitter {:.0} ms, upstream loss {:.1}%\n",
+                    client_id,
+                    entry.reported,
+                    entry.local.rtt_ms,
+                    entry.local.jitter_ms,
+                    entry.upstream_loss() * 100.0
+                )
+            })
+            .collect()
+    }
+}
+
+/// Traffic through all of the server's sockets, averaged over the last `METRICS_INTERVAL`
+#[derive(Resource, Default, Debug)]
+pub struct ServerTraffic {
+    /// Only the byte and packet rates are filled in
+    pub metrics: ConnectionMetrics,
+    rates: TrafficRates,
+}
+
+fn receive_client_reports(
+    mut report_events: ResMut<Events<FromClients<MetricsReport>>>,
+    mut client_metrics: ResMut<ClientMetrics>,
+) {
+    for ev in report_events.drain() {
+        let entry = client_metrics.0.entry(ev.from).or_default();
+        entry.loss.receive(ev.message.sequence);
+        entry.reported = ev.message.metrics;
+    }
+}
+
+fn sample_server_traffic(connections: Res<ServerConnections>, mut traffic: ResMut<ServerTraffic>) {
+    let totals = connections
+        .servers
+        .iter()
+        .filter_map(|server| server.io())
+        .fold(TrafficTotals::default(), |totals, io| {
+            let stats = io.stats();
+            TrafficTotals {
+                bytes_in: totals.bytes_in + stats.bytes_received,
+                bytes_out: totals.bytes_out + stats.bytes_sent,
+                packets_in: totals.packets_in + stats.packets_received,
+                packets_out: totals.packets_out + stats.packets_sent,
+            }
+        });
+
+    let traffic = &mut *traffic;
+    traffic
+        .rates
+        .sample(totals, METRICS_INTERVAL, &mut traffic.metrics);
+}
+
+/// Tell each client its round trip and jitter as the server measures them, how many of
+/// its reports went missing and the messages of each type received from it
+fn send_server_reports(
+    mut server: ResMut<ServerConnectionManager>,
+    mut client_metrics: ResMut<ClientMetrics>,
+    message_counts: Res<ClientMessageCounts>,
+) {
+    for (client_id, entry) in client_metrics.0.iter_mut() {
+        if let Ok(connection) = server.connection(*client_id) {
+            entry.local.rtt_ms = connection.rtt().as_secs_f32() * 1000.0;
+            entry.local.jitter_ms = connection.jitter().as_secs_f32() * 1000.0;
+        }
+        entry.local.packet_loss = entry.upstream_loss();
+        entry.local.messages = message_counts
+            .get(*client_id)
+            .map(MessageCounts::to_vec)
+            .unwrap_or_default();
+
+        let report = MetricsReport {
+            sequence: entry.sequence,
+            metrics: entry.local.clone(),
+        };
+        entry.sequence = entry.sequence.wrapping_add(1);
+
+        if let Err(e) = server.send_message_to_target::<MetricsChannel, MetricsReport>(
+            &report,
+            NetworkTarget::Single(*client_id),
+        ) {
+            debug!(
+                "unable to send metrics report to client id {}, had error {}",
+                client_id, e
+            );
+        }
+    }
+}
+
+fn log_client_metrics(
+    config: Res<MetricsConfig>,
+    time: Res<Time<Real>>,
+    client_metrics: Res<ClientMetrics>,
+    traffic: Res<ServerTraffic>,
+    mut timer: Local<Option<Timer>>,
+) {
+    let Some(interval) = config.log_interval else {
+        return;
+    };
+
+    let timer = timer.get_or_insert_with(|| Timer::new(interval, TimerMode::Repeating));
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    info!("server: {}", traffic.metrics);
+    for line in client_metrics.report().lines() {
+        info!("{}", line);
+    }
+}
+
+fn track_connected_client(
+    trigger: Trigger<ServerConnectEvent>,
+    mut client_metrics: ResMut<ClientMetrics>,
+) {
+    client_metrics
+        .0
+        .insert(trigger.event().client_id, ClientMetricsEntry::default());
+}
+
+fn forget_disconnected_client(
+    trigger: Trigger<ServerDisconnectEvent>,
+    mut client_metrics: ResMut<ClientMetrics>,
+    mut message_counts: ResMut<ClientMessageCounts>,
+) {
+    let client_id = trigger.event().client_id;
+    client_metrics.0.remove(&client_id);
+    message_counts.remove(client_id);
+}
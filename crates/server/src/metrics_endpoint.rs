@@ -0,0 +1,308 @@
+use std::{
+    collections::HashSet,
+    fmt::Write,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use assets::{CurrentLevel, LevelState};
+use bevy::{ecs::entity::Entities, prelude::*};
+use lightyear::{
+    prelude::{ClientId, Replicating, ServerConnectEvent, ServerDisconnectEvent},
+    server::config::ServerConfig,
+};
+use protocol::metrics::ClientMessageCounts;
+use tiny_http::{Header, Response, Server};
+
+use crate::metrics::{ClientMetrics, ServerTraffic};
+
+/// Serves Prometheus text format gauges on `/metrics` over HTTP, when
+/// `MetricsEndpointConfig::addr` is set
+pub struct MetricsEndpointPlugin;
+
+impl Plugin for MetricsEndpointPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MetricsEndpointConfig>()
+            .init_resource::<ServerStats>()
+            .add_observer(count_connected_client)
+            .add_observer(count_disconnected_client)
+            .add_systems(Startup, bind_metrics_endpoint)
+            .add_systems(FixedFirst, start_tick_timing)
+            .add_systems(FixedLast, end_tick_timing)
+            .add_systems(OnEnter(LevelState::Loading), start_level_load_timing)
+            .add_systems(OnEnter(LevelState::Loaded), end_level_load_timing)
+            .add_systems(
+                Update,
+                serve_metrics_requests.run_if(resource_exists::<MetricsEndpoint>),
+            );
+    }
+}
+
+/// Insert before startup to serve `/metrics`. Port 0 binds any free port.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct MetricsEndpointConfig {
+    pub addr: Option<SocketAddr>,
+}
+
+#[derive(Resource)]
+pub struct MetricsEndpoint(Server);
+
+impl MetricsEndpoint {
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.0.server_addr().to_ip()
+    }
+}
+
+/// What the gauges are read from that isn't kept anywhere else
+#[derive(Resource, Default)]
+struct ServerStats {
+    connected_clients: HashSet<ClientId>,
+    tick_started: Option<Instant>,
+    last_tick_duration: Duration,
+    level_load_started: Option<Instant>,
+    level_load_durations: Vec<(String, Duration)>,
+}
+
+fn bind_metrics_endpoint(mut commands: Commands, config: Res<MetricsEndpointConfig>) {
+    let Some(addr) = config.addr else {
+        return;
+    };
+
+    match Server::http(addr) {
+        Ok(server) => {
+            info!("serving metrics on {:?}", server.server_addr().to_ip());
+            commands.insert_resource(MetricsEndpoint(server));
+        }
+        Err(e) => warn!("unable to serve metrics on {}: {}", addr, e),
+    }
+}
+
+fn count_connected_client(trigger: Trigger<ServerConnectEvent>, mut stats: ResMut<ServerStats>) {
+    stats.connected_clients.insert(trigger.event().client_id);
+}
+
+fn count_disconnected_client(
+    trigger: Trigger<ServerDisconnectEvent>,
+    mut stats: ResMut<ServerStats>,
+) {
+    stats.connected_clients.remove(&trigger.event().client_id);
+}
+
+fn start_tick_timing(mut stats: ResMut<ServerStats>) {
+    stats.tick_started = Some(Instant::now());
+}
+
+fn end_tick_timing(mut stats: ResMut<ServerStats>) {
+    if let Some(started) = stats.tick_started.take() {
+        stats.last_tick_duration = started.elapsed();
+    }
+}
+
+fn start_level_load_timing(mut stats: ResMut<ServerStats>) {
+    stats.level_load_started = Some(Instant::now());
+}
+
+fn end_level_load_timing(mut stats: ResMut<ServerStats>, current_level: Res<CurrentLevel>) {
+    if let Some(started) = stats.level_load_started.take() {
+        let level = format!("{:?}", current_level.0);
+        let duration = started.elapsed();
+
+        stats
+            .level_load_durations
+            .retain(|(loaded, _)| *loaded != level);
+        stats.level_load_durations.push((level, duration));
+    }
+}
+
+fn metric(out: &mut String, kind: &str, name: &str, help: &str, samples: &[(String, f64)]) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{}{} {}", name, labels, value);
+    }
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, samples: &[(String, f64)]) {
+    metric(out, "gauge", name, help, samples);
+}
+
+fn counter(out: &mut String, name: &str, help: &str, samples: &[(String, f64)]) {
+    metric(out, "counter", name, help, samples);
+}
+
+fn render_metrics(
+    stats: &ServerStats,
+    server_config: &ServerConfig,
+    frame_time: Duration,
+    entity_count: usize,
+    replicated_count: usize,
+    traffic: &ServerTraffic,
+    client_metrics: &ClientMetrics,
+    message_counts: &ClientMessageCounts,
+) -> String {
+    let mut out = String::new();
+    let unlabelled = |value: f64| vec![(String::new(), value)];
+
+    gauge(
+        &mut out,
+        "game_tick_rate_hz",
+        "Configured fixed tick rate",
+        &unlabelled(1.0 / server_config.shared.tick.tick_duration.as_secs_f64()),
+    );
+    gauge(
+        &mut out,
+        "game_tick_duration_seconds",
+        "Time spent simulating the last fixed tick",
+        &unlabelled(stats.last_tick_duration.as_secs_f64()),
+    );
+    gauge(
+        &mut out,
+        "game_frame_time_seconds",
+        "Wall clock time of the last frame",
+        &unlabelled(frame_time.as_secs_f64()),
+    );
+    gauge(
+        &mut out,
+        "game_connected_clients",
+        "Clients connected to the server",
+        &unlabelled(stats.connected_clients.len() as f64),
+    );
+    gauge(
+        &mut out,
+        "game_entities",
+        "Entities in the server world",
+        &unlabelled(entity_count as f64),
+    );
+    gauge(
+        &mut out,
+        "game_replicated_entities",
+        "Entities replicated to clients",
+        &unlabelled(replicated_count as f64),
+    );
+
+    gauge(
+        &mut out,
+        "game_replication_bytes_per_second",
+        "Traffic through the server's sockets, as counted by the server",
+        &[
+            (
+                "{direction=\"out\"}".to_string(),
+                traffic.metrics.bytes_out_per_sec as f64,
+            ),
+            (
+                "{direction=\"in\"}".to_string(),
+                traffic.metrics.bytes_in_per_sec as f64,
+            ),
+        ],
+    );
+
+    // Netcode only counts traffic per socket, so per client rates come from the clients
+    let client_traffic: Vec<(String, f64)> = client_metrics
+        .iter()
+        .flat_map(|(client_id, entry)| {
+            [
+                ("in", entry.reported.bytes_in_per_sec),
+                ("out", entry.reported.bytes_out_per_sec),
+            ]
+            .map(|(direction, value)| {
+                (
+                    format!(
+                        "{{client=\"{}\",direction=\"{}\"}}",
+                        client_id.to_bits(),
+                        direction
+                    ),
+                    value as f64,
+                )
+            })
+        })
+        .collect();
+    gauge(
+        &mut out,
+        "game_client_reported_bytes_per_second",
+        "Traffic each client reports on its own connection, not measured by the server",
+        &client_traffic,
+    );
+
+    let client_messages: Vec<(String, f64)> = message_counts
+        .iter()
+        .flat_map(|(client_id, counts)| {
+            counts.to_vec().into_iter().map(move |count| {
+                (
+                    format!(
+                        "{{client=\"{}\",channel=\"{}\",message=\"{}\"}}",
+                        client_id.to_bits(),
+                        count.channel,
+                        count.message
+                    ),
+                    count.received as f64,
+                )
+            })
+        })
+        .collect();
+    counter(
+        &mut out,
+        "game_client_messages_received_total",
+        "Messages the server received from each client, by channel and type",
+        &client_messages,
+    );
+
+    let level_loads: Vec<(String, f64)> = stats
+        .level_load_durations
+        .iter()
+        .map(|(level, duration)| (format!("{{level=\"{}\"}}", level), duration.as_secs_f64()))
+        .collect();
+    gauge(
+        &mut out,
+        "game_level_load_seconds",
+        "Time taken to load each level's assets",
+        &level_loads,
+    );
+
+    out
+}
+
+fn serve_metrics_requests(
+    endpoint: Res<MetricsEndpoint>,
+    stats: Res<ServerStats>,
+    server_config: Res<ServerConfig>,
+    real_time: Res<Time<Real>>,
+    entities: &Entities,
+    q_replicated: Query<(), With<Replicating>>,
+    traffic: Res<ServerTraffic>,
+    client_metrics: Res<ClientMetrics>,
+    message_counts: Res<ClientMessageCounts>,
+) {
+    loop {
+        let request = match endpoint.0.try_recv() {
+            Ok(Some(request)) => request,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("metrics endpoint error: {}", e);
+                return;
+            }
+        };
+
+        let result = if request.url() == "/metrics" {
+            let body = render_metrics(
+                &stats,
+                &server_config,
+                real_time.delta(),
+                entities.len() as usize,
+                q_replicated.iter().count(),
+                &traffic,
+                &client_metrics,
+                &message_counts,
+            );
+            let content_type =
+                Header::from_bytes(&b"Content-Type"[..], &b"text/plain; version=0.0.4"[..])
+                    .expect("static header is valid");
+            request.respond(Response::from_string(body).with_header(content_type))
+        } else {
+            request.respond(Response::from_string("not found").with_status_code(404))
+        };
+
+        if let Err(e) = result {
+            warn!("unable to answer metrics request: {}", e);
+        }
+    }
+}
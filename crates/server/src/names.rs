@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use lightyear::prelude::{ClientId, FromClients, ServerDisconnectEvent};
+use protocol::{
+    component::{ClientNames, MAX_PLAYER_NAME_LENGTH, Player, PlayerName},
+    message::ClientProfile,
+};
+
+/// Takes the display name each client asks for in its `ClientProfile`, cleans it up
+/// and makes it unique, then puts it on that client's player as `PlayerName` and
+/// lists it in the replicated `ClientNames`
+pub struct NamesPlugin;
+
+impl Plugin for NamesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerNames>()
+            .add_observer(forget_disconnected_name)
+            .add_systems(
+                Update,
+                (
+                    on_client_profile,
+                    sync_player_names,
+                    sync_client_names.run_if(resource_changed::<PlayerNames>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// The display name of every client that sent its profile
+#[derive(Resource, Default, Debug)]
+pub struct PlayerNames(HashMap<ClientId, String>);
+
+impl PlayerNames {
+    pub fn get(&self, client_id: ClientId) -> Option<&str> {
+        self.0.get(&client_id).map(String::as_str)
+    }
+
+    /// Every client that sent its profile and is still connected
+    pub fn iter(&self) -> impl Iterator<Item = (ClientId, &str)> + '_ {
+        self.0
+            .iter()
+            .map(|(client_id, name)| (*client_id, name.as_str()))
+    }
+
+    fn taken(&self, name: &str, by_other_than: ClientId) -> bool {
+        self.0.iter().any(|(client_id, taken)| {
+            *client_id != by_other_than && taken.eq_ignore_ascii_case(name)
+        })
+    }
+
+    /// Strip control characters and extra whitespace, cap the length, and number the
+    /// name if another client has it already. An empty name falls back to the client id.
+    pub fn validate(&self, client_id: ClientId, requested: &str) -> String {
+        let cleaned = requested
+            .split_whitespace()
+            .map(|word| word.chars().filter(|c| !c.is_control()).collect::<String>())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let base: String = if cleaned.is_empty() {
+            format!("Player {}", client_id)
+        } else {
+            cleaned
+        }
+        .chars()
+        .take(MAX_PLAYER_NAME_LENGTH)
+        .collect();
+
+        (1..)
+            .map(|number| {
+                if number == 1 {
+                    return base.clone();
+                }
+
+                let suffix = format!(" {}", number);
+                let kept = MAX_PLAYER_NAME_LENGTH.saturating_sub(suffix.chars().count());
+                let stem: String = base.chars().take(kept).collect();
+                format!("{}{}", stem.trim_end(), suffix)
+            })
+            .find(|name| !self.taken(name, client_id))
+            .unwrap_or(base)
+    }
+}
+
+/// Capacity reads the same profiles, so leave them in place
+fn on_client_profile(
+    mut ev_client_profile: EventReader<FromClients<ClientProfile>>,
+    mut names: ResMut<PlayerNames>,
+) {
+    for ev in ev_client_profile.read() {
+        let name = names.validate(ev.from, &ev.message.name);
+        if name != ev.message.name {
+            info!(
+                "client {} asked to be called {:?}, named {:?}",
+                ev.from, ev.message.name, name
+            );
+        } else {
+            info!("client {} is called {:?}", ev.from, name);
+        }
+        names.0.insert(ev.from, name);
+    }
+}
+
+/// The profile and the player can arrive in either order, so keep checking
+fn sync_player_names(
+    mut commands: Commands,
+    names: Res<PlayerNames>,
+    mut q_players: Query<(Entity, &Player, Option<&mut PlayerName>)>,
+) {
+    for (entity, player, player_name) in &mut q_players {
+        let Some(name) = names.get(player.0) else {
+            continue;
+        };
+
+        match player_name {
+            Some(mut player_name) if player_name.0 != name => player_name.0 = name.to_string(),
+            Some(_) => {}
+            None => {
+                commands.entity(entity).insert(PlayerName(name.to_string()));
+            }
+        }
+    }
+}
+
+fn sync_client_names(names: Res<PlayerNames>, mut q_client_names: Query<&mut ClientNames>) {
+    for mut client_names in &mut q_client_names {
+        client_names.0 = names
+            .0
+            .iter()
+            .map(|(client_id, name)| (*client_id, name.clone()))
+            .collect();
+    }
+}
+
+fn forget_disconnected_name(
+    trigger: Trigger<ServerDisconnectEvent>,
+    mut names: ResMut<PlayerNames>,
+) {
+    names.0.remove(&trigger.event().client_id);
+}
@@ -1,10 +1,18 @@
+use std::time::Duration;
+
 use assets::{CurrentLevel, LevelState};
 use bevy::prelude::*;
 use lightyear::prelude::{
-    FromClients, ReplicationGroup, ServerConnectionManager,
+    ClientId, FromClients, NetworkTarget, ReplicationGroup, ServerConnectEvent,
+    ServerConnectionManager,
     server::{NetworkingState, ServerCommandsExt, ServerConnection},
 };
-use protocol::message::Level;
+use protocol::{
+    fingerprint::ProtocolFingerprint,
+    message::{
+        DisconnectReason, Handshake, Level, ServerDisconnectNotice, ServerHello, UnorderedReliable,
+    },
+};
 
 use crate::app::ServerMode;
 
@@ -12,14 +20,115 @@ pub struct NetworkPlugin;
 
 impl Plugin for NetworkPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, start_server);
+        app.add_systems(Startup, start_server)
+            .add_observer(send_hello);
+
+        app.init_resource::<PendingDisconnects>().add_systems(
+            Update,
+            (process_pending_disconnects, notify_clients_of_shutdown),
+        );
     }
 }
 
 pub(crate) const REPLICATION_GROUP_PREDICTED: ReplicationGroup = ReplicationGroup::new_id(42);
 
+/// How long a client has to receive its `ServerDisconnectNotice` before we drop it
+const DISCONNECT_NOTICE_GRACE: Duration = Duration::from_millis(250);
+
+/// Clients that have been sent a `ServerDisconnectNotice` and will be dropped once their timer ends
+#[derive(Resource, Default)]
+struct PendingDisconnects(Vec<(ClientId, Timer)>);
+
+pub trait DisconnectWithReasonExt {
+    /// Tell a client why it is being disconnected, then disconnect it shortly after
+    fn disconnect_with_reason(&mut self, client_id: ClientId, reason: DisconnectReason);
+}
+
+impl DisconnectWithReasonExt for Commands<'_, '_> {
+    fn disconnect_with_reason(&mut self, client_id: ClientId, reason: DisconnectReason) {
+        self.queue(move |world: &mut World| {
+            info!("disconnecting client {} because {:?}", client_id, reason);
+
+            let mut server = world.resource_mut::<ServerConnectionManager>();
+            if let Err(e) = server
+                .send_message_to_target::<UnorderedReliable, ServerDisconnectNotice>(
+                    &ServerDisconnectNotice { reason },
+                    NetworkTarget::Single(client_id),
+                )
+            {
+                error!(
+                    "unable to send disconnect notice to client id {}, had error {}",
+                    client_id, e
+                );
+            }
+
+            world.resource_mut::<PendingDisconnects>().0.push((
+                client_id,
+                Timer::new(DISCONNECT_NOTICE_GRACE, TimerMode::Once),
+            ));
+        });
+    }
+}
+
 fn start_server(mut commands: Commands, mut current_level: ResMut<CurrentLevel>) {
     commands.start_server();
 
     current_level.0 = Level::Example;
 }
+
+/// Clients built from a different protocol leave on reading this, and the others reply
+/// with their `ClientProfile`
+fn send_hello(
+    trigger: Trigger<ServerConnectEvent>,
+    mut server: ResMut<ServerConnectionManager>,
+    protocol_fingerprint: Res<ProtocolFingerprint>,
+) {
+    let client_id = trigger.event().client_id;
+
+    if let Err(e) = server.send_message_to_target::<Handshake, ServerHello>(
+        &ServerHello {
+            protocol_fingerprint: **protocol_fingerprint,
+        },
+        NetworkTarget::Single(client_id),
+    ) {
+        error!(
+            "unable to send hello to client id {}, had error {}",
+            client_id, e
+        );
+    }
+}
+
+fn process_pending_disconnects(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pending_disconnects: ResMut<PendingDisconnects>,
+) {
+    pending_disconnects.0.retain_mut(|(client_id, timer)| {
+        if timer.tick(time.delta()).finished() {
+            commands.disconnect(*client_id);
+            false
+        } else {
+            true
+        }
+    });
+}
+
+/// Best effort: the app stops after the frame in which `AppExit` is sent,
+/// so this only reaches clients if it runs before this frame's messages go out
+fn notify_clients_of_shutdown(
+    mut exit_events: EventReader<AppExit>,
+    mut server: ResMut<ServerConnectionManager>,
+) {
+    if exit_events.read().next().is_none() {
+        return;
+    }
+
+    if let Err(e) = server.send_message_to_target::<UnorderedReliable, ServerDisconnectNotice>(
+        &ServerDisconnectNotice {
+            reason: DisconnectReason::ServerShutdown,
+        },
+        NetworkTarget::All,
+    ) {
+        error!("unable to notify clients of shutdown, had error {}", e);
+    }
+}
@@ -0,0 +1,170 @@
+use assets::{LevelState, Waypoint};
+use avian3d::prelude::{Position, Rotation};
+use bevy::prelude::*;
+use common::player::{self, CharacterMovement};
+use leafwing_input_manager::prelude::ActionState;
+use lightyear::prelude::{
+    NetworkRelevanceMode, NetworkTarget, ReplicateHierarchy, ServerReplicate, server::SyncTarget,
+};
+use protocol::{
+    component::{Npc, Player},
+    input::NetworkedInput,
+};
+
+use crate::{interest::SpatiallyReplicated, replication::PLAYER_START_POSITION};
+
+/// Populates the level with server controlled characters. They move through the same
+/// `ActionState<NetworkedInput>` as players, and are interpolated on every client.
+pub struct NpcPlugin;
+
+impl Plugin for NpcPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NpcConfig>()
+            .add_systems(OnEnter(LevelState::Loaded), spawn_npcs)
+            .add_systems(OnExit(LevelState::Loaded), despawn_npcs)
+            .add_systems(
+                FixedUpdate,
+                drive_npcs
+                    .before(CharacterMovement)
+                    .run_if(in_state(LevelState::Loaded)),
+            );
+    }
+}
+
+/// Insert before startup to change how many NPCs are spawned with each level
+#[derive(Resource, Clone, Debug, Default)]
+pub struct NpcConfig {
+    /// NPCs take turns at each of `NpcBehaviour::ALL`
+    pub count: usize,
+}
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NpcBehaviour {
+    /// Heads off in a new random direction every couple of seconds
+    Wander,
+    /// Walks up to whichever player is closest
+    FollowNearestPlayer,
+    /// Walks between the level's `Waypoint`s in name order, or wanders if it has none
+    Patrol { next: usize },
+}
+
+impl NpcBehaviour {
+    pub const ALL: [NpcBehaviour; 3] = [
+        NpcBehaviour::Wander,
+        NpcBehaviour::FollowNearestPlayer,
+        NpcBehaviour::Patrol { next: 0 },
+    ];
+}
+
+/// NPCs start on a circle this far around the player start
+const SPAWN_RADIUS: f32 = 10.0;
+
+/// Close enough to a target to stop, or to move on to the next waypoint
+const ARRIVE_DISTANCE: f32 = 5.0;
+
+const WANDER_SECS: f32 = 2.0;
+
+fn spawn_npcs(mut commands: Commands, config: Res<NpcConfig>) {
+    for index in 0..config.count {
+        let angle = index as f32 / config.count as f32 * std::f32::consts::TAU;
+        let offset = Vec3::new(angle.cos(), 0.0, angle.sin()) * SPAWN_RADIUS;
+
+        commands.spawn((
+            Position(PLAYER_START_POSITION.0 + offset),
+            Rotation::default(),
+            Npc,
+            NpcBehaviour::ALL[index % NpcBehaviour::ALL.len()],
+            ActionState::<NetworkedInput>::default(),
+            SpatiallyReplicated,
+            ServerReplicate {
+                sync: SyncTarget {
+                    interpolation: NetworkTarget::All,
+                    ..default()
+                },
+                hierarchy: ReplicateHierarchy {
+                    enabled: false,
+                    ..default()
+                },
+                relevance_mode: NetworkRelevanceMode::InterestManagement,
+                ..default()
+            },
+            Name::new("Npc"),
+        ));
+    }
+
+    if config.count > 0 {
+        info!("spawned {} npcs", config.count);
+    }
+}
+
+fn despawn_npcs(mut commands: Commands, q_npcs: Query<Entity, With<Npc>>) {
+    for entity in &q_npcs {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// The `NetworkedInput::Move` that walks from `from` towards `to`, zero once there
+fn move_towards(from: Vec3, to: Vec3) -> Vec2 {
+    let delta = to - from;
+    let flat = Vec2::new(delta.x, delta.z);
+
+    if flat.length() < ARRIVE_DISTANCE {
+        Vec2::ZERO
+    } else {
+        // Forward on the stick is -z in the world
+        Vec2::new(flat.x, -flat.y)
+    }
+}
+
+/// Differs per NPC and changes every `WANDER_SECS`
+fn wander_direction(entity: Entity, secs: f32) -> Vec2 {
+    let segment = (secs / WANDER_SECS).floor() + entity.index() as f32 * 17.0;
+    player::wander_direction(segment)
+}
+
+fn drive_npcs(
+    time: Res<Time>,
+    mut q_npcs: Query<
+        (
+            Entity,
+            &Position,
+            &mut NpcBehaviour,
+            &mut ActionState<NetworkedInput>,
+        ),
+        With<Npc>,
+    >,
+    q_players: Query<&Position, With<Player>>,
+    q_waypoints: Query<(&Name, &GlobalTransform), With<Waypoint>>,
+) {
+    let mut waypoints: Vec<_> = q_waypoints.iter().collect();
+    waypoints.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+
+    for (entity, position, mut behaviour, mut action_state) in &mut q_npcs {
+        let direction = match &mut *behaviour {
+            NpcBehaviour::Wander => wander_direction(entity, time.elapsed_secs()),
+            NpcBehaviour::FollowNearestPlayer => q_players
+                .iter()
+                .min_by(|a, b| {
+                    a.0.distance_squared(position.0)
+                        .total_cmp(&b.0.distance_squared(position.0))
+                })
+                .map(|player| move_towards(position.0, player.0))
+                .unwrap_or(Vec2::ZERO),
+            NpcBehaviour::Patrol { next } => {
+                if waypoints.is_empty() {
+                    wander_direction(entity, time.elapsed_secs())
+                } else {
+                    *next %= waypoints.len();
+                    let target = waypoints[*next].1.translation();
+                    let direction = move_towards(position.0, target);
+                    if direction == Vec2::ZERO {
+                        *next = (*next + 1) % waypoints.len();
+                    }
+                    direction
+                }
+            }
+        };
+
+        action_state.set_axis_pair(&NetworkedInput::Move, direction);
+    }
+}
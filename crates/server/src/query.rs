@@ -0,0 +1,177 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io::ErrorKind,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
+    time::Duration,
+};
+
+use assets::CurrentLevel;
+use bevy::prelude::*;
+use lightyear::server::config::ServerConfig;
+use protocol::query::{QUERY_PORT, QUERY_VERSION, STATUS_QUERY, ServerStatus, StatusPlayer};
+use socket2::{Domain, Protocol, Socket, Type};
+
+use crate::{discovery::DiscoveryConfig, names::PlayerNames, spectator::Spectators};
+
+/// Answers status queries from outside tools, on a UDP port of its own
+pub struct QueryPlugin;
+
+impl Plugin for QueryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<QueryConfig>()
+            .init_resource::<RecentQueries>()
+            .add_systems(Startup, bind_query_socket)
+            .add_systems(
+                Update,
+                answer_status_queries.run_if(resource_exists::<QuerySocket>),
+            );
+    }
+}
+
+/// Insert before startup to change the query port and limits
+#[derive(Resource, Clone, Debug)]
+pub struct QueryConfig {
+    /// Port 0 binds any free port
+    pub port: u16,
+    /// Queries answered per source address within `rate_window`, the rest are dropped.
+    /// Keeps the port from being used to flood a spoofed address with answers.
+    pub max_queries: usize,
+    pub rate_window: Duration,
+}
+
+impl Default for QueryConfig {
+    fn default() -> Self {
+        Self {
+            port: QUERY_PORT,
+            max_queries: 5,
+            rate_window: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct QuerySocket(UdpSocket);
+
+impl QuerySocket {
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.0.local_addr().ok()
+    }
+}
+
+/// Dual stack, so that `launcher query` can reach it over ipv6 as well as ipv4
+fn open_query_socket(port: u16) -> std::io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV6, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_only_v6(false)?;
+    socket.bind(&SocketAddr::from((Ipv6Addr::UNSPECIFIED, port)).into())?;
+
+    Ok(socket.into())
+}
+
+fn bind_query_socket(mut commands: Commands, config: Res<QueryConfig>) {
+    let socket = open_query_socket(config.port)
+        .or_else(|e| {
+            warn!(
+                "unable to bind an ipv6 query socket, answering ipv4 only: {}",
+                e
+            );
+            UdpSocket::bind((Ipv4Addr::UNSPECIFIED, config.port))
+        })
+        .and_then(|socket| {
+            socket.set_nonblocking(true)?;
+            Ok(socket)
+        });
+
+    match socket {
+        Ok(socket) => {
+            info!("answering status queries on {:?}", socket.local_addr());
+            commands.insert_resource(QuerySocket(socket));
+        }
+        Err(e) => warn!(
+            "unable to bind query port {}, status queries won't be answered: {}",
+            config.port, e
+        ),
+    }
+}
+
+/// When each source address was last answered, within the rate window
+#[derive(Resource, Default)]
+struct RecentQueries(HashMap<IpAddr, VecDeque<Duration>>);
+
+fn answer_status_queries(
+    socket: Res<QuerySocket>,
+    config: Res<QueryConfig>,
+    mut recent: ResMut<RecentQueries>,
+    discovery_config: Res<DiscoveryConfig>,
+    server_config: Res<ServerConfig>,
+    current_level: Res<CurrentLevel>,
+    real_time: Res<Time<Real>>,
+    player_names: Res<PlayerNames>,
+    spectators: Res<Spectators>,
+) {
+    let now = real_time.elapsed();
+    recent.0.retain(|_, answered| {
+        while answered
+            .front()
+            .is_some_and(|answered_at| now.saturating_sub(*answered_at) >= config.rate_window)
+        {
+            answered.pop_front();
+        }
+        !answered.is_empty()
+    });
+
+    let mut buffer = [0; 64];
+
+    loop {
+        let (len, from) = match socket.0.recv_from(&mut buffer) {
+            Ok(received) => received,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => return,
+            Err(e) => {
+                warn!("query socket error: {}", e);
+                return;
+            }
+        };
+
+        if buffer[..len].trim_ascii() != STATUS_QUERY {
+            continue;
+        }
+
+        // Ipv4 clients of the dual stack socket show up as mapped ipv6 addresses
+        let answered = recent.0.entry(from.ip().to_canonical()).or_default();
+        if answered.len() >= config.max_queries {
+            continue;
+        }
+        answered.push_back(now);
+
+        let mut players: Vec<StatusPlayer> = player_names
+            .iter()
+            .filter(|(client_id, _)| !spectators.contains(*client_id))
+            .map(|(client_id, name)| StatusPlayer {
+                client_id,
+                name: name.to_string(),
+            })
+            .collect();
+        players.sort_by_key(|player| player.client_id.to_bits());
+
+        let status = ServerStatus {
+            version: QUERY_VERSION,
+            name: discovery_config.name.clone(),
+            current_level: current_level.0,
+            tick_rate_hz: 1.0 / server_config.shared.tick.tick_duration.as_secs_f64(),
+            players,
+            spectator_count: spectators.iter().count() as u32,
+            uptime_secs: real_time.elapsed_secs_f64(),
+        };
+
+        let packet = match serde_json::to_vec(&status) {
+            Ok(packet) => packet,
+            Err(e) => {
+                error!("unable to encode server status: {}", e);
+                return;
+            }
+        };
+
+        if let Err(e) = socket.0.send_to(&packet, from) {
+            warn!("unable to answer status query from {}: {}", from, e);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use std::{fs::File, io::BufWriter, path::PathBuf};
+
+use assets::{CurrentLevel, LevelState};
+use avian3d::prelude::{Position, Rotation};
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use leafwing_input_manager::prelude::ActionState;
+use protocol::{
+    component::{Npc, Player},
+    framed::FLUSH_INTERVAL,
+    input::NetworkedInput,
+    message::Level,
+    replay::{
+        EntitySnapshot, InputSnapshot, REPLAY_VERSION, ReplayFrame, ReplayHeader, ReplayWriter,
+        SnapshotKind,
+    },
+};
+
+/// Records every tick of players, NPCs and the inputs applied to them to a replay file,
+/// when `RecorderConfig::path` is set. `launcher replay` plays the file back.
+pub struct RecorderPlugin;
+
+impl Plugin for RecorderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RecorderConfig>()
+            .add_systems(OnEnter(LevelState::Loaded), start_recording)
+            .add_systems(
+                FixedLast,
+                record_frame.run_if(resource_exists::<Recorder>.and(in_state(LevelState::Loaded))),
+            )
+            .add_systems(
+                Update,
+                flush_recording.run_if(resource_exists::<Recorder>.and(on_timer(FLUSH_INTERVAL))),
+            );
+    }
+}
+
+/// Insert before startup to record a replay
+#[derive(Resource, Clone, Debug, Default)]
+pub struct RecorderConfig {
+    /// Overwritten when the server starts
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Resource)]
+struct Recorder {
+    writer: ReplayWriter<BufWriter<File>>,
+    level: Level,
+    tick: u32,
+}
+
+/// Reloading the level passes through here again, which keeps the running recording
+fn start_recording(
+    mut commands: Commands,
+    config: Res<RecorderConfig>,
+    recorder: Option<Res<Recorder>>,
+    current_level: Res<CurrentLevel>,
+    fixed_time: Res<Time<Fixed>>,
+) {
+    let Some(path) = &config.path else {
+        return;
+    };
+    if recorder.is_some_and(|recorder| recorder.level == **current_level) {
+        return;
+    }
+
+    let header = ReplayHeader {
+        version: REPLAY_VERSION,
+        level: **current_level,
+        tick_duration: fixed_time.timestep(),
+    };
+
+    match File::create(path).and_then(|file| ReplayWriter::new(BufWriter::new(file), &header)) {
+        Ok(writer) => {
+            info!("recording a replay of {:?} to {:?}", header.level, path);
+            commands.insert_resource(Recorder {
+                writer,
+                level: header.level,
+                tick: 0,
+            });
+        }
+        Err(e) => error!("unable to record a replay to {:?}: {}", path, e),
+    }
+}
+
+fn record_frame(
+    mut commands: Commands,
+    mut recorder: ResMut<Recorder>,
+    q_characters: Query<
+        (Entity, &Position, &Rotation, Option<&Player>),
+        Or<(With<Player>, With<Npc>)>,
+    >,
+    q_inputs: Query<(&Player, &ActionState<NetworkedInput>)>,
+) {
+    let entities = q_characters
+        .iter()
+        .map(|(entity, position, rotation, player)| EntitySnapshot {
+            id: entity.to_bits(),
+            kind: match player {
+                Some(player) => SnapshotKind::Player(player.0),
+                None => SnapshotKind::Npc,
+            },
+            position: position.0,
+            rotation: rotation.0,
+        })
+        .collect();
+
+    let inputs = q_inputs
+        .iter()
+        .filter_map(|(player, action_state)| {
+            let movement = action_state.dual_axis_data(&NetworkedInput::Move)?;
+            Some(InputSnapshot {
+                client_id: player.0,
+                movement: movement.pair,
+            })
+        })
+        .collect();
+
+    let frame = ReplayFrame {
+        tick: recorder.tick,
+        entities,
+        inputs,
+    };
+    recorder.tick += 1;
+
+    if let Err(e) = recorder.writer.write_frame(&frame) {
+        error!("stopped recording the replay: {}", e);
+        commands.remove_resource::<Recorder>();
+    }
+}
+
+fn flush_recording(mut commands: Commands, mut recorder: ResMut<Recorder>) {
+    if let Err(e) = recorder.writer.flush() {
+        error!("stopped recording the replay: {}", e);
+        commands.remove_resource::<Recorder>();
+    }
+}
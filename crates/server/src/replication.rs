@@ -1,79 +1,77 @@
-use assets::CurrentLevel;
+use assets::{CurrentLevel, LevelAssetHashes};
 use avian3d::prelude::{Position, Rotation};
 use bevy::prelude::*;
+use common::game_mode::PlayerJoined;
 use lightyear::prelude::{
-    FromClients, MessageSend, NetworkTarget, ReplicateHierarchy, Replicating, ServerConnectEvent,
+    ClientId, MessageSend, NetworkRelevanceMode, NetworkTarget, ReplicateHierarchy, Replicating,
     ServerConnectionManager, ServerDisconnectEvent, ServerReplicate,
     server::{ControlledBy, Lifetime, ServerCommandsExt, SyncTarget},
 };
 use protocol::{
     component::Player,
-    message::{ClientLevelLoadComplete, Level, ServerWelcome, UnorderedReliable},
+    message::{Level, ServerWelcome, UnorderedReliable},
 };
 
-use crate::network::REPLICATION_GROUP_PREDICTED;
+use crate::{
+    capacity::ClientAdmitted, interest::SpatiallyReplicated, network::REPLICATION_GROUP_PREDICTED,
+};
 
 pub struct ReplicationPlugin;
 impl Plugin for ReplicationPlugin {
     fn build(&self, app: &mut App) {
-        app.add_observer(on_client_connect_success);
+        app.add_observer(welcome_admitted_client);
         app.add_observer(on_client_disconnect);
-
-        app.add_systems(Update, on_client_load_complete);
     }
 }
 
-fn on_client_load_complete(
-    mut ev_client_load_complete: ResMut<Events<FromClients<ClientLevelLoadComplete>>>,
-    mut commands: Commands,
-    q_players: Query<&Player>,
-) {
-    for ev in ev_client_load_complete.drain() {
-        let player_exists = q_players.iter().any(|player_id| player_id.0 == ev.from);
-        let player_start_position = Position(Vec3::new(0.0, 6.0, 0.0));
+pub(crate) const PLAYER_START_POSITION: Position = Position(Vec3::new(0.0, 6.0, 0.0));
 
-        if !player_exists {
-            commands.spawn((
-                player_start_position,
-                Rotation::default(),
-                Player(ev.from),
-                ServerReplicate {
-                    group: REPLICATION_GROUP_PREDICTED,
-                    controlled_by: ControlledBy {
-                        target: NetworkTarget::Single(ev.from),
-                        lifetime: Lifetime::SessionBased,
-                    },
-                    sync: SyncTarget {
-                        prediction: NetworkTarget::Single(ev.from),
-                        interpolation: NetworkTarget::AllExceptSingle(ev.from),
-                    },
-                    hierarchy: ReplicateHierarchy {
-                        enabled: false,
-                        ..default()
-                    },
+/// Spawn the character a client controls, predicted by that client and interpolated by everyone
+/// else nearby
+pub(crate) fn spawn_player(commands: &mut Commands, client_id: ClientId) {
+    let player = commands
+        .spawn((
+            PLAYER_START_POSITION,
+            Rotation::default(),
+            Player(client_id),
+            SpatiallyReplicated,
+            ServerReplicate {
+                group: REPLICATION_GROUP_PREDICTED,
+                controlled_by: ControlledBy {
+                    target: NetworkTarget::Single(client_id),
+                    lifetime: Lifetime::SessionBased,
+                },
+                sync: SyncTarget {
+                    prediction: NetworkTarget::Single(client_id),
+                    interpolation: NetworkTarget::AllExceptSingle(client_id),
+                },
+                hierarchy: ReplicateHierarchy {
+                    enabled: false,
                     ..default()
                 },
-            ));
-        } else {
-            warn!(
-                "Client {} reported load complete, but character already existed in world. Ignoring.",
-                ev.from
-            );
-        }
-    }
+                relevance_mode: NetworkRelevanceMode::InterestManagement,
+                ..default()
+            },
+        ))
+        .id();
+
+    commands.send_event(PlayerJoined { player, client_id });
 }
 
-fn on_client_connect_success(
-    trigger: Trigger<ServerConnectEvent>,
+/// Only once capacity has found room for the client, so a full server never has it load the level
+fn welcome_admitted_client(
+    trigger: Trigger<ClientAdmitted>,
     mut commands: Commands,
     mut server: ResMut<ServerConnectionManager>,
     current_level: Res<CurrentLevel>,
+    level_asset_hashes: Res<LevelAssetHashes>,
 ) {
     let client_id = trigger.event().client_id;
 
     if let Err(e) = server.send_message_to_target::<UnorderedReliable, ServerWelcome>(
         &ServerWelcome {
             current_level: current_level.0,
+            level_asset_hashes: level_asset_hashes.0.clone(),
         },
         NetworkTarget::Single(client_id),
     ) {
@@ -86,7 +84,7 @@ fn on_client_connect_success(
         return;
     }
 
-    info!("connected client ${}", trigger.event().client_id);
+    info!("welcomed client ${}", client_id);
 }
 
 fn on_client_disconnect(trigger: Trigger<ServerDisconnectEvent>) {
@@ -0,0 +1,48 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use lightyear::prelude::{ClientId, ServerDisconnectEvent};
+
+/// Keeps track of clients that loaded the level as spectators. They never join the
+/// lobby or get a player, and see every spatially replicated entity.
+pub struct SpectatorPlugin;
+
+impl Plugin for SpectatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Spectators>()
+            .add_observer(forget_disconnected_spectator);
+    }
+}
+
+/// Every connected spectator, counted apart from players
+#[derive(Resource, Default, Debug)]
+pub struct Spectators(HashSet<ClientId>);
+
+impl Spectators {
+    pub fn contains(&self, client_id: ClientId) -> bool {
+        self.0.contains(&client_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ClientId> + '_ {
+        self.0.iter().copied()
+    }
+
+    pub(crate) fn insert(&mut self, client_id: ClientId) {
+        self.0.insert(client_id);
+    }
+}
+
+fn forget_disconnected_spectator(
+    trigger: Trigger<ServerDisconnectEvent>,
+    mut spectators: ResMut<Spectators>,
+) {
+    spectators.0.remove(&trigger.event().client_id);
+}
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use common::game_mode::PlayerJoined;
+use lightyear::prelude::{
+    ClientId, NetworkTarget, ServerConnectEvent, ServerDisconnectEvent, ServerReplicate,
+    server::ReplicateToClient,
+};
+use protocol::component::{Team, TeamWaypoint};
+
+/// Puts every client on a team when it connects, and keeps team-only entities
+/// replicating to exactly the clients on their team
+pub struct TeamsPlugin;
+
+impl Plugin for TeamsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TeamConfig>()
+            .init_resource::<TeamAssignments>()
+            .add_observer(assign_team_on_connect)
+            .add_observer(unassign_team_on_disconnect)
+            .add_systems(Startup, spawn_team_waypoints)
+            .add_systems(Update, (insert_player_teams, update_team_only_targets));
+    }
+}
+
+/// Insert before startup to change the number of teams
+#[derive(Resource, Clone, Debug)]
+pub struct TeamConfig {
+    pub team_count: u8,
+}
+
+impl Default for TeamConfig {
+    fn default() -> Self {
+        Self { team_count: 2 }
+    }
+}
+
+/// The team of every connected client
+#[derive(Resource, Default, Debug)]
+pub struct TeamAssignments(HashMap<ClientId, Team>);
+
+impl TeamAssignments {
+    pub fn team_of(&self, client_id: ClientId) -> Option<Team> {
+        self.0.get(&client_id).copied()
+    }
+
+    pub fn members(&self, team: Team) -> Vec<ClientId> {
+        self.0
+            .iter()
+            .filter(|(_, member_team)| **member_team == team)
+            .map(|(client_id, _)| *client_id)
+            .collect()
+    }
+
+    /// Spectators are on no team
+    pub(crate) fn unassign(&mut self, client_id: ClientId) {
+        self.0.remove(&client_id);
+    }
+
+    /// The team with the fewest members, lowest id first on ties
+    fn smallest_team(&self, team_count: u8) -> Team {
+        (0..team_count.max(1))
+            .map(Team)
+            .min_by_key(|team| self.members(*team).len())
+            .unwrap_or_default()
+    }
+}
+
+/// Only replicated to the clients on this team, however membership changes.
+/// Spawn alongside a `ServerReplicate` whose target will be overwritten.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct TeamOnly(pub Team);
+
+/// Network target reaching every member of `team`
+pub fn team_target(assignments: &TeamAssignments, team: Team) -> NetworkTarget {
+    NetworkTarget::Only(assignments.members(team))
+}
+
+fn assign_team_on_connect(
+    trigger: Trigger<ServerConnectEvent>,
+    config: Res<TeamConfig>,
+    mut assignments: ResMut<TeamAssignments>,
+) {
+    let client_id = trigger.event().client_id;
+    let team = assignments.smallest_team(config.team_count);
+
+    info!("client {} joins team {}", client_id, team.0);
+    assignments.0.insert(client_id, team);
+}
+
+fn unassign_team_on_disconnect(
+    trigger: Trigger<ServerDisconnectEvent>,
+    mut assignments: ResMut<TeamAssignments>,
+) {
+    assignments.0.remove(&trigger.event().client_id);
+}
+
+fn spawn_team_waypoints(mut commands: Commands, config: Res<TeamConfig>) {
+    for id in 0..config.team_count {
+        let team = Team(id);
+
+        commands.spawn((
+            TeamWaypoint {
+                team,
+                position: Vec3::new(id as f32 * 20.0 - 10.0, 0.0, 0.0),
+            },
+            TeamOnly(team),
+            ServerReplicate {
+                target: ReplicateToClient {
+                    target: NetworkTarget::None,
+                },
+                ..default()
+            },
+            Name::new(format!("Team {} waypoint", id)),
+        ));
+    }
+}
+
+fn insert_player_teams(
+    mut commands: Commands,
+    mut joined_events: EventReader<PlayerJoined>,
+    assignments: Res<TeamAssignments>,
+) {
+    for joined in joined_events.read() {
+        let Some(team) = assignments.team_of(joined.client_id) else {
+            warn!("client {} has a player but no team", joined.client_id);
+            continue;
+        };
+
+        if let Some(mut player) = commands.get_entity(joined.player) {
+            player.insert(team);
+        }
+    }
+}
+
+fn update_team_only_targets(
+    assignments: Res<TeamAssignments>,
+    mut q_team_only: Query<(Ref<TeamOnly>, &mut ReplicateToClient)>,
+) {
+    for (team_only, mut replicate) in &mut q_team_only {
+        if !assignments.is_changed() && !team_only.is_changed() {
+            continue;
+        }
+
+        replicate.target = team_target(&assignments, team_only.0);
+    }
+}
@@ -0,0 +1,90 @@
+//! Fills a one player server: the next client waits in the queue, the one after that is
+//! turned away, and the queued client gets the slot once the player leaves.
+
+use std::cell::Cell;
+
+use bevy::prelude::*;
+use client::{
+    game_state::GameState,
+    lobby::QueuePosition,
+    network::{DisconnectWithReasonExt, Disconnection},
+};
+use harness::TestHarness;
+use protocol::message::{DisconnectReason, ServerQueuePosition};
+use server::capacity::CapacityConfig;
+
+const PLAYER_ID: u64 = 1;
+const QUEUED_ID: u64 = 2;
+const REJECTED_ID: u64 = 3;
+
+fn queue_position(harness: &TestHarness, client_id: u64) -> Option<ServerQueuePosition> {
+    harness
+        .client(client_id)
+        .world()
+        .resource::<QueuePosition>()
+        .0
+}
+
+#[test]
+fn full_server_queues_then_rejects() {
+    let mut harness = TestHarness::new(&[PLAYER_ID, QUEUED_ID, REJECTED_ID]);
+    harness.server.insert_resource(CapacityConfig {
+        max_players: Some(1),
+        max_queue: 1,
+        ..default()
+    });
+
+    harness.connect(PLAYER_ID);
+    harness.run_until("the player in the lobby", |harness| {
+        harness.in_lobby(PLAYER_ID)
+    });
+
+    harness.connect(QUEUED_ID);
+    harness.run_until("the second client queued", |harness| {
+        queue_position(harness, QUEUED_ID).is_some()
+    });
+    assert_eq!(
+        queue_position(&harness, QUEUED_ID),
+        Some(ServerQueuePosition {
+            position: 1,
+            queue_length: 1,
+        })
+    );
+    assert_eq!(harness.game_state(QUEUED_ID), GameState::Lobby);
+    assert!(!harness.in_lobby(QUEUED_ID));
+
+    // Turned away before the welcome, so it never starts loading the level
+    let welcomed = Cell::new(false);
+    harness.connect(REJECTED_ID);
+    harness.run_until("the third client turned away", |harness| {
+        let state = harness.game_state(REJECTED_ID);
+        welcomed.set(
+            welcomed.get() || !matches!(state, GameState::ConnectingRemote | GameState::MainMenu),
+        );
+
+        state == GameState::MainMenu
+            && harness
+                .client(REJECTED_ID)
+                .world()
+                .resource::<Disconnection>()
+                .last_reason
+                .is_some()
+    });
+    assert!(matches!(
+        harness
+            .client(REJECTED_ID)
+            .world()
+            .resource::<Disconnection>()
+            .last_reason,
+        Some(DisconnectReason::ServerFull(_))
+    ));
+    assert!(!welcomed.get(), "the third client was welcomed");
+
+    let world = harness.client_mut(PLAYER_ID).world_mut();
+    world.commands().disconnect_by_request();
+    world.flush();
+
+    harness.run_until("the queued client in the lobby", |harness| {
+        harness.in_lobby(QUEUED_ID) && queue_position(harness, QUEUED_ID).is_none()
+    });
+}
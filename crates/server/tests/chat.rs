@@ -0,0 +1,124 @@
+//! Connects three clients to an in-process server and chats between them: blocked words
+//! are starred out, whispers only reach the two ends, and muted clients are told so. Also
+//! checks how the blocked words filter splits words.
+
+use bevy::prelude::*;
+use client::chat::{ChatHistory, SendChatExt};
+use harness::TestHarness;
+use lightyear::prelude::ClientId;
+use protocol::message::{ChatChannel, ClientChat};
+use server::chat::ChatModeration;
+
+const ALICE: u64 = 1;
+const BOB: u64 = 2;
+const CAROL: u64 = 3;
+
+fn send(harness: &mut TestHarness, client_id: u64, channel: ChatChannel, text: &str) {
+    let world = harness.client_mut(client_id).world_mut();
+    world.commands().send_chat(ClientChat {
+        channel,
+        text: String::from(text),
+    });
+    world.flush();
+}
+
+/// Every chat line the client has received, as `(sender, text)`
+fn received(harness: &TestHarness, client_id: u64) -> Vec<(Option<ClientId>, String)> {
+    harness
+        .client(client_id)
+        .world()
+        .resource::<ChatHistory>()
+        .0
+        .iter()
+        .map(|message| (message.from, message.text.clone()))
+        .collect()
+}
+
+#[test]
+fn chat_is_moderated_and_routed() {
+    let mut harness = TestHarness::new(&[ALICE, BOB, CAROL]);
+    let mut moderation = ChatModeration::default();
+    moderation.blocked_words.insert(String::from("darn"));
+    moderation.muted.insert(ClientId::Netcode(BOB));
+    harness.server.insert_resource(moderation);
+
+    harness.connect_all();
+    harness.run_until("everyone in the lobby", |harness| {
+        harness
+            .client_ids()
+            .into_iter()
+            .all(|client_id| harness.in_lobby(client_id))
+    });
+
+    send(&mut harness, ALICE, ChatChannel::All, "well, Darn it");
+    let expected = (
+        Some(ClientId::Netcode(ALICE)),
+        String::from("well, **** it"),
+    );
+    harness.run_until("everyone to hear alice", |harness| {
+        harness
+            .client_ids()
+            .into_iter()
+            .all(|client_id| received(harness, client_id).contains(&expected))
+    });
+
+    send(
+        &mut harness,
+        ALICE,
+        ChatChannel::Whisper(ClientId::Netcode(CAROL)),
+        "just you",
+    );
+    send(&mut harness, BOB, ChatChannel::All, "can anyone hear me");
+    harness.run_until("the whisper and bob's rejection", |harness| {
+        received(harness, CAROL).len() == 2 && received(harness, BOB).len() == 2
+    });
+
+    let whisper = (Some(ClientId::Netcode(ALICE)), String::from("just you"));
+    assert!(received(&harness, ALICE).contains(&whisper));
+    assert!(received(&harness, CAROL).contains(&whisper));
+    assert!(!received(&harness, BOB).contains(&whisper));
+
+    assert!(received(&harness, BOB).contains(&(None, String::from("You are muted"))));
+    assert_eq!(received(&harness, CAROL).len(), 2);
+
+    send(
+        &mut harness,
+        CAROL,
+        ChatChannel::Whisper(ClientId::Netcode(CAROL)),
+        "note to self",
+    );
+    let refusal = (None, String::from("You can't whisper to yourself"));
+    harness.run_until("carol's whisper to be refused", |harness| {
+        received(harness, CAROL).contains(&refusal)
+    });
+    assert!(
+        !received(&harness, CAROL)
+            .iter()
+            .any(|(_, text)| text == "note to self")
+    );
+}
+
+fn moderation(blocked: &[&str]) -> ChatModeration {
+    ChatModeration {
+        blocked_words: blocked.iter().map(|word| word.to_string()).collect(),
+        ..default()
+    }
+}
+
+#[test]
+fn blocked_words_are_split_on_punctuation() {
+    let moderation = moderation(&["darn", "heck"]);
+    assert_eq!(moderation.filter("darn-it"), "****-it");
+    assert_eq!(moderation.filter("(DARN),heck!"), "(****),****!");
+    assert_eq!(moderation.filter("what the...heck?"), "what the...****?");
+    // Only whole words are blocked
+    assert_eq!(moderation.filter("darned heckler"), "darned heckler");
+}
+
+#[test]
+fn blocked_words_are_split_on_any_whitespace() {
+    let moderation = moderation(&["darn"]);
+    assert_eq!(moderation.filter("oh\tdarn\nit"), "oh\t****\nit");
+    assert_eq!(moderation.filter("  Darn  "), "  ****  ");
+    assert_eq!(moderation.filter("d\u{e4}rn darn"), "d\u{e4}rn ****");
+}
@@ -0,0 +1,87 @@
+//! Connects two headless clients to an in-process server, moves one player far across the level
+//! and checks that the other client stops receiving it, then receives it again once it is back.
+
+use std::time::Duration;
+
+use avian3d::prelude::Position;
+use bevy::prelude::*;
+use harness::TestHarness;
+use lightyear::prelude::ClientId;
+use protocol::component::Player;
+use server::interest::InterestConfig;
+
+const CLIENT_IDS: [u64; 2] = [1, 2];
+
+/// Several view distances away from the start, whatever the cell size
+fn far_away(config: &InterestConfig) -> Vec3 {
+    Vec3::new(
+        config.cell_size * (config.view_distance_cells + 3) as f32,
+        6.0,
+        0.0,
+    )
+}
+
+fn sees_player(harness: &TestHarness, client_id: u64, player_id: u64) -> bool {
+    harness
+        .client(client_id)
+        .world()
+        .iter_entities()
+        .filter_map(|entity| entity.get::<Player>())
+        .any(|player| player.0 == ClientId::Netcode(player_id))
+}
+
+/// Moves a player on the server, where it has authority
+fn teleport_player(harness: &mut TestHarness, client_id: u64, position: Vec3) {
+    let world = harness.server.world_mut();
+    let mut q_players = world.query::<(&Player, &mut Position)>();
+    for (player, mut player_position) in q_players.iter_mut(world) {
+        if player.0 == ClientId::Netcode(client_id) {
+            player_position.0 = position;
+        }
+    }
+}
+
+#[test]
+fn far_away_players_are_not_replicated() {
+    let mut harness = TestHarness::new(&CLIENT_IDS);
+    let [near_id, far_id] = CLIENT_IDS;
+
+    // Everyone starts in the same spot, so everyone sees everyone
+    harness.start_match();
+    harness.run_until("both players", |harness| {
+        CLIENT_IDS.into_iter().all(|client_id| {
+            sees_player(harness, client_id, near_id) && sees_player(harness, client_id, far_id)
+        })
+    });
+
+    let far_away = far_away(harness.server.world().resource::<InterestConfig>());
+    teleport_player(&mut harness, far_id, far_away);
+
+    harness.run_until("far player to leave", |harness| {
+        !sees_player(harness, near_id, far_id) && !sees_player(harness, far_id, near_id)
+    });
+
+    // Keep running for a while, neither player may come back while they are apart
+    harness.run_for(Duration::from_secs(1), |harness| {
+        assert!(
+            !sees_player(harness, near_id, far_id),
+            "client {} received the far away player",
+            near_id
+        );
+        assert!(
+            !sees_player(harness, far_id, near_id),
+            "client {} received the far away player",
+            far_id
+        );
+    });
+
+    // Both still have their own player
+    assert!(sees_player(&harness, near_id, near_id));
+    assert!(sees_player(&harness, far_id, far_id));
+
+    teleport_player(&mut harness, far_id, Vec3::new(0.0, 6.0, 0.0));
+
+    harness.run_until("far player to return", |harness| {
+        sees_player(harness, near_id, far_id) && sees_player(harness, far_id, near_id)
+    });
+}
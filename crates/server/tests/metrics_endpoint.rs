@@ -0,0 +1,139 @@
+//! Starts a server with its metrics endpoint on a free port, connects a headless client,
+//! then fetches `/metrics` over plain HTTP the way a scraper would.
+
+use std::{
+    io::{Read, Write},
+    net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use harness::TestHarness;
+use lightyear::prelude::ClientId;
+use protocol::metrics::ClientMessageCounts;
+use server::{
+    metrics::{ClientMetrics, ServerTraffic},
+    metrics_endpoint::{MetricsEndpoint, MetricsEndpointConfig},
+};
+
+const CLIENT_ID: u64 = 1;
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Sends a bare HTTP/1.0 GET and returns the whole response, headers included
+fn http_get(addr: SocketAddr, path: &str) -> JoinHandle<String> {
+    let path = path.to_string();
+
+    thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).expect("unable to connect to metrics endpoint");
+        stream
+            .set_read_timeout(Some(TIMEOUT))
+            .expect("unable to set read timeout");
+        write!(stream, "GET {} HTTP/1.0\r\nHost: {}\r\n\r\n", path, addr)
+            .expect("unable to send request");
+
+        let mut response = String::new();
+        stream
+            .read_to_string(&mut response)
+            .expect("unable to read response");
+        response
+    })
+}
+
+/// Keeps the server answering while the request is in flight
+fn fetch(harness: &mut TestHarness, path: &str) -> String {
+    let addr = harness
+        .server
+        .world()
+        .get_resource::<MetricsEndpoint>()
+        .and_then(MetricsEndpoint::local_addr)
+        .expect("server should be serving metrics");
+    let request = http_get(addr, path);
+
+    harness.run_until(path, |_| request.is_finished());
+
+    request.join().expect("request thread panicked")
+}
+
+#[test]
+fn metrics_endpoint_serves_prometheus_text() {
+    let mut harness = TestHarness::new(&[CLIENT_ID]).with_server_resource(MetricsEndpointConfig {
+        addr: Some(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)),
+    });
+
+    harness.connect(CLIENT_ID);
+    harness.run_until("the server to sample its traffic", |harness| {
+        harness
+            .server
+            .world()
+            .resource::<ServerTraffic>()
+            .metrics
+            .bytes_in_per_sec
+            > 0.0
+    });
+    let client_id = ClientId::Netcode(CLIENT_ID);
+    harness.run_until("the client to load in and report", |harness| {
+        let world = harness.server.world();
+        let loaded = world
+            .resource::<ClientMessageCounts>()
+            .get(client_id)
+            .is_some_and(|counts| {
+                counts
+                    .to_vec()
+                    .iter()
+                    .any(|count| count.message == "ClientLevelLoadComplete")
+            });
+        let reported = world
+            .resource::<ClientMetrics>()
+            .get(client_id)
+            .is_some_and(|entry| entry.reported.bytes_out_per_sec > 0.0);
+        loaded && reported
+    });
+    let client = client_id.to_bits();
+    let client_traffic = format!(
+        "game_client_reported_bytes_per_second{{client=\"{}\",direction=\"out\"}} ",
+        client
+    );
+    let client_load_complete = format!(
+        "game_client_messages_received_total{{client=\"{}\",channel=\"UnorderedReliable\",message=\"ClientLevelLoadComplete\"}} 1\n",
+        client
+    );
+    let client_reports = format!(
+        "game_client_messages_received_total{{client=\"{}\",channel=\"MetricsChannel\",message=\"MetricsReport\"}} ",
+        client
+    );
+
+    let response = fetch(&mut harness, "/metrics");
+    assert!(
+        response.starts_with("HTTP/1.0 200") || response.starts_with("HTTP/1.1 200"),
+        "unexpected response: {}",
+        response
+    );
+    assert!(response.contains("text/plain; version=0.0.4"));
+
+    for expected in [
+        "# TYPE game_tick_duration_seconds gauge",
+        "game_frame_time_seconds ",
+        "game_connected_clients 1\n",
+        "game_entities ",
+        "game_replicated_entities ",
+        "game_replication_bytes_per_second{direction=\"out\"} ",
+        "game_level_load_seconds{level=\"Example\"} ",
+        client_traffic.as_str(),
+        client_load_complete.as_str(),
+        client_reports.as_str(),
+    ] {
+        assert!(
+            response.contains(expected),
+            "missing {:?} in:\n{}",
+            expected,
+            response
+        );
+    }
+
+    let not_found = fetch(&mut harness, "/other");
+    assert!(
+        not_found.starts_with("HTTP/1.0 404") || not_found.starts_with("HTTP/1.1 404"),
+        "unexpected response: {}",
+        not_found
+    );
+}
@@ -0,0 +1,60 @@
+//! Spawns NPCs on an in-process server and checks that they move on their own and that
+//! a client receives them as interpolated entities.
+
+use avian3d::prelude::Position;
+use bevy::prelude::*;
+use harness::{TestHarness, count};
+use lightyear::prelude::client::Interpolated;
+use protocol::component::Npc;
+use server::npc::NpcConfig;
+
+const CLIENT_ID: u64 = 1;
+const NPC_COUNT: usize = 3;
+
+fn npc_positions(app: &mut App) -> Vec<(Entity, Vec3)> {
+    let world = app.world_mut();
+    let mut q_npcs = world.query_filtered::<(Entity, &Position), With<Npc>>();
+    q_npcs
+        .iter(world)
+        .map(|(entity, position)| (entity, position.0))
+        .collect()
+}
+
+#[test]
+fn npcs_move_and_are_interpolated() {
+    let mut harness = TestHarness::new(&[CLIENT_ID]);
+    harness
+        .server
+        .insert_resource(NpcConfig { count: NPC_COUNT });
+
+    harness.start_match();
+
+    harness.run_until("npcs on the client", |harness| {
+        harness
+            .client(CLIENT_ID)
+            .world()
+            .iter_entities()
+            .filter(|entity| entity.contains::<Npc>() && entity.contains::<Interpolated>())
+            .count()
+            == NPC_COUNT
+    });
+
+    let server_start = npc_positions(&mut harness.server);
+    assert_eq!(server_start.len(), NPC_COUNT);
+
+    harness.steps(120);
+
+    let server_end = npc_positions(&mut harness.server);
+    for (entity, start) in &server_start {
+        let (_, end) = server_end
+            .iter()
+            .find(|(other, _)| other == entity)
+            .expect("npc despawned");
+        assert_ne!(start, end, "npc {} never moved on the server", entity);
+    }
+
+    assert_eq!(
+        count::<(With<Npc>, With<Interpolated>)>(harness.client_mut(CLIENT_ID)),
+        NPC_COUNT
+    );
+}
@@ -0,0 +1,96 @@
+//! Two clients ask for the same name. The server cleans it up, numbers the second one,
+//! and both names reach the other client on its interpolated copy of each player.
+
+use bevy::prelude::*;
+use client::profile::Profile;
+use harness::TestHarness;
+use lightyear::prelude::{ClientId, client::Interpolated};
+use protocol::component::{ClientNames, Player, PlayerName};
+
+const FIRST_ID: u64 = 1;
+const SECOND_ID: u64 = 2;
+
+/// The name on `client_id`'s player as seen from `app`, if it is there yet
+fn player_name(app: &App, client_id: u64, interpolated: bool) -> Option<String> {
+    app.world()
+        .iter_entities()
+        .filter(|entity| !interpolated || entity.contains::<Interpolated>())
+        .filter(|entity| {
+            entity
+                .get::<Player>()
+                .is_some_and(|player| player.0 == ClientId::Netcode(client_id))
+        })
+        .find_map(|entity| entity.get::<PlayerName>())
+        .map(|name| name.0.clone())
+}
+
+/// `client_id`'s name in the `ClientNames` that `app` has, if it is there yet
+fn listed_name(app: &App, client_id: u64) -> Option<String> {
+    app.world()
+        .iter_entities()
+        .find_map(|entity| entity.get::<ClientNames>())
+        .and_then(|names| names.get(ClientId::Netcode(client_id)).map(str::to_string))
+}
+
+#[test]
+fn names_are_listed_before_the_match() {
+    let mut harness = TestHarness::new(&[FIRST_ID, SECOND_ID]);
+    harness.client_mut(FIRST_ID).insert_resource(Profile {
+        name: String::from("Alice"),
+    });
+    harness.client_mut(SECOND_ID).insert_resource(Profile {
+        name: String::from("Bob"),
+    });
+
+    harness.connect_all();
+    harness.run_until("both names in each lobby", |harness| {
+        [FIRST_ID, SECOND_ID].into_iter().all(|client_id| {
+            listed_name(harness.client(client_id), FIRST_ID).as_deref() == Some("Alice")
+                && listed_name(harness.client(client_id), SECOND_ID).as_deref() == Some("Bob")
+        })
+    });
+}
+
+#[test]
+fn names_are_validated_and_replicated() {
+    let mut harness = TestHarness::new(&[FIRST_ID, SECOND_ID]);
+    harness.client_mut(FIRST_ID).insert_resource(Profile {
+        name: String::from("  Sam\u{7}   the  Brave  "),
+    });
+    harness.client_mut(SECOND_ID).insert_resource(Profile {
+        name: String::from("sam the brave"),
+    });
+
+    // One after the other, so the first keeps its name
+    for client_id in [FIRST_ID, SECOND_ID] {
+        harness.connect(client_id);
+        harness.run_until("the client in the lobby", |harness| {
+            harness.in_lobby(client_id)
+        });
+    }
+    for client_id in [FIRST_ID, SECOND_ID] {
+        harness.set_ready(client_id, true);
+    }
+    harness.run_until("both playing", |harness| {
+        harness.player_spawned(FIRST_ID) && harness.player_spawned(SECOND_ID)
+    });
+
+    harness.run_until("both names on the server", |harness| {
+        player_name(&harness.server, FIRST_ID, false).is_some()
+            && player_name(&harness.server, SECOND_ID, false).is_some()
+    });
+    assert_eq!(
+        player_name(&harness.server, FIRST_ID, false).as_deref(),
+        Some("Sam the Brave")
+    );
+    assert_eq!(
+        player_name(&harness.server, SECOND_ID, false).as_deref(),
+        Some("sam the brave 2")
+    );
+
+    harness.run_until("each name on the other client", |harness| {
+        player_name(harness.client(SECOND_ID), FIRST_ID, true).as_deref() == Some("Sam the Brave")
+            && player_name(harness.client(FIRST_ID), SECOND_ID, true).as_deref()
+                == Some("sam the brave 2")
+    });
+}
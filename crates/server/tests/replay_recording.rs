@@ -0,0 +1,54 @@
+//! Records a replay on an in-process server while a client plays, then reads the file back
+//! and checks it holds the player, an NPC and the level.
+
+use std::{fs::File, io::BufReader};
+
+use harness::TestHarness;
+use lightyear::prelude::ClientId;
+use protocol::{
+    message::Level,
+    replay::{SnapshotKind, read_replay},
+};
+use server::{npc::NpcConfig, recorder::RecorderConfig};
+
+const CLIENT_ID: u64 = 1;
+
+#[test]
+fn recorded_replay_reads_back() {
+    let path = std::env::temp_dir().join(format!("replay_recording_{}.replay", std::process::id()));
+
+    let mut harness = TestHarness::new(&[CLIENT_ID]);
+    harness.server.insert_resource(RecorderConfig {
+        path: Some(path.clone()),
+    });
+    harness.server.insert_resource(NpcConfig { count: 1 });
+
+    harness.start_match();
+    harness.steps(60);
+
+    // Dropping the server flushes the recording
+    drop(harness);
+
+    let replay = read_replay(BufReader::new(File::open(&path).unwrap())).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    assert_ne!(replay.header.level, Level::Void);
+    assert!(!replay.header.tick_duration.is_zero());
+    assert!(replay.frames.len() >= 60);
+
+    for (index, frame) in replay.frames.iter().enumerate() {
+        assert_eq!(frame.tick, index as u32);
+    }
+
+    let last = replay.frames.last().unwrap();
+    assert!(
+        last.entities
+            .iter()
+            .any(|snapshot| snapshot.kind == SnapshotKind::Player(ClientId::Netcode(CLIENT_ID)))
+    );
+    assert!(
+        last.entities
+            .iter()
+            .any(|snapshot| snapshot.kind == SnapshotKind::Npc)
+    );
+}
@@ -0,0 +1,66 @@
+//! Connects a player and a spectator to an in-process server. The spectator must stay out
+//! of the lobby, never get a player, and receive the player's character as interpolated.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use client::{game_state::GameState, spectator::Spectate};
+use harness::{TestHarness, count};
+use lightyear::prelude::{ClientId, client::Interpolated};
+use protocol::component::Player;
+use server::{lobby::LobbyConfig, spectator::Spectators};
+
+const PLAYER_ID: u64 = 1;
+const SPECTATOR_ID: u64 = 2;
+
+#[test]
+fn spectator_watches_without_a_player() {
+    let mut harness = TestHarness::new(&[PLAYER_ID, SPECTATOR_ID]);
+    harness.server.insert_resource(LobbyConfig {
+        min_players: 1,
+        countdown: Duration::ZERO,
+        ..default()
+    });
+    harness
+        .client_mut(SPECTATOR_ID)
+        .insert_resource(Spectate(true));
+
+    harness.connect_all();
+    harness.run_until(
+        "the player in the lobby and the spectator watching",
+        |harness| {
+            harness.in_lobby(PLAYER_ID) && harness.game_state(SPECTATOR_ID) == GameState::Spectating
+        },
+    );
+    assert!(!harness.in_lobby(SPECTATOR_ID));
+    assert!(
+        harness
+            .server
+            .world()
+            .resource::<Spectators>()
+            .contains(ClientId::Netcode(SPECTATOR_ID))
+    );
+
+    harness.set_ready(PLAYER_ID, true);
+    harness.run_until("the player playing", |harness| {
+        harness.game_state(PLAYER_ID) == GameState::Playing
+    });
+
+    harness.run_until("the player's character on the spectator", |harness| {
+        harness
+            .client(SPECTATOR_ID)
+            .world()
+            .iter_entities()
+            .filter_map(|entity| {
+                entity
+                    .contains::<Interpolated>()
+                    .then(|| entity.get::<Player>())
+                    .flatten()
+            })
+            .any(|player| player.0 == ClientId::Netcode(PLAYER_ID))
+    });
+
+    assert_eq!(harness.game_state(SPECTATOR_ID), GameState::Spectating);
+    assert!(!harness.player_spawned(SPECTATOR_ID));
+    assert_eq!(count::<With<Player>>(&mut harness.server), 1);
+}
@@ -0,0 +1,135 @@
+//! Queries a server's status over UDP the way `launcher query` does, over ipv4 and ipv6,
+//! and checks that a burst of queries from one address only gets as many answers as the
+//! rate limit allows.
+
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
+    thread,
+    time::Duration,
+};
+
+use client::profile::Profile;
+use harness::TestHarness;
+use lightyear::prelude::ClientId;
+use protocol::query::{
+    QUERY_VERSION, STATUS_QUERY, ServerStatus, StatusPlayer, query_status, resolve_query_addr,
+};
+use server::{
+    discovery::DiscoveryConfig,
+    query::{QueryConfig, QuerySocket},
+};
+
+const CLIENT_ID: u64 = 1;
+
+/// A server answering status queries on a free port, and the port
+fn query_server(client_ids: &[u64]) -> (TestHarness, u16) {
+    let mut harness = TestHarness::new(client_ids);
+    harness.server.insert_resource(QueryConfig {
+        port: 0,
+        ..Default::default()
+    });
+    harness.server.insert_resource(DiscoveryConfig {
+        name: String::from("Query test"),
+        port: 0,
+    });
+    harness.step();
+
+    let port = harness
+        .server
+        .world()
+        .get_resource::<QuerySocket>()
+        .and_then(QuerySocket::local_addr)
+        .expect("the query socket was not bound")
+        .port();
+
+    (harness, port)
+}
+
+/// Keeps the server answering while the query is in flight
+fn query(harness: &mut TestHarness, ip: IpAddr, port: u16) -> ServerStatus {
+    let addr = SocketAddr::new(ip, port);
+    let query = thread::spawn(move || query_status(addr).map_err(|e| e.to_string()));
+    harness.run_until("the status query", |_| query.is_finished());
+
+    query
+        .join()
+        .expect("the query thread panicked")
+        .expect("the status query failed")
+}
+
+#[test]
+fn status_query_is_answered() {
+    let (mut harness, port) = query_server(&[CLIENT_ID]);
+    harness.client_mut(CLIENT_ID).insert_resource(Profile {
+        name: String::from("Alice"),
+    });
+
+    let status = query(&mut harness, IpAddr::V4(Ipv4Addr::LOCALHOST), port);
+    assert_eq!(status.version, QUERY_VERSION);
+    assert_eq!(status.name, "Query test");
+    assert!(status.players.is_empty());
+    assert_eq!(status.spectator_count, 0);
+
+    harness.connect(CLIENT_ID);
+    harness.run_until("lobby", |harness| harness.in_lobby(CLIENT_ID));
+
+    let status = query(&mut harness, IpAddr::V4(Ipv4Addr::LOCALHOST), port);
+    assert_eq!(
+        status.players,
+        vec![StatusPlayer {
+            client_id: ClientId::Netcode(CLIENT_ID),
+            name: String::from("Alice"),
+        }]
+    );
+}
+
+#[test]
+fn status_query_is_answered_over_ipv6() {
+    let (mut harness, port) = query_server(&[]);
+
+    let status = query(&mut harness, IpAddr::V6(Ipv6Addr::LOCALHOST), port);
+    assert_eq!(status.name, "Query test");
+}
+
+#[test]
+fn queries_are_rate_limited_per_address() {
+    let (mut harness, port) = query_server(&[]);
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let max_queries = harness.server.world().resource::<QueryConfig>().max_queries;
+
+    let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_millis(200)))
+        .unwrap();
+    for _ in 0..max_queries * 4 {
+        socket.send_to(STATUS_QUERY, addr).unwrap();
+    }
+    harness.step();
+
+    let mut buffer = [0; 65_507];
+    let mut answers = 0;
+    while socket.recv_from(&mut buffer).is_ok() {
+        answers += 1;
+    }
+    assert_eq!(answers, max_queries);
+}
+
+#[test]
+fn query_addresses_resolve() {
+    assert_eq!(
+        resolve_query_addr("127.0.0.1").unwrap(),
+        SocketAddr::from((Ipv4Addr::LOCALHOST, 12027))
+    );
+    assert_eq!(
+        resolve_query_addr("127.0.0.1:4000").unwrap(),
+        SocketAddr::from((Ipv4Addr::LOCALHOST, 4000))
+    );
+    assert_eq!(
+        resolve_query_addr("::1").unwrap(),
+        "[::1]:12027".parse::<SocketAddr>().unwrap()
+    );
+    assert_eq!(
+        resolve_query_addr("[::1]:4000").unwrap(),
+        "[::1]:4000".parse::<SocketAddr>().unwrap()
+    );
+}
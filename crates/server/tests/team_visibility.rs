@@ -0,0 +1,121 @@
+//! Connects two headless clients to an in-process server, which puts them on different teams,
+//! and checks that neither ever receives an entity meant only for the other team.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use client::game_state::GameState;
+use harness::TestHarness;
+use lightyear::prelude::ClientId;
+use protocol::component::{LobbyRoster, Player, Team, TeamWaypoint};
+
+const CLIENT_IDS: [u64; 2] = [1, 2];
+
+/// The team the server put this client on, once the lobby has been replicated
+fn own_team(harness: &TestHarness, client_id: u64) -> Option<Team> {
+    harness
+        .client(client_id)
+        .world()
+        .iter_entities()
+        .filter_map(|entity| entity.get::<LobbyRoster>())
+        .find_map(|roster| roster.member(ClientId::Netcode(client_id)))
+        .map(|member| member.team)
+}
+
+fn waypoint_teams(harness: &TestHarness, client_id: u64) -> Vec<Team> {
+    harness
+        .client(client_id)
+        .world()
+        .iter_entities()
+        .filter_map(|entity| entity.get::<TeamWaypoint>())
+        .map(|waypoint| waypoint.team)
+        .collect()
+}
+
+/// Fails the test as soon as a client holds a waypoint that isn't its team's
+fn assert_no_foreign_waypoints(harness: &TestHarness) {
+    for client_id in CLIENT_IDS {
+        let teams = waypoint_teams(harness, client_id);
+        assert!(
+            teams.iter().all(|team| *team == teams[0]),
+            "client {} received waypoints of several teams: {:?}",
+            client_id,
+            teams
+        );
+
+        if let Some(team) = own_team(harness, client_id) {
+            assert!(
+                teams.iter().all(|waypoint_team| *waypoint_team == team),
+                "client {} on {:?} received another team's waypoint: {:?}",
+                client_id,
+                team,
+                teams
+            );
+        }
+    }
+}
+
+#[test]
+fn team_only_entities_stay_within_the_team() {
+    let mut harness = TestHarness::new(&CLIENT_IDS);
+
+    harness.connect_all();
+    harness.run_until("lobby", |harness| {
+        assert_no_foreign_waypoints(harness);
+        CLIENT_IDS.into_iter().all(|client_id| {
+            harness.game_state(client_id) == GameState::Lobby
+                && own_team(harness, client_id).is_some()
+        })
+    });
+
+    let teams: Vec<Team> = CLIENT_IDS
+        .into_iter()
+        .filter_map(|client_id| own_team(&harness, client_id))
+        .collect();
+    assert_ne!(
+        teams[0], teams[1],
+        "clients should be balanced across teams"
+    );
+
+    harness.run_until("team waypoints", |harness| {
+        assert_no_foreign_waypoints(harness);
+        CLIENT_IDS
+            .into_iter()
+            .all(|client_id| waypoint_teams(harness, client_id).len() == 1)
+    });
+
+    for client_id in CLIENT_IDS {
+        harness.set_ready(client_id, true);
+    }
+
+    harness.run_until("match start", |harness| {
+        assert_no_foreign_waypoints(harness);
+        CLIENT_IDS
+            .into_iter()
+            .all(|client_id| harness.game_state(client_id) == GameState::Playing)
+    });
+
+    // Keep running for a while, the waypoints must stay hidden from the other team
+    harness.run_for(Duration::from_secs(1), assert_no_foreign_waypoints);
+
+    // Everyone can see everyone's team on their player
+    for client_id in CLIENT_IDS {
+        let client = harness.client_mut(client_id);
+        let player_teams: Vec<(ClientId, Team)> = client
+            .world_mut()
+            .query::<(&Player, &Team)>()
+            .iter(client.world())
+            .map(|(player, team)| (player.0, *team))
+            .collect();
+
+        for (player_id, team) in CLIENT_IDS.iter().zip(&teams) {
+            assert!(
+                player_teams.contains(&(ClientId::Netcode(*player_id), *team)),
+                "missing team {:?} for client {} in {:?}",
+                team,
+                player_id,
+                player_teams
+            );
+        }
+    }
+}